@@ -32,6 +32,9 @@ fn parse(mut buf: BytesMut) {
         let mut b = isobmff::Object::parse(&mut buf);
 
         eprintln!("0x{:08x?}: {:?}", b.box_type, std::str::from_utf8(&b.box_type.to_be_bytes()).unwrap_or(""));
+        if let Some(usertype) = &b.usertype {
+            eprintln!("\tusertype: {:}", isobmff::uuid::format_usertype(usertype));
+        }
         match b.box_type {
             // ftyp: File Type
             isobmff::ftyp::ftyp::BOX_TYPE => {
@@ -48,6 +51,41 @@ fn parse(mut buf: BytesMut) {
                 let moof = isobmff::moof::parse(&mut b.payload);
                 eprintln!("{:?}", moof);
             }
+            // styp: Segment Type
+            isobmff::styp::styp::BOX_TYPE => {
+                let styp = isobmff::styp::parse(&mut b.payload);
+                eprintln!("{:?}", styp);
+            }
+            // sidx: Segment Index
+            isobmff::sidx::sidx::BOX_TYPE => {
+                let sidx = isobmff::sidx::parse(&mut b.payload);
+                eprintln!("{:?}", sidx);
+            }
+            // prft: Producer Reference Time
+            isobmff::prft::prft::BOX_TYPE => {
+                let prft = isobmff::prft::parse(&mut b.payload);
+                eprintln!("{:?}", prft);
+            }
+            // emsg: Event Message
+            isobmff::emsg::emsg::BOX_TYPE => {
+                let emsg = isobmff::emsg::parse(&mut b.payload);
+                eprintln!("{:?}", emsg);
+            }
+            // mfra: Movie Fragment Random Access
+            isobmff::mfra::mfra::BOX_TYPE => {
+                let mfra = isobmff::mfra::parse(&mut b.payload);
+                eprintln!("{:?}", mfra);
+            }
+            // free: Free Space
+            isobmff::free::free::BOX_TYPE => {
+                let free = isobmff::free::parse(&mut b.payload);
+                eprintln!("{:?}", free);
+            }
+            // skip: Free Space
+            isobmff::free::skip::BOX_TYPE => {
+                let skip = isobmff::free::skip::parse(&mut b.payload);
+                eprintln!("{:?}", skip);
+            }
             // mdat: Media Data
             0x6d646174 => {
             }
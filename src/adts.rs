@@ -0,0 +1,282 @@
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+use crate::bits::BitReader;
+use crate::Error;
+use crate::moov::{moov, trak};
+use crate::segment::Segment;
+
+/// Sampling frequencies of `sampling_frequency_index`.
+const SAMPLING_FREQUENCIES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// The fields of an MPEG-4 AudioSpecificConfig an ADTS header carries.
+///
+/// With explicit SBR or PS signalling, these are of the core AAC stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioSpecificConfig {
+    pub audio_object_type: u8,
+    /// 15 when the frequency is given explicitly by `sampling_frequency`
+    pub sampling_frequency_index: u8,
+    pub sampling_frequency: u32,
+    pub channel_configuration: u8,
+}
+
+impl AudioSpecificConfig {
+    // AudioSpecificConfig() {
+    //     audioObjectType = GetAudioObjectType();
+    //     samplingFrequencyIndex;                 4 bslbf
+    //     if (samplingFrequencyIndex == 0xf) {
+    //         samplingFrequency;                  24 uimsbf
+    //     }
+    //     channelConfiguration;                   4 bslbf
+    //     if (audioObjectType == 5 || audioObjectType == 29) {
+    //         extensionSamplingFrequencyIndex;    4 uimsbf
+    //         if (extensionSamplingFrequencyIndex == 0xf)
+    //             extensionSamplingFrequency;     24 uimsbf
+    //         audioObjectType = GetAudioObjectType();
+    //     }
+    //     ...
+    // }
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        fn get_audio_object_type(r: &mut BitReader) -> Option<u8> {
+            match r.read(5)? {
+                31 => { Some(32 + r.read(6)? as u8) }
+                v => { Some(v as u8) }
+            }
+        }
+        fn get_sampling_frequency(r: &mut BitReader) -> Option<(u8, u32)> {
+            match r.read(4)? as u8 {
+                15 => { Some((15, r.read(24)?)) }
+                v => { Some((v, *SAMPLING_FREQUENCIES.get(v as usize).unwrap_or(&0))) }
+            }
+        }
+
+        let mut r = BitReader::new(data);
+        let mut audio_object_type = get_audio_object_type(&mut r)?;
+        let (sampling_frequency_index, sampling_frequency) = get_sampling_frequency(&mut r)?;
+        let channel_configuration = r.read(4)? as u8;
+
+        if 5 == audio_object_type || 29 == audio_object_type {
+            let _ = get_sampling_frequency(&mut r)?;
+            audio_object_type = get_audio_object_type(&mut r)?;
+        }
+
+        Some(Self {
+            audio_object_type,
+            sampling_frequency_index,
+            sampling_frequency,
+            channel_configuration,
+        })
+    }
+
+    /// Whether an ADTS header can describe the stream: an object type with
+    /// a 2-bit profile, an indexed sampling frequency and a channel
+    /// configuration without a program config element.
+    pub fn is_adts_compatible(&self) -> bool {
+        (1..=4).contains(&self.audio_object_type) &&
+            (self.sampling_frequency_index as usize) < SAMPLING_FREQUENCIES.len() &&
+            (1..=7).contains(&self.channel_configuration)
+    }
+
+    /// ADTS header, without CRC, of a raw access unit of `size` bytes.
+    pub fn adts_header(&self, size: usize) -> [u8; 7] {
+        // adts_fixed_header() {
+        //     syncword;                    12 bslbf
+        //     ID;                          1 bslbf
+        //     layer;                       2 uimsbf
+        //     protection_absent;           1 bslbf
+        //     profile_ObjectType;          2 uimsbf
+        //     sampling_frequency_index;    4 uimsbf
+        //     private_bit;                 1 bslbf
+        //     channel_configuration;       3 uimsbf
+        //     original_copy;               1 bslbf
+        //     home;                        1 bslbf
+        // }
+        // adts_variable_header() {
+        //     copyright_identification_bit;    1 bslbf
+        //     copyright_identification_start;  1 bslbf
+        //     aac_frame_length;                13 bslbf
+        //     adts_buffer_fullness;            11 bslbf
+        //     number_of_raw_data_blocks_in_frame; 2 uimsbf
+        // }
+        let profile = self.audio_object_type.wrapping_sub(1) & 0x03;
+        let length = size + 7;
+
+        [
+            0xff,
+            0xf1,
+            profile << 6 | (self.sampling_frequency_index & 0x0f) << 2 | (self.channel_configuration >> 2 & 0x01),
+            (self.channel_configuration & 0x03) << 6 | (length >> 11 & 0x03) as u8,
+            (length >> 3) as u8,
+            (length as u8 & 0x07) << 5 | 0x1f,
+            0xfc,
+        ]
+    }
+}
+
+/// Writes the samples of the AAC track `trak`, read from the movie in `r`,
+/// to `w` as an ADTS stream.
+pub fn export<R, W>(r: &mut R, trak: &trak, w: &mut W) -> Result<(), Error>
+    where R: Read + Seek, W: Write {
+    let configs = configs(trak)?;
+    let mut buf = vec![];
+
+    for it in trak.mdia.minf.stbl.samples() {
+        let Some(Some(config)) = configs.get((it.description_index as usize).wrapping_sub(1)) else {
+            return Err(Error::UnsupportedCodec { track_id: trak.tkhd.track_id });
+        };
+
+        buf.resize(it.size as usize, 0);
+        r.seek(SeekFrom::Start(it.offset))?;
+        r.read_exact(&mut buf)?;
+        write_frame(config, &buf, w)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the samples of the AAC track `track_id` of `moov`, carried by the
+/// movie fragments of `segment` read from file offset `offset`, to `w` as
+/// an ADTS stream.
+///
+/// Sample data is located as by `Segment::samples`.
+pub fn export_fragments<W: Write>(moov: &moov, segment: &Segment, offset: u64, track_id: u32, w: &mut W) -> Result<(), Error> {
+    let Some(trak) = moov.traks.iter().find(|it| track_id == it.tkhd.track_id) else {
+        return Err(Error::MissingTrack { track_id });
+    };
+    let trexs = moov.mvex.as_ref().map_or(&[][..], |it| &it.trexs[..]);
+    let configs = configs(trak)?;
+    let samples = segment.samples(offset, track_id, trexs).ok_or(Error::Io(ErrorKind::UnexpectedEof))?;
+
+    for (description_index, it) in samples {
+        let Some(Some(config)) = configs.get((description_index as usize).wrapping_sub(1)) else {
+            return Err(Error::UnsupportedCodec { track_id });
+        };
+
+        write_frame(config, it, w)?;
+    }
+
+    Ok(())
+}
+
+/// AudioSpecificConfig of every sample entry of `trak`, failing unless one
+/// of them is `mp4a`, or when one cannot be described by ADTS.
+fn configs(trak: &trak) -> Result<Vec<Option<AudioSpecificConfig>>, Error> {
+    let track_id = trak.tkhd.track_id;
+    let configs = trak.mdia.minf.stbl.stsd.entries.iter()
+        .map(|it| it.decoder_specific_info().and_then(|it| AudioSpecificConfig::parse(&it)))
+        .collect::<Vec<_>>();
+
+    if configs.iter().all(|it| it.is_none()) {
+        return Err(Error::UnsupportedCodec { track_id });
+    }
+    if configs.iter().flatten().any(|it| !it.is_adts_compatible()) {
+        return Err(Error::UnsupportedAudioConfig { track_id });
+    }
+
+    Ok(configs)
+}
+
+fn write_frame<W: Write>(config: &AudioSpecificConfig, sample: &[u8], w: &mut W) -> Result<(), Error> {
+    // aac_frame_length has 13 bits
+    if 0x1fff < sample.len() + 7 {
+        return Err(Error::Io(ErrorKind::InvalidData));
+    }
+
+    w.write_all(&config.adts_header(sample.len()))?;
+    w.write_all(sample)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bytes::{BufMut, BytesMut};
+
+    use crate::{IO, Object};
+    use crate::adts::{AudioSpecificConfig, export};
+    use crate::Error;
+    use crate::moov::{hdlr, Sample, SampleEntry, SoundDescriptionVersion, trak};
+    use crate::types::types;
+
+    #[test]
+    fn chk_adts() {
+        // AAC LC, 44100 Hz, stereo
+        assert_eq!(Some(AudioSpecificConfig {
+            audio_object_type: 2,
+            sampling_frequency_index: 4,
+            sampling_frequency: 44100,
+            channel_configuration: 2,
+        }), AudioSpecificConfig::parse(&[0x12, 0x10]));
+        // HE-AAC with explicit SBR signalling, over AAC LC at 22050 Hz
+        assert_eq!(Some(AudioSpecificConfig {
+            audio_object_type: 2,
+            sampling_frequency_index: 7,
+            sampling_frequency: 22050,
+            channel_configuration: 2,
+        }), AudioSpecificConfig::parse(&[0x2b, 0x91, 0x88]));
+
+        let source = [1, 2, 3, 4, 5, 6];
+        for (audio_specific_config, expected) in [
+            // AAC LC, 44100 Hz, stereo
+            (&[0x12, 0x10][..], Ok(vec![
+                0xff, 0xf1, 0x50, 0x80, 0x01, 0x5f, 0xfc, 1, 2, 3,
+                0xff, 0xf1, 0x50, 0x80, 0x01, 0x5f, 0xfc, 4, 5, 6,
+            ])),
+            // AAC LC with an explicit sampling frequency of 44100 Hz
+            (&[0x17, 0x80, 0x56, 0x22, 0x10], Err(Error::UnsupportedAudioConfig { track_id: 1 })),
+            // AAC LC with a program config element
+            (&[0x12, 0x00], Err(Error::UnsupportedAudioConfig { track_id: 1 })),
+        ] {
+            let mut esds = BytesMut::new();
+            esds.put_u32(0);
+            // ES_Descriptor
+            esds.put_slice(&[0x03, 23 + audio_specific_config.len() as u8, 0, 1, 0]);
+            // DecoderConfigDescriptor
+            esds.put_slice(&[0x04, 15 + audio_specific_config.len() as u8, 0x40, 0x15, 0, 0, 0]);
+            esds.put_u32(0);
+            esds.put_u32(0);
+            // DecoderSpecificInfo
+            esds.put_slice(&[0x05, audio_specific_config.len() as u8]);
+            esds.put_slice(audio_specific_config);
+            // SLConfigDescriptor
+            esds.put_slice(&[0x06, 1, 2]);
+
+            let mut v = trak::default();
+            v.tkhd.track_id = 1;
+            v.mdia.hdlr = hdlr::soun("Audio");
+            v.mdia.minf.stbl.stsd.entries.push(SampleEntry::mp4a {
+                base: Box::new(SampleEntry::Audio {
+                    base: Box::new(SampleEntry::Base {
+                        handler_type: types::mp4a,
+                        data_reference_index: 1,
+                    }),
+                    version: SoundDescriptionVersion::V0,
+                    revision_level: 0,
+                    vendor: 0,
+                    channel_count: 2,
+                    sample_size: 16,
+                    compression_id: 0,
+                    packet_size: 0,
+                    sample_rate: 44100 << 16,
+                }),
+                ext: Object::new(types::esds, esds).as_bytes(),
+            });
+            v.mdia.minf.stbl.set_samples(&(0..2).map(|i| Sample {
+                offset: 3 * i,
+                size: 3,
+                decode_time: 1024 * i,
+                duration: 1024,
+                composition_offset: 0,
+                is_sync: true,
+                description_index: 1,
+            }).collect::<Vec<_>>());
+
+            let mut w = vec![];
+            assert_eq!(expected, export(&mut Cursor::new(&source), &v, &mut w).map(|_| w));
+        }
+    }
+}
@@ -0,0 +1,1096 @@
+use std::collections::HashMap;
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::{Error, IO, Object};
+use crate::bits::BitReader;
+use crate::ftyp::ftyp;
+use crate::moov::{avcC, hdlr, hvcC, MediaInformationHeader, moov, Sample, SampleEntry, trak, vmhd};
+use crate::remux;
+use crate::segment::Segment;
+use crate::types::types;
+
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// Writes the samples of the H.264 track `trak`, read from the movie in
+/// `r`, to `w` as an Annex-B byte stream.
+///
+/// Each access unit starting an IDR picture is preceded by the parameter
+/// sets of its `avcC`, unless it already carries them.
+pub fn export<R, W>(r: &mut R, trak: &trak, w: &mut W) -> Result<(), Error>
+    where R: Read + Seek, W: Write {
+    let configs = configs(trak)?;
+    let mut buf = vec![];
+
+    for it in trak.mdia.minf.stbl.samples() {
+        let Some(Some(config)) = configs.get((it.description_index as usize).wrapping_sub(1)) else {
+            return Err(Error::UnsupportedCodec { track_id: trak.tkhd.track_id });
+        };
+
+        buf.resize(it.size as usize, 0);
+        r.seek(SeekFrom::Start(it.offset))?;
+        r.read_exact(&mut buf)?;
+        write_access_unit(config, &buf, w)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the samples of the H.264 track `track_id` of `moov`, carried by
+/// the movie fragments of `segment` read from file offset `offset`, to `w`
+/// as an Annex-B byte stream.
+///
+/// Sample data is located as by `Segment::samples`.
+pub fn export_fragments<W: Write>(moov: &moov, segment: &Segment, offset: u64, track_id: u32, w: &mut W) -> Result<(), Error> {
+    let Some(trak) = moov.traks.iter().find(|it| track_id == it.tkhd.track_id) else {
+        return Err(Error::MissingTrack { track_id });
+    };
+    let trexs = moov.mvex.as_ref().map_or(&[][..], |it| &it.trexs[..]);
+    let configs = configs(trak)?;
+
+    let samples = segment.samples(offset, track_id, trexs).ok_or(Error::Io(ErrorKind::UnexpectedEof))?;
+
+    for (description_index, it) in samples {
+        let Some(Some(config)) = configs.get((description_index as usize).wrapping_sub(1)) else {
+            return Err(Error::UnsupportedCodec { track_id });
+        };
+
+        write_access_unit(config, it, w)?;
+    }
+
+    Ok(())
+}
+
+/// Decoder configuration of every sample entry of `trak`, failing unless
+/// one of them is `avc1`.
+fn configs(trak: &trak) -> Result<Vec<Option<avcC>>, Error> {
+    let configs = trak.mdia.minf.stbl.stsd.entries.iter().map(|it| it.avcC()).collect::<Vec<_>>();
+
+    if configs.iter().all(|it| it.is_none()) {
+        return Err(Error::UnsupportedCodec { track_id: trak.tkhd.track_id });
+    }
+
+    Ok(configs)
+}
+
+/// Converts the length-prefixed NAL units of one sample to start code
+/// prefixed ones.
+fn write_access_unit<W: Write>(config: &avcC, mut sample: &[u8], w: &mut W) -> Result<(), Error> {
+    let length_size = config.nal_length_size();
+    let mut has_parameter_sets = false;
+
+    while !sample.is_empty() {
+        if sample.len() < length_size {
+            return Err(Error::Io(ErrorKind::InvalidData));
+        }
+        let size = sample[..length_size].iter().fold(0, |v, it| v << 8 | *it as usize);
+        sample = &sample[length_size..];
+        if sample.len() < size {
+            return Err(Error::Io(ErrorKind::InvalidData));
+        }
+        let (nal, rest) = sample.split_at(size);
+        sample = rest;
+
+        match nal.first().map(|it| it & 0x1f) {
+            // Sequence parameter set
+            Some(7) => {
+                has_parameter_sets = true;
+            }
+            // Coded slice of an IDR picture
+            Some(5) if !has_parameter_sets => {
+                for it in config.sequence_parameter_sets.iter().chain(&config.picture_parameter_sets) {
+                    w.write_all(&START_CODE)?;
+                    w.write_all(it)?;
+                }
+                has_parameter_sets = true;
+            }
+            _ => {}
+        }
+
+        w.write_all(&START_CODE)?;
+        w.write_all(nal)?;
+    }
+
+    Ok(())
+}
+
+/// Video coding of an Annex-B byte stream.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Codec {
+    H264,
+    H265,
+}
+
+impl Codec {
+    fn nal_unit_type(self, nal: &[u8]) -> u8 {
+        match self {
+            Codec::H264 => { nal[0] & 0x1f }
+            Codec::H265 => { nal[0] >> 1 & 0x3f }
+        }
+    }
+
+    fn is_vcl(self, nal_unit_type: u8) -> bool {
+        match self {
+            Codec::H264 => { (1..=5).contains(&nal_unit_type) }
+            Codec::H265 => { nal_unit_type < 32 }
+        }
+    }
+
+    /// Whether the slice of a VCL NAL unit is the first of its picture,
+    /// from `first_mb_in_slice` or `first_slice_segment_in_pic_flag`.
+    fn is_first_slice(self, nal: &[u8]) -> bool {
+        match self {
+            Codec::H264 => { nal.get(1).is_some_and(|it| 0 != it & 0x80) }
+            Codec::H265 => { nal.get(2).is_some_and(|it| 0 != it & 0x80) }
+        }
+    }
+
+    /// Whether a non-VCL NAL unit following a picture starts the next
+    /// access unit.
+    fn starts_access_unit(self, nal_unit_type: u8) -> bool {
+        match self {
+            Codec::H264 => { matches!(nal_unit_type, 6..=9 | 14..=18) }
+            Codec::H265 => { matches!(nal_unit_type, 32..=35 | 39 | 41..=44 | 48..=55) }
+        }
+    }
+
+    fn is_parameter_set(self, nal_unit_type: u8) -> bool {
+        match self {
+            Codec::H264 => { matches!(nal_unit_type, 7 | 8) }
+            Codec::H265 => { matches!(nal_unit_type, 32..=34) }
+        }
+    }
+
+    fn is_access_unit_delimiter(self, nal_unit_type: u8) -> bool {
+        match self {
+            Codec::H264 => { 9 == nal_unit_type }
+            Codec::H265 => { 35 == nal_unit_type }
+        }
+    }
+
+    /// Whether a VCL NAL unit is of a random access picture: IDR in H.264,
+    /// and BLA, IDR or CRA in H.265.
+    fn is_sync(self, nal_unit_type: u8) -> bool {
+        match self {
+            Codec::H264 => { 5 == nal_unit_type }
+            Codec::H265 => { (16..=21).contains(&nal_unit_type) }
+        }
+    }
+}
+
+/// Writes the Annex-B byte stream `stream` to `w` as a movie with a single
+/// video track, whose frames last `frame_duration` in `timescale`.
+///
+/// Parameter sets are moved from the stream to the `avcC` or `hvcC` of the
+/// sample entry, access unit delimiters are dropped, and random access
+/// pictures are sync samples. Frames are presented in picture order count
+/// order, with composition offsets relative to decoding order.
+/// Returns the `moov` of the new file.
+pub fn import<W: Write>(stream: &[u8], codec: Codec, timescale: u32, frame_duration: u32, w: &mut W) -> Result<moov, Error> {
+    let invalid = || Error::Io(ErrorKind::InvalidData);
+    let mut parameter_sets: Vec<(u8, &[u8])> = vec![];
+    let mut picture_order = PictureOrder::default();
+    let mut data = vec![];
+    let mut samples = vec![];
+    // coded video sequence and picture order count of each sample
+    let mut orders = vec![];
+    let mut start = 0;
+    let mut has_vcl = false;
+    let mut is_sync = false;
+    let mut order = None;
+    let mut sequence = 0;
+
+    for nal in nal_units(stream).chain([&[][..]]) {
+        let nal_unit_type = if nal.len() < 2 { None } else { Some(codec.nal_unit_type(nal)) };
+        let is_vcl = nal_unit_type.is_some_and(|it| codec.is_vcl(it));
+
+        // the empty NAL unit chained after the stream ends the last one
+        let ends_access_unit = match nal_unit_type {
+            None => { nal.is_empty() }
+            Some(_) if is_vcl => { codec.is_first_slice(nal) }
+            Some(v) => { codec.starts_access_unit(v) }
+        };
+
+        if has_vcl && ends_access_unit {
+            orders.push(order.take().ok_or_else(invalid)?);
+            samples.push(Sample {
+                offset: start as u64,
+                size: (data.len() - start) as u32,
+                decode_time: samples.len() as u64 * frame_duration as u64,
+                duration: frame_duration,
+                composition_offset: 0,
+                is_sync,
+                description_index: 1,
+            });
+            start = data.len();
+            has_vcl = false;
+            is_sync = false;
+        }
+
+        let Some(nal_unit_type) = nal_unit_type else { continue };
+        if codec.is_parameter_set(nal_unit_type) {
+            picture_order.insert(codec, nal_unit_type, nal).ok_or_else(invalid)?;
+            if !parameter_sets.contains(&(nal_unit_type, nal)) {
+                parameter_sets.push((nal_unit_type, nal));
+            }
+            continue;
+        }
+        if codec.is_access_unit_delimiter(nal_unit_type) {
+            continue;
+        }
+
+        if is_vcl && codec.is_first_slice(nal) {
+            let (is_new_sequence, pic_order_cnt) = picture_order.pic_order_cnt(codec, nal)?;
+            sequence += is_new_sequence as usize;
+            order = Some((sequence, pic_order_cnt));
+        }
+        data.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+        data.extend_from_slice(nal);
+        has_vcl |= is_vcl;
+        is_sync |= is_vcl && codec.is_sync(nal_unit_type);
+    }
+    data.truncate(start);
+    if samples.is_empty() {
+        return Err(Error::NoInput);
+    }
+
+    let mut presentation = (0..samples.len()).collect::<Vec<_>>();
+    presentation.sort_by_key(|it| orders[*it]);
+    for (i, it) in presentation.into_iter().enumerate() {
+        let offset = (i as i64 - it as i64) * frame_duration as i64;
+        samples[it].composition_offset = i32::try_from(offset).map_err(|_| invalid())?;
+    }
+
+    let parameter_sets = |v: u8| parameter_sets.iter()
+        .filter(|(t, _)| v == *t)
+        .map(|(_, it)| BytesMut::from(*it))
+        .collect::<Vec<_>>();
+    let missing = |nal_unit_type: u8| Error::MissingParameterSet { nal_unit_type };
+
+    let (format, sps, mut config) = match codec {
+        Codec::H264 => {
+            let sequence_parameter_sets = parameter_sets(7);
+            let picture_parameter_sets = parameter_sets(8);
+            let sps = sequence_parameter_sets.first().ok_or_else(|| missing(7)).and_then(|it| avc_sps(it).ok_or_else(invalid))?;
+            if picture_parameter_sets.is_empty() {
+                return Err(missing(8));
+            }
+
+            let nal = &sequence_parameter_sets[0];
+            let mut ext = BytesMut::new();
+            if matches!(nal[1], 100 | 110 | 122 | 144) {
+                ext.put_u8(0xfc | sps.chroma_format_idc);
+                ext.put_u8(0xf8 | sps.bit_depth_luma_minus8);
+                ext.put_u8(0xf8 | sps.bit_depth_chroma_minus8);
+                ext.put_u8(0);
+            }
+            let mut v = avcC {
+                profile_indication: nal[1],
+                profile_compatibility: nal[2],
+                level_indication: nal[3],
+                sequence_parameter_sets,
+                picture_parameter_sets,
+                ext,
+                ..Default::default()
+            };
+
+            (types::avc1, sps, Object::new(avcC::BOX_TYPE, v.as_bytes()))
+        }
+        Codec::H265 => {
+            let video_parameter_sets = parameter_sets(32);
+            let sequence_parameter_sets = parameter_sets(33);
+            let picture_parameter_sets = parameter_sets(34);
+            let (sps, ptl) = sequence_parameter_sets.first().ok_or_else(|| missing(33)).and_then(|it| hevc_sps(it).ok_or_else(invalid))?;
+            if video_parameter_sets.is_empty() {
+                return Err(missing(32));
+            }
+            if picture_parameter_sets.is_empty() {
+                return Err(missing(34));
+            }
+
+            let mut v = hvcC {
+                general_profile: ptl.general_profile,
+                general_profile_compatibility_flags: ptl.general_profile_compatibility_flags,
+                general_constraint_indicator_flags: ptl.general_constraint_indicator_flags,
+                general_level_idc: ptl.general_level_idc,
+                chroma_format: sps.chroma_format_idc,
+                bit_depth_luma_minus8: sps.bit_depth_luma_minus8,
+                bit_depth_chroma_minus8: sps.bit_depth_chroma_minus8,
+                avg_frame_rate: (256 * timescale as u64 / frame_duration.max(1) as u64).min(u16::MAX as u64) as u16,
+                frame_rate_flags: ptl.temporal_layers << 1 | ptl.temporal_id_nested as u8,
+                arrays: vec![
+                    (true, 32, video_parameter_sets),
+                    (true, 33, sequence_parameter_sets),
+                    (true, 34, picture_parameter_sets),
+                ],
+                ..Default::default()
+            };
+
+            (types::hvc1, sps, Object::new(hvcC::BOX_TYPE, v.as_bytes()))
+        }
+    };
+
+    let visual = SampleEntry::Visual {
+        base: Box::new(SampleEntry::Base {
+            handler_type: format,
+            data_reference_index: 1,
+        }),
+        width: sps.width as u16,
+        height: sps.height as u16,
+        horiz_resolution: 0x00480000,
+        vert_resolution: 0x00480000,
+        frame_count: 1,
+        compressor_name: "".to_owned(),
+        depth: 24,
+    };
+    let entry = match codec {
+        Codec::H264 => {
+            SampleEntry::avc1 {
+                base: Box::new(visual),
+                ext: config.as_bytes(),
+            }
+        }
+        Codec::H265 => {
+            SampleEntry::hvc1 {
+                base: Box::new(visual),
+                ext: config.as_bytes(),
+            }
+        }
+    };
+
+    let duration = samples.len() as u64 * frame_duration as u64;
+    let mut trak = trak::default();
+    trak.tkhd.track_id = 1;
+    trak.tkhd.duration = duration;
+    trak.tkhd.width = sps.width << 16;
+    trak.tkhd.height = sps.height << 16;
+    trak.mdia.mdhd.timescale = timescale;
+    trak.mdia.mdhd.duration = duration;
+    trak.mdia.hdlr = hdlr::vide("Video");
+    trak.mdia.minf.mhd = MediaInformationHeader::vmhd(vmhd::new(0, 0, 0, 0));
+    trak.mdia.minf.stbl.stsd.entries.push(entry);
+
+    let mut moov = moov::default();
+    moov.mvhd.timescale = timescale;
+    moov.mvhd.duration = duration;
+    moov.traks.push(trak);
+
+    let ftyp = ftyp {
+        major_brand: types::isom,
+        minor_version: 512,
+        compatible_brands: match codec {
+            Codec::H264 => { vec![types::isom, types::iso2, types::avc1, types::mp41] }
+            Codec::H265 => { vec![types::isom, types::iso2, types::mp41] }
+        },
+    };
+    let samples = vec![samples.into_iter().map(|it| (0, it)).collect()];
+
+    remux::write(&mut [Cursor::new(data)], &ftyp, moov, samples, w)
+}
+
+/// Splits an Annex-B byte stream at its start codes, dropping the trailing
+/// zero bytes of each NAL unit.
+fn nal_units(stream: &[u8]) -> impl Iterator<Item=&[u8]> {
+    let mut starts = vec![];
+    let mut i = 0;
+    while i + 3 <= stream.len() {
+        if 0 == stream[i] && 0 == stream[i + 1] && 1 == stream[i + 2] {
+            starts.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    let ends = starts.iter().skip(1).map(|it| it - 3).chain([stream.len()]).collect::<Vec<_>>();
+
+    starts.into_iter().zip(ends).map(|(start, end)| {
+        let mut end = end;
+        while start < end && 0 == stream[end - 1] {
+            end -= 1;
+        }
+
+        &stream[start..end]
+    })
+}
+
+/// Payload of a NAL unit without its header of `header_size` bytes, with
+/// emulation prevention bytes removed.
+fn rbsp(nal: &[u8], header_size: usize) -> Vec<u8> {
+    let mut rst = Vec::with_capacity(nal.len());
+    let mut zeros = 0;
+
+    for it in nal.iter().skip(header_size) {
+        if 2 <= zeros && 3 == *it {
+            zeros = 0;
+            continue;
+        }
+        zeros = if 0 == *it { zeros + 1 } else { 0 };
+        rst.push(*it);
+    }
+
+    rst
+}
+
+/// Fields of a sequence parameter set the sample entry and the picture
+/// order count need. The frame number and POC type 1 fields are of H.264.
+#[derive(Clone, Default)]
+struct SequenceParameterSet {
+    seq_parameter_set_id: u32,
+    width: u32,
+    height: u32,
+    chroma_format_idc: u8,
+    bit_depth_luma_minus8: u8,
+    bit_depth_chroma_minus8: u8,
+    separate_colour_plane_flag: bool,
+    frame_mbs_only_flag: bool,
+    log2_max_frame_num: u32,
+    pic_order_cnt_type: u32,
+    log2_max_pic_order_cnt_lsb: u32,
+    delta_pic_order_always_zero_flag: bool,
+    offset_for_non_ref_pic: i32,
+    offset_for_top_to_bottom_field: i32,
+    offset_for_ref_frame: Vec<i32>,
+}
+
+/// Fields of a picture parameter set needed to reach the picture order
+/// count in a slice header. The bottom field flag is of H.264, the others
+/// of H.265.
+#[derive(Clone, Default)]
+struct PictureParameterSet {
+    seq_parameter_set_id: u32,
+    bottom_field_pic_order_in_frame_present_flag: bool,
+    output_flag_present_flag: bool,
+    num_extra_slice_header_bits: u32,
+}
+
+/// Parameter sets seen so far in a stream, and the state of the picture
+/// order count decoding process (8.2.1 of H.264, 8.3.1 of H.265).
+#[derive(Default)]
+struct PictureOrder {
+    sequence_parameter_sets: HashMap<u32, SequenceParameterSet>,
+    picture_parameter_sets: HashMap<u32, PictureParameterSet>,
+    prev_pic_order_cnt_msb: i64,
+    prev_pic_order_cnt_lsb: i64,
+    prev_frame_num_offset: i64,
+    prev_frame_num: i64,
+    has_picture: bool,
+}
+
+impl PictureOrder {
+    /// Keeps the sequence or picture parameter set `nal`, or `None` when it
+    /// cannot be read.
+    fn insert(&mut self, codec: Codec, nal_unit_type: u8, nal: &[u8]) -> Option<()> {
+        match (codec, nal_unit_type) {
+            (Codec::H264, 7) => {
+                let v = avc_sps(nal)?;
+                self.sequence_parameter_sets.insert(v.seq_parameter_set_id, v);
+            }
+            (Codec::H265, 33) => {
+                let (v, _) = hevc_sps(nal)?;
+                self.sequence_parameter_sets.insert(v.seq_parameter_set_id, v);
+            }
+            (Codec::H264, 8) | (Codec::H265, 34) => {
+                let (id, v) = pps(codec, nal)?;
+                self.picture_parameter_sets.insert(id, v);
+            }
+            _ => {}
+        }
+
+        Some(())
+    }
+
+    /// Whether the first slice `nal` of a picture starts a coded video
+    /// sequence, and its picture order count.
+    fn pic_order_cnt(&mut self, codec: Codec, nal: &[u8]) -> Result<(bool, i64), Error> {
+        let nal_unit_type = codec.nal_unit_type(nal);
+        let rbsp = rbsp(nal, match codec { Codec::H264 => { 1 } Codec::H265 => { 2 } });
+        let mut r = BitReader::new(&rbsp);
+        let invalid = || Error::Io(ErrorKind::InvalidData);
+
+        let (pps, sps) = {
+            let pic_parameter_set_id = match codec {
+                Codec::H264 => {
+                    let _first_mb_in_slice = r.read_ue().ok_or_else(invalid)?;
+                    let _slice_type = r.read_ue().ok_or_else(invalid)?;
+                    r.read_ue().ok_or_else(invalid)?
+                }
+                Codec::H265 => {
+                    let _first_slice_segment_in_pic_flag = r.read(1).ok_or_else(invalid)?;
+                    if (16..=23).contains(&nal_unit_type) {
+                        let _no_output_of_prior_pics_flag = r.read(1).ok_or_else(invalid)?;
+                    }
+                    r.read_ue().ok_or_else(invalid)?
+                }
+            };
+            let (sps_type, pps_type) = match codec {
+                Codec::H264 => { (7, 8) }
+                Codec::H265 => { (33, 34) }
+            };
+            let pps = self.picture_parameter_sets.get(&pic_parameter_set_id)
+                .ok_or(Error::MissingParameterSet { nal_unit_type: pps_type })?;
+            let sps = self.sequence_parameter_sets.get(&pps.seq_parameter_set_id)
+                .ok_or(Error::MissingParameterSet { nal_unit_type: sps_type })?;
+
+            (pps.clone(), sps.clone())
+        };
+
+        let rst = match codec {
+            Codec::H264 => { self.avc_pic_order_cnt(nal, &sps, &pps, &mut r) }
+            Codec::H265 => { self.hevc_pic_order_cnt(nal, &sps, &pps, &mut r) }
+        };
+        self.has_picture = true;
+
+        rst.ok_or_else(invalid)
+    }
+
+    fn avc_pic_order_cnt(&mut self, nal: &[u8], sps: &SequenceParameterSet, pps: &PictureParameterSet, r: &mut BitReader) -> Option<(bool, i64)> {
+        let nal_ref_idc = nal[0] >> 5 & 0x03;
+        let is_idr = 5 == nal[0] & 0x1f;
+
+        if sps.separate_colour_plane_flag {
+            let _colour_plane_id = r.read(2)?;
+        }
+        let frame_num = r.read(sps.log2_max_frame_num as usize)? as i64;
+        let mut field_pic_flag = false;
+        if !sps.frame_mbs_only_flag {
+            field_pic_flag = 1 == r.read(1)?;
+            if field_pic_flag {
+                let _bottom_field_flag = r.read(1)?;
+            }
+        }
+        if is_idr {
+            let _idr_pic_id = r.read_ue()?;
+        }
+
+        let frame_num_offset = if is_idr {
+            0
+        } else if frame_num < self.prev_frame_num {
+            self.prev_frame_num_offset + (1 << sps.log2_max_frame_num)
+        } else {
+            self.prev_frame_num_offset
+        };
+        self.prev_frame_num = frame_num;
+        self.prev_frame_num_offset = frame_num_offset;
+
+        let pic_order_cnt = match sps.pic_order_cnt_type {
+            0 => {
+                let pic_order_cnt_lsb = r.read(sps.log2_max_pic_order_cnt_lsb as usize)? as i64;
+                let mut delta_pic_order_cnt_bottom = 0;
+                if pps.bottom_field_pic_order_in_frame_present_flag && !field_pic_flag {
+                    delta_pic_order_cnt_bottom = r.read_se()? as i64;
+                }
+
+                if is_idr {
+                    (self.prev_pic_order_cnt_msb, self.prev_pic_order_cnt_lsb) = (0, 0);
+                }
+                let pic_order_cnt_msb = self.pic_order_cnt_msb(pic_order_cnt_lsb, sps.log2_max_pic_order_cnt_lsb);
+                if 0 != nal_ref_idc {
+                    (self.prev_pic_order_cnt_msb, self.prev_pic_order_cnt_lsb) = (pic_order_cnt_msb, pic_order_cnt_lsb);
+                }
+
+                let top_field_order_cnt = pic_order_cnt_msb + pic_order_cnt_lsb;
+                top_field_order_cnt.min(top_field_order_cnt + delta_pic_order_cnt_bottom)
+            }
+            1 => {
+                let mut delta_pic_order_cnt = [0, 0];
+                if !sps.delta_pic_order_always_zero_flag {
+                    delta_pic_order_cnt[0] = r.read_se()? as i64;
+                    if pps.bottom_field_pic_order_in_frame_present_flag && !field_pic_flag {
+                        delta_pic_order_cnt[1] = r.read_se()? as i64;
+                    }
+                }
+
+                let cycle = sps.offset_for_ref_frame.len() as i64;
+                let mut abs_frame_num = if 0 != cycle { frame_num_offset + frame_num } else { 0 };
+                if 0 == nal_ref_idc && 0 < abs_frame_num {
+                    abs_frame_num -= 1;
+                }
+                let mut expected_pic_order_cnt = 0;
+                if 0 < abs_frame_num {
+                    let pic_order_cnt_cycle_cnt = (abs_frame_num - 1) / cycle;
+                    let frame_num_in_pic_order_cnt_cycle = (abs_frame_num - 1) % cycle;
+                    let expected_delta_per_pic_order_cnt_cycle = sps.offset_for_ref_frame.iter().map(|it| *it as i64).sum::<i64>();
+
+                    expected_pic_order_cnt = pic_order_cnt_cycle_cnt * expected_delta_per_pic_order_cnt_cycle +
+                        sps.offset_for_ref_frame[..=frame_num_in_pic_order_cnt_cycle as usize].iter().map(|it| *it as i64).sum::<i64>();
+                }
+                if 0 == nal_ref_idc {
+                    expected_pic_order_cnt += sps.offset_for_non_ref_pic as i64;
+                }
+
+                let top_field_order_cnt = expected_pic_order_cnt + delta_pic_order_cnt[0];
+                top_field_order_cnt.min(top_field_order_cnt + sps.offset_for_top_to_bottom_field as i64 + delta_pic_order_cnt[1])
+            }
+            2 => {
+                match (is_idr, nal_ref_idc) {
+                    (true, _) => { 0 }
+                    (false, 0) => { 2 * (frame_num_offset + frame_num) - 1 }
+                    (false, _) => { 2 * (frame_num_offset + frame_num) }
+                }
+            }
+            _ => { return None; }
+        };
+
+        Some((is_idr, pic_order_cnt))
+    }
+
+    fn hevc_pic_order_cnt(&mut self, nal: &[u8], sps: &SequenceParameterSet, pps: &PictureParameterSet, r: &mut BitReader) -> Option<(bool, i64)> {
+        let nal_unit_type = nal[0] >> 1 & 0x3f;
+        let temporal_id = (nal[1] & 0x07).saturating_sub(1);
+        let is_idr = matches!(nal_unit_type, 19 | 20);
+        // BLA and IDR pictures, and a CRA picture first in the stream
+        let is_new_sequence = matches!(nal_unit_type, 16..=20) || (21 == nal_unit_type && !self.has_picture);
+
+        r.skip(pps.num_extra_slice_header_bits as usize)?;
+        let _slice_type = r.read_ue()?;
+        if pps.output_flag_present_flag {
+            let _pic_output_flag = r.read(1)?;
+        }
+        if sps.separate_colour_plane_flag {
+            let _colour_plane_id = r.read(2)?;
+        }
+        let pic_order_cnt_lsb = if is_idr { 0 } else { r.read(sps.log2_max_pic_order_cnt_lsb as usize)? as i64 };
+
+        let pic_order_cnt_msb = if is_new_sequence {
+            0
+        } else {
+            self.pic_order_cnt_msb(pic_order_cnt_lsb, sps.log2_max_pic_order_cnt_lsb)
+        };
+        // prevTid0Pic: neither RADL, RASL nor a sub-layer non-reference picture
+        if 0 == temporal_id && !matches!(nal_unit_type, 6..=9) && !(nal_unit_type <= 14 && 0 == nal_unit_type & 0x01) {
+            (self.prev_pic_order_cnt_msb, self.prev_pic_order_cnt_lsb) = (pic_order_cnt_msb, pic_order_cnt_lsb);
+        }
+
+        Some((is_new_sequence, pic_order_cnt_msb + pic_order_cnt_lsb))
+    }
+
+    fn pic_order_cnt_msb(&self, pic_order_cnt_lsb: i64, log2_max_pic_order_cnt_lsb: u32) -> i64 {
+        let max_pic_order_cnt_lsb = 1 << log2_max_pic_order_cnt_lsb;
+        let (prev_msb, prev_lsb) = (self.prev_pic_order_cnt_msb, self.prev_pic_order_cnt_lsb);
+
+        if pic_order_cnt_lsb < prev_lsb && max_pic_order_cnt_lsb / 2 <= prev_lsb - pic_order_cnt_lsb {
+            prev_msb + max_pic_order_cnt_lsb
+        } else if prev_lsb < pic_order_cnt_lsb && max_pic_order_cnt_lsb / 2 < pic_order_cnt_lsb - prev_lsb {
+            prev_msb - max_pic_order_cnt_lsb
+        } else {
+            prev_msb
+        }
+    }
+}
+
+/// General profile, tier and level of an H.265 sequence parameter set.
+struct ProfileTierLevel {
+    general_profile: u8,
+    general_profile_compatibility_flags: u32,
+    general_constraint_indicator_flags: u64,
+    general_level_idc: u8,
+    temporal_layers: u8,
+    temporal_id_nested: bool,
+}
+
+fn avc_sps(nal: &[u8]) -> Option<SequenceParameterSet> {
+    let rbsp = rbsp(nal, 1);
+    let mut r = BitReader::new(&rbsp);
+
+    let profile_idc = r.read(8)?;
+    // constraint_set flags and level_idc
+    r.skip(16)?;
+    let seq_parameter_set_id = r.read_ue()?;
+
+    let mut chroma_format_idc = 1;
+    let mut separate_colour_plane_flag = false;
+    let mut bit_depth_luma_minus8 = 0;
+    let mut bit_depth_chroma_minus8 = 0;
+    if matches!(profile_idc, 100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135) {
+        chroma_format_idc = r.read_ue()?;
+        if 3 == chroma_format_idc {
+            separate_colour_plane_flag = 1 == r.read(1)?;
+        }
+        bit_depth_luma_minus8 = r.read_ue()?;
+        bit_depth_chroma_minus8 = r.read_ue()?;
+        let _qpprime_y_zero_transform_bypass_flag = r.read(1)?;
+        if 1 == r.read(1)? {
+            for i in 0..if 3 != chroma_format_idc { 8 } else { 12 } {
+                if 1 == r.read(1)? {
+                    let (mut last_scale, mut next_scale) = (8, 8);
+                    for _ in 0..if i < 6 { 16 } else { 64 } {
+                        if 0 != next_scale {
+                            next_scale = (last_scale + r.read_se()? + 256) % 256;
+                        }
+                        if 0 != next_scale {
+                            last_scale = next_scale;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut sps = SequenceParameterSet {
+        seq_parameter_set_id,
+        log2_max_frame_num: r.read_ue()?.checked_add(4).filter(|it| *it <= 16)?,
+        pic_order_cnt_type: r.read_ue()?,
+        ..Default::default()
+    };
+    match sps.pic_order_cnt_type {
+        0 => {
+            sps.log2_max_pic_order_cnt_lsb = r.read_ue()?.checked_add(4).filter(|it| *it <= 16)?;
+        }
+        1 => {
+            sps.delta_pic_order_always_zero_flag = 1 == r.read(1)?;
+            sps.offset_for_non_ref_pic = r.read_se()?;
+            sps.offset_for_top_to_bottom_field = r.read_se()?;
+            for _ in 0..r.read_ue()?.min(255) {
+                sps.offset_for_ref_frame.push(r.read_se()?);
+            }
+        }
+        _ => {}
+    }
+    let _max_num_ref_frames = r.read_ue()?;
+    let _gaps_in_frame_num_value_allowed_flag = r.read(1)?;
+    let pic_width_in_mbs = r.read_ue()? + 1;
+    let pic_height_in_map_units = r.read_ue()? + 1;
+    let frame_mbs_only_flag = r.read(1)?;
+    if 0 == frame_mbs_only_flag {
+        let _mb_adaptive_frame_field_flag = r.read(1)?;
+    }
+    let _direct_8x8_inference_flag = r.read(1)?;
+    let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+    if 1 == r.read(1)? {
+        (left, right, top, bottom) = (r.read_ue()?, r.read_ue()?, r.read_ue()?, r.read_ue()?);
+    }
+
+    let (crop_unit_x, crop_unit_y) = match (chroma_format_idc, separate_colour_plane_flag) {
+        (0, _) | (3, true) => { (1, 2 - frame_mbs_only_flag) }
+        (1, _) => { (2, 2 * (2 - frame_mbs_only_flag)) }
+        (2, _) => { (2, 2 - frame_mbs_only_flag) }
+        _ => { (1, 2 - frame_mbs_only_flag) }
+    };
+
+    Some(SequenceParameterSet {
+        width: (pic_width_in_mbs * 16).saturating_sub(crop_unit_x * (left + right)),
+        height: ((2 - frame_mbs_only_flag) * pic_height_in_map_units * 16).saturating_sub(crop_unit_y * (top + bottom)),
+        chroma_format_idc: chroma_format_idc as u8,
+        bit_depth_luma_minus8: bit_depth_luma_minus8 as u8,
+        bit_depth_chroma_minus8: bit_depth_chroma_minus8 as u8,
+        separate_colour_plane_flag,
+        frame_mbs_only_flag: 1 == frame_mbs_only_flag,
+        ..sps
+    })
+}
+
+fn hevc_sps(nal: &[u8]) -> Option<(SequenceParameterSet, ProfileTierLevel)> {
+    let rbsp = rbsp(nal, 2);
+    let mut r = BitReader::new(&rbsp);
+
+    let _sps_video_parameter_set_id = r.read(4)?;
+    let max_sub_layers_minus1 = r.read(3)?;
+    let temporal_id_nesting_flag = r.read(1)?;
+
+    // profile_tier_level(1, sps_max_sub_layers_minus1)
+    let ptl = ProfileTierLevel {
+        general_profile: r.read(8)? as u8,
+        general_profile_compatibility_flags: r.read(32)?,
+        general_constraint_indicator_flags: (r.read(16)? as u64) << 32 | r.read(32)? as u64,
+        general_level_idc: r.read(8)? as u8,
+        temporal_layers: max_sub_layers_minus1 as u8 + 1,
+        temporal_id_nested: 1 == temporal_id_nesting_flag,
+    };
+    let mut sub_layers = vec![];
+    for _ in 0..max_sub_layers_minus1 {
+        sub_layers.push((r.read(1)?, r.read(1)?));
+    }
+    if 0 < max_sub_layers_minus1 {
+        r.skip(2 * (8 - max_sub_layers_minus1 as usize))?;
+    }
+    for (sub_layer_profile_present_flag, sub_layer_level_present_flag) in sub_layers {
+        r.skip(88 * sub_layer_profile_present_flag as usize + 8 * sub_layer_level_present_flag as usize)?;
+    }
+
+    let sps_seq_parameter_set_id = r.read_ue()?;
+    let chroma_format_idc = r.read_ue()?;
+    let mut separate_colour_plane_flag = false;
+    if 3 == chroma_format_idc {
+        separate_colour_plane_flag = 1 == r.read(1)?;
+    }
+    let pic_width_in_luma_samples = r.read_ue()?;
+    let pic_height_in_luma_samples = r.read_ue()?;
+    let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+    if 1 == r.read(1)? {
+        (left, right, top, bottom) = (r.read_ue()?, r.read_ue()?, r.read_ue()?, r.read_ue()?);
+    }
+    let bit_depth_luma_minus8 = r.read_ue()?;
+    let bit_depth_chroma_minus8 = r.read_ue()?;
+    let log2_max_pic_order_cnt_lsb = r.read_ue()?.checked_add(4).filter(|it| *it <= 16)?;
+
+    let (sub_width_c, sub_height_c) = match (chroma_format_idc, separate_colour_plane_flag) {
+        (1, _) => { (2, 2) }
+        (2, _) => { (2, 1) }
+        _ => { (1, 1) }
+    };
+
+    Some((SequenceParameterSet {
+        seq_parameter_set_id: sps_seq_parameter_set_id,
+        width: pic_width_in_luma_samples.saturating_sub(sub_width_c * (left + right)),
+        height: pic_height_in_luma_samples.saturating_sub(sub_height_c * (top + bottom)),
+        chroma_format_idc: chroma_format_idc as u8,
+        bit_depth_luma_minus8: bit_depth_luma_minus8 as u8,
+        bit_depth_chroma_minus8: bit_depth_chroma_minus8 as u8,
+        separate_colour_plane_flag,
+        frame_mbs_only_flag: true,
+        log2_max_pic_order_cnt_lsb,
+        ..Default::default()
+    }, ptl))
+}
+
+/// Identifier and fields of an H.264 or H.265 picture parameter set.
+fn pps(codec: Codec, nal: &[u8]) -> Option<(u32, PictureParameterSet)> {
+    let rbsp = rbsp(nal, match codec { Codec::H264 => { 1 } Codec::H265 => { 2 } });
+    let mut r = BitReader::new(&rbsp);
+
+    let pic_parameter_set_id = r.read_ue()?;
+    let mut rst = PictureParameterSet {
+        seq_parameter_set_id: r.read_ue()?,
+        ..Default::default()
+    };
+    match codec {
+        Codec::H264 => {
+            let _entropy_coding_mode_flag = r.read(1)?;
+            rst.bottom_field_pic_order_in_frame_present_flag = 1 == r.read(1)?;
+        }
+        Codec::H265 => {
+            let _dependent_slice_segments_enabled_flag = r.read(1)?;
+            rst.output_flag_present_flag = 1 == r.read(1)?;
+            rst.num_extra_slice_header_bits = r.read(3)?;
+        }
+    }
+
+    Some((pic_parameter_set_id, rst))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bytes::BytesMut;
+
+    use crate::{IO, Object};
+    use crate::annexb::{Codec, export, export_fragments, import};
+    use crate::Error;
+    use crate::moof::{moof, traf, trun};
+    use crate::moov::{avcC, hdlr, moov, mvex, Sample, SampleEntry, trak, trex};
+    use crate::segment::{Fragment, Segment};
+    use crate::types::types;
+
+    #[test]
+    fn chk_annexb() {
+        // an IDR picture, a non-IDR picture, and an IDR picture with in-band parameter sets
+        let samples: [&[u8]; 3] = [
+            &[0, 2, 0x65, 0xaa, 0, 1, 0x06],
+            &[0, 2, 0x41, 0xbb],
+            &[0, 2, 0x67, 0x42, 0, 2, 0x68, 0xce, 0, 2, 0x65, 0xcc],
+        ];
+        let expected: &[u8] = &[
+            0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x68, 0xce, 0, 0, 0, 1, 0x65, 0xaa, 0, 0, 0, 1, 0x06,
+            0, 0, 0, 1, 0x41, 0xbb,
+            0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x68, 0xce, 0, 0, 0, 1, 0x65, 0xcc,
+        ];
+
+        let source = samples.concat();
+        let mut v = {
+            let mut config = avcC {
+                profile_indication: 66,
+                level_indication: 30,
+                length_size_minus_one: 1,
+                sequence_parameter_sets: vec![BytesMut::from(&[0x67, 0x42][..])],
+                picture_parameter_sets: vec![BytesMut::from(&[0x68, 0xce][..])],
+                ..Default::default()
+            };
+            let mut v = trak::default();
+
+            v.tkhd.track_id = 1;
+            v.mdia.hdlr = hdlr::vide("Video");
+            v.mdia.minf.stbl.stsd.entries.push(SampleEntry::avc1 {
+                base: Box::new(SampleEntry::Visual {
+                    base: Box::new(SampleEntry::Base {
+                        handler_type: types::avc1,
+                        data_reference_index: 1,
+                    }),
+                    width: 16,
+                    height: 16,
+                    horiz_resolution: 0x00480000,
+                    vert_resolution: 0x00480000,
+                    frame_count: 1,
+                    compressor_name: "".to_owned(),
+                    depth: 24,
+                }),
+                ext: Object::new(avcC::BOX_TYPE, config.as_bytes()).as_bytes(),
+            });
+
+            v
+        };
+        let mut offset = 0;
+        v.mdia.minf.stbl.set_samples(&samples.iter().enumerate().map(|(i, it)| {
+            offset += it.len() as u64;
+
+            Sample {
+                offset: offset - it.len() as u64,
+                size: it.len() as u32,
+                decode_time: i as u64,
+                duration: 1,
+                composition_offset: 0,
+                is_sync: 1 != i,
+                description_index: 1,
+            }
+        }).collect::<Vec<_>>());
+
+        let mut w = vec![];
+        export(&mut Cursor::new(&source), &v, &mut w).unwrap();
+        assert_eq!(expected, &w[..]);
+
+        let mut m = moov::default();
+        m.traks.push(v);
+        m.mvex = Some(mvex {
+            trexs: vec![{
+                let mut v = trex::default();
+
+                v.track_id = 1;
+                v.default_sample_description_index = 1;
+
+                v
+            }],
+            ..Default::default()
+        });
+
+        let mut segment = Segment::default();
+        for it in samples {
+            let mut moof = moof::default();
+            moof.trafs.push({
+                let mut v = traf::default();
+
+                v.tfhd.track_id = 1;
+                v.truns.push({
+                    let mut v = trun::default();
+
+                    v.data_offset = Some(0);
+                    v.samples.push((Some(1), Some(it.len() as u32), None, None));
+
+                    v
+                });
+
+                v
+            });
+            moof.trafs[0].truns[0].data_offset = Some(8 + moof.len() as u32 + 8);
+            segment.fragments.push(Fragment::new(moof, BytesMut::from(it)));
+        }
+
+        let mut w = vec![];
+        export_fragments(&m, &segment, 0, 1, &mut w).unwrap();
+        assert_eq!(expected, &w[..]);
+
+        assert_eq!(Err(Error::MissingTrack { track_id: 2 }), export_fragments(&m, &segment, 0, 2, &mut vec![]));
+        m.traks[0].mdia.minf.stbl.stsd.entries.clear();
+        assert_eq!(Err(Error::UnsupportedCodec { track_id: 1 }), export_fragments(&m, &segment, 0, 1, &mut vec![]));
+    }
+
+    #[test]
+    fn chk_import() {
+        let sps: &[u8] = &[0x67, 0x64, 0x00, 0x28, 0xac, 0xd9, 0x40, 0x78, 0x02, 0x27, 0xe5, 0x40];
+        let pps: &[u8] = &[0x68, 0xeb, 0xe3, 0xcb];
+        let stream = [
+            &[0, 0, 0, 1, 0x09, 0xf0][..],
+            &[0, 0, 0, 1], sps, &[0, 0, 1], pps,
+            &[0, 0, 1, 0x65, 0x88, 0x84, 0x08, 0],
+            &[0, 0, 0, 1, 0x09, 0xf0],
+            &[0, 0, 0, 1, 0x41, 0x9a, 0x22, 0x40],
+            &[0, 0, 1, 0x41, 0x40, 0x03],
+            &[0, 0, 0, 1, 0x01, 0x9e, 0x41, 0x40],
+            &[0, 0, 0, 1], sps, &[0, 0, 1], pps,
+            &[0, 0, 1, 0x65, 0x88, 0x82, 0x02],
+        ].concat();
+
+        let mut file = vec![];
+        let m = import(&stream, Codec::H264, 30000, 1001, &mut file).unwrap();
+        let mut r = BytesMut::from(&file[..]);
+        let _ = Object::parse(&mut r);
+        assert_eq!(m, moov::parse(&mut Object::parse(&mut r).payload));
+        assert_eq!(1, m.traks.len());
+        assert_eq!(4 * 1001, m.traks[0].tkhd.duration);
+        assert_eq!((1920 << 16, 1080 << 16), (m.traks[0].tkhd.width, m.traks[0].tkhd.height));
+
+        let entry = &m.traks[0].mdia.minf.stbl.stsd.entries[0];
+        let SampleEntry::avc1 { base, .. } = entry else { panic!() };
+        assert!(matches!(**base, SampleEntry::Visual { width: 1920, height: 1080, .. }));
+        let config = entry.avcC().unwrap();
+        assert_eq!((100, 40, 3), (config.profile_indication, config.level_indication, config.length_size_minus_one));
+        assert_eq!(vec![sps], config.sequence_parameter_sets);
+        assert_eq!(vec![pps], config.picture_parameter_sets);
+        assert_eq!(&[0xfd, 0xf8, 0xf8, 0][..], &config.ext[..]);
+
+        let samples = m.traks[0].mdia.minf.stbl.samples();
+        assert_eq!(vec![true, false, false, true], samples.iter().map(|it| it.is_sync).collect::<Vec<_>>());
+        assert_eq!(vec![0, 1001, 2002, 3003], samples.iter().map(|it| it.decode_time).collect::<Vec<_>>());
+        // I P B I, presented as I B P I
+        assert_eq!(vec![0, 1001, -1001, 0], samples.iter().map(|it| it.composition_offset).collect::<Vec<_>>());
+
+        let mut w = vec![];
+        export(&mut Cursor::new(&file), &m.traks[0], &mut w).unwrap();
+        assert_eq!([
+            &[0, 0, 0, 1], sps, &[0, 0, 0, 1], pps, &[0, 0, 0, 1, 0x65, 0x88, 0x84, 0x08][..],
+            &[0, 0, 0, 1, 0x41, 0x9a, 0x22, 0x40, 0, 0, 0, 1, 0x41, 0x40, 0x03],
+            &[0, 0, 0, 1, 0x01, 0x9e, 0x41, 0x40],
+            &[0, 0, 0, 1], sps, &[0, 0, 0, 1], pps, &[0, 0, 0, 1, 0x65, 0x88, 0x82, 0x02],
+        ].concat(), w);
+
+        assert_eq!(
+            Err(Error::MissingParameterSet { nal_unit_type: 8 }),
+            import(&[&[0, 0, 0, 1], sps, &[0, 0, 1, 0x65, 0x88, 0x84, 0x08]].concat(), Codec::H264, 25, 1, &mut vec![]),
+        );
+
+        let vps: &[u8] = &[0x40, 0x01, 0x0c, 0x01, 0xff, 0xff, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90];
+        let sps: &[u8] = &[
+            0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+            0x00, 0x5d, 0xa0, 0x02, 0x80, 0x80, 0x2d, 0x16, 0x58,
+        ];
+        let pps: &[u8] = &[0x44, 0x01, 0xc1, 0x72, 0xb4];
+        let stream = [
+            &[0, 0, 0, 1], vps, &[0, 0, 0, 1], sps, &[0, 0, 0, 1], pps,
+            &[0, 0, 0, 1, 0x26, 0x01, 0xae],
+            &[0, 0, 0, 1, 0x02, 0x01, 0xd0, 0x14],
+            &[0, 0, 0, 1, 0x00, 0x01, 0xe0, 0x30],
+            &[0, 0, 0, 1, 0x2a, 0x01, 0xac, 0x0e],
+        ].concat();
+
+        let mut file = vec![];
+        let m = import(&stream, Codec::H265, 25, 1, &mut file).unwrap();
+        let mut r = BytesMut::from(&file[..]);
+        let _ = Object::parse(&mut r);
+        assert_eq!(m, moov::parse(&mut Object::parse(&mut r).payload));
+        assert_eq!((1280 << 16, 720 << 16), (m.traks[0].tkhd.width, m.traks[0].tkhd.height));
+
+        let entry = &m.traks[0].mdia.minf.stbl.stsd.entries[0];
+        let SampleEntry::hvc1 { base, .. } = entry else { panic!() };
+        assert!(matches!(**base, SampleEntry::Visual { width: 1280, height: 720, .. }));
+        let config = entry.hvcC().unwrap();
+        assert_eq!((1, 0x60000000, 0x900000000000, 93), (
+            config.general_profile,
+            config.general_profile_compatibility_flags,
+            config.general_constraint_indicator_flags,
+            config.general_level_idc,
+        ));
+        assert_eq!(vec![
+            (true, 32, vec![BytesMut::from(vps)]),
+            (true, 33, vec![BytesMut::from(sps)]),
+            (true, 34, vec![BytesMut::from(pps)]),
+        ], config.arrays);
+
+        // IDR, TRAIL_R, TRAIL_N and CRA pictures
+        let samples = m.traks[0].mdia.minf.stbl.samples();
+        assert_eq!(vec![true, false, false, true], samples.iter().map(|it| it.is_sync).collect::<Vec<_>>());
+        assert_eq!(vec![0, 1, -1, 0], samples.iter().map(|it| it.composition_offset).collect::<Vec<_>>());
+
+        assert_eq!(Err(Error::NoInput), import(&[], Codec::H264, 25, 1, &mut vec![]));
+    }
+}
@@ -0,0 +1,59 @@
+/// Reads big-endian bit fields, most significant bit first.
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+        }
+    }
+
+    /// Reads `n` bits, at most 32, or `None` past the end of the data.
+    pub(crate) fn read(&mut self, n: usize) -> Option<u32> {
+        if self.data.len() * 8 < self.position + n {
+            return None;
+        }
+
+        let mut v = 0u32;
+        for _ in 0..n {
+            let bit = self.data[self.position / 8] >> (7 - self.position % 8) & 1;
+            v = v << 1 | bit as u32;
+            self.position += 1;
+        }
+
+        Some(v)
+    }
+
+    pub(crate) fn skip(&mut self, n: usize) -> Option<()> {
+        if self.data.len() * 8 < self.position + n {
+            return None;
+        }
+        self.position += n;
+
+        Some(())
+    }
+
+    /// Reads an unsigned Exp-Golomb code, ue(v).
+    pub(crate) fn read_ue(&mut self) -> Option<u32> {
+        let mut leading_zero_bits = 0;
+        while 0 == self.read(1)? {
+            leading_zero_bits += 1;
+            if 31 < leading_zero_bits {
+                return None;
+            }
+        }
+
+        Some(((1u64 << leading_zero_bits) - 1 + self.read(leading_zero_bits)? as u64) as u32)
+    }
+
+    /// Reads a signed Exp-Golomb code, se(v).
+    pub(crate) fn read_se(&mut self) -> Option<i32> {
+        let v = self.read_ue()? as i64;
+
+        Some(if 0 == v % 2 { -(v / 2) } else { (v + 1) / 2 } as i32)
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt::{Debug, Formatter};
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{FullBox, IO};
+use crate::scte35::{self, SpliceInfoSection};
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> emsg {
+    emsg::parse(r)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct emsg {
+    base: FullBox,
+
+    pub scheme_id_uri: String,
+    pub value: String,
+    pub timescale: u32,
+    pub presentation_time: EventPresentationTime,
+    pub event_duration: u32,
+    pub id: u32,
+    pub message_data: BytesMut,
+}
+
+/// Presentation time of an event, which also selects the box version.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventPresentationTime {
+    /// version 0: delta from the earliest presentation time of the segment
+    Delta(u32),
+    /// version 1: absolute time on the presentation timeline
+    Absolute(u64),
+}
+
+impl emsg {
+    pub const BOX_TYPE: u32 = types::emsg;
+
+    /// Event time in `timescale` units on the presentation timeline, given
+    /// the earliest presentation time of the segment carrying the event in
+    /// `track_timescale` units.
+    pub fn absolute_presentation_time(&self, earliest_presentation_time: u64, track_timescale: u32) -> u64 {
+        match self.presentation_time {
+            EventPresentationTime::Delta(v) => {
+                rescale(earliest_presentation_time, track_timescale, self.timescale) + v as u64
+            }
+            EventPresentationTime::Absolute(v) => { v }
+        }
+    }
+
+    /// Version 1 form of this event.
+    pub fn to_v1(&self, earliest_presentation_time: u64, track_timescale: u32) -> Self {
+        let mut rst = self.clone();
+
+        rst.presentation_time = EventPresentationTime::Absolute(
+            self.absolute_presentation_time(earliest_presentation_time, track_timescale)
+        );
+
+        rst
+    }
+
+    /// Version 0 form of this event, or `None` when it starts before the
+    /// segment or too far after it for a 32-bit delta.
+    pub fn to_v0(&self, earliest_presentation_time: u64, track_timescale: u32) -> Option<Self> {
+        let mut rst = self.clone();
+
+        let v = self.absolute_presentation_time(earliest_presentation_time, track_timescale)
+            .checked_sub(rescale(earliest_presentation_time, track_timescale, self.timescale))?;
+        rst.presentation_time = EventPresentationTime::Delta(u32::try_from(v).ok()?);
+
+        Some(rst)
+    }
+
+    /// SCTE 35 section carried by this event, if its scheme is
+    /// `urn:scte:scte35:2013:bin` and the section decodes.
+    pub fn splice_info_section(&self) -> Option<SpliceInfoSection> {
+        if scte35::SCHEME_ID_URI != self.scheme_id_uri {
+            return None;
+        }
+
+        SpliceInfoSection::decode(&self.message_data)
+    }
+
+    /// Makes this event carry `section`, under the SCTE 35 binary scheme.
+    pub fn set_splice_info_section(&mut self, section: &SpliceInfoSection) {
+        self.scheme_id_uri = scte35::SCHEME_ID_URI.to_owned();
+        self.message_data = section.encode();
+    }
+}
+
+fn rescale(v: u64, from: u32, to: u32) -> u64 {
+    if 0 == from || from == to {
+        v
+    } else {
+        (v as u128 * to as u128 / from as u128) as u64
+    }
+}
+
+impl Default for emsg {
+    //! extends FullBox(‘emsg’, version, flags = 0) {
+    //!     if (version==0) {
+    //!         string scheme_id_uri;
+    //!         string value;
+    //!         unsigned int(32) timescale;
+    //!         unsigned int(32) presentation_time_delta;
+    //!         unsigned int(32) event_duration;
+    //!         unsigned int(32) id;
+    //!     } else if (version==1) {
+    //!         unsigned int(32) timescale;
+    //!         unsigned int(64) presentation_time;
+    //!         unsigned int(32) event_duration;
+    //!         unsigned int(32) id;
+    //!         string scheme_id_uri;
+    //!         string value;
+    //!     }
+    //!     unsigned int(8) message_data[];
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            scheme_id_uri: "".to_owned(),
+            value: "".to_owned(),
+            timescale: 1000,
+            presentation_time: EventPresentationTime::Delta(0),
+            event_duration: 0,
+            id: 0,
+            message_data: BytesMut::new(),
+        }
+    }
+}
+
+impl Debug for emsg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\tscheme_id_uri: {:?}", self.scheme_id_uri))?;
+        f.write_fmt(format_args!("\n\tvalue: {:?}", self.value))?;
+        f.write_fmt(format_args!("\n\ttimescale: {:?}", self.timescale))?;
+        match self.presentation_time {
+            EventPresentationTime::Delta(v) => {
+                f.write_fmt(format_args!("\n\tpresentation_time_delta: {:?}", v))?;
+            }
+            EventPresentationTime::Absolute(v) => {
+                f.write_fmt(format_args!("\n\tpresentation_time: {:?}", v))?;
+            }
+        }
+        f.write_fmt(format_args!("\n\tevent_duration: {:?}", self.event_duration))?;
+        f.write_fmt(format_args!("\n\tid: {:?}", self.id))?;
+        f.write_fmt(format_args!("\n\tmessage_data: {:?} bytes", self.message_data.len()))?;
+
+        Ok(())
+    }
+}
+
+impl IO for emsg {
+    fn len(&self) -> usize {
+        self.base.len() + self.scheme_id_uri.len() + 1 + self.value.len() + 1 + self.message_data.len() +
+            match self.presentation_time {
+                EventPresentationTime::Delta(_) => { 16 }
+                EventPresentationTime::Absolute(_) => { 20 }
+            }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+
+        let mut rst = Self {
+            base,
+            ..Self::default()
+        };
+
+        if 1 == rst.base.version {
+            rst.timescale = r.get_u32();
+            rst.presentation_time = EventPresentationTime::Absolute(r.get_u64());
+            rst.event_duration = r.get_u32();
+            rst.id = r.get_u32();
+            rst.scheme_id_uri = get_string(r);
+            rst.value = get_string(r);
+        } else {
+            rst.scheme_id_uri = get_string(r);
+            rst.value = get_string(r);
+            rst.timescale = r.get_u32();
+            rst.presentation_time = EventPresentationTime::Delta(r.get_u32());
+            rst.event_duration = r.get_u32();
+            rst.id = r.get_u32();
+        }
+        rst.message_data = r.split_to(r.len());
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        match self.presentation_time {
+            EventPresentationTime::Delta(v) => {
+                self.base.version = 0;
+                w.put(self.base.as_bytes());
+
+                w.put_slice(self.scheme_id_uri.as_bytes());
+                w.put_u8(0);
+                w.put_slice(self.value.as_bytes());
+                w.put_u8(0);
+                w.put_u32(self.timescale);
+                w.put_u32(v);
+                w.put_u32(self.event_duration);
+                w.put_u32(self.id);
+            }
+            EventPresentationTime::Absolute(v) => {
+                self.base.version = 1;
+                w.put(self.base.as_bytes());
+
+                w.put_u32(self.timescale);
+                w.put_u64(v);
+                w.put_u32(self.event_duration);
+                w.put_u32(self.id);
+                w.put_slice(self.scheme_id_uri.as_bytes());
+                w.put_u8(0);
+                w.put_slice(self.value.as_bytes());
+                w.put_u8(0);
+            }
+        }
+        w.put(self.message_data.chunk());
+
+        w
+    }
+}
+
+fn get_string(r: &mut BytesMut) -> String {
+    let len = r.iter().position(|c| 0 == *c).unwrap_or(r.len());
+    let rst = String::from_utf8_lossy(&r.split_to(len)).into_owned();
+    if r.has_remaining() {
+        r.advance(1);
+    }
+
+    rst
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{IO, Object};
+    use crate::emsg::{emsg, EventPresentationTime};
+
+    #[test]
+    fn chk_emsg() {
+        let mut b = emsg::default();
+
+        b.scheme_id_uri = "urn:mpeg:dash:event:2012".to_owned();
+        b.value = "1".to_owned();
+        b.timescale = 1000;
+        b.presentation_time = EventPresentationTime::Delta(500);
+        b.event_duration = 0xFFFFFFFF;
+        b.id = 7;
+        b.message_data = BytesMut::from(&b"mpd"[..]);
+
+        let mut obj = Object::parse(&mut Object::new(emsg::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(emsg::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, emsg::parse(&mut obj.payload));
+
+        let mut v1 = b.to_v1(90000 * 10, 90000);
+        assert_eq!(EventPresentationTime::Absolute(10500), v1.presentation_time);
+        assert_eq!(Some(b.clone()), v1.to_v0(90000 * 10, 90000));
+        assert_eq!(None, v1.to_v0(90000 * 11, 90000));
+
+        let mut obj = Object::parse(&mut Object::new(emsg::BOX_TYPE, v1.as_bytes()).as_bytes());
+
+        assert_eq!(v1.len(), obj.payload.len());
+        assert_eq!(v1, emsg::parse(&mut obj.payload));
+    }
+}
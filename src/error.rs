@@ -0,0 +1,118 @@
+use std::fmt::{Display, Formatter};
+
+/// Errors of operations spanning several boxes, such as validating a `moov`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Io(std::io::ErrorKind),
+    /// `track_id` references `reference_track_id`, which is not in the `moov`
+    UnknownTrackReference {
+        track_id: u32,
+        reference_type: u32,
+        reference_track_id: u32,
+    },
+    /// the time range is empty
+    InvalidRange {
+        start: u64,
+        end: u64,
+    },
+    /// the edit list of `track_id` is too complex for the operation
+    UnsupportedEditList {
+        track_id: u32,
+    },
+    /// the samples are in movie fragments, not in the `moov`
+    Fragmented,
+    /// there is nothing to process
+    NoInput,
+    /// input `input` has no track `track_id`
+    UnknownTrack {
+        input: usize,
+        track_id: u32,
+    },
+    /// the `moov` has no track `track_id`
+    MissingTrack {
+        track_id: u32,
+    },
+    /// the track of input `input` matching `track_id` of the first input is
+    /// missing or has a different kind of media
+    Incompatible {
+        input: usize,
+        track_id: u32,
+    },
+    /// the sample entries of `track_id` are not in a format the operation
+    /// supports
+    UnsupportedCodec {
+        track_id: u32,
+    },
+    /// the audio configuration of `track_id` cannot be represented in the
+    /// output format
+    UnsupportedAudioConfig {
+        track_id: u32,
+    },
+    /// the video stream lacks a parameter set of `nal_unit_type` that its
+    /// pictures need
+    MissingParameterSet {
+        nal_unit_type: u8,
+    },
+    /// a PIFF `senc` of `track_id` overrides the track encryption parameters,
+    /// which CENC carries in `seig` sample groups instead
+    UnsupportedEncryptionOverride {
+        track_id: u32,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(kind) => {
+                f.write_fmt(format_args!("I/O error: {:}", kind))
+            }
+            Error::UnknownTrackReference { track_id, reference_type, reference_track_id } => {
+                f.write_fmt(format_args!(
+                    "track {:?} references unknown track {:?} with {:?}",
+                    track_id, reference_track_id, String::from_utf8_lossy(&reference_type.to_be_bytes()),
+                ))
+            }
+            Error::InvalidRange { start, end } => {
+                f.write_fmt(format_args!("invalid time range [{:?}, {:?})", start, end))
+            }
+            Error::UnsupportedEditList { track_id } => {
+                f.write_fmt(format_args!("unsupported edit list in track {:?}", track_id))
+            }
+            Error::Fragmented => {
+                f.write_fmt(format_args!("fragmented movie"))
+            }
+            Error::NoInput => {
+                f.write_fmt(format_args!("no input"))
+            }
+            Error::UnknownTrack { input, track_id } => {
+                f.write_fmt(format_args!("input {:?} has no track {:?}", input, track_id))
+            }
+            Error::MissingTrack { track_id } => {
+                f.write_fmt(format_args!("no track {:?}", track_id))
+            }
+            Error::Incompatible { input, track_id } => {
+                f.write_fmt(format_args!("input {:?} is incompatible with track {:?}", input, track_id))
+            }
+            Error::UnsupportedCodec { track_id } => {
+                f.write_fmt(format_args!("unsupported codec in track {:?}", track_id))
+            }
+            Error::UnsupportedAudioConfig { track_id } => {
+                f.write_fmt(format_args!("unsupported audio configuration in track {:?}", track_id))
+            }
+            Error::MissingParameterSet { nal_unit_type } => {
+                f.write_fmt(format_args!("missing parameter set of NAL unit type {:?}", nal_unit_type))
+            }
+            Error::UnsupportedEncryptionOverride { track_id } => {
+                f.write_fmt(format_args!("unsupported encryption parameter override in track {:?}", track_id))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.kind())
+    }
+}
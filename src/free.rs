@@ -0,0 +1,237 @@
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{IO, Object};
+use crate::moov::moov;
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> free {
+    free::parse(r)
+}
+
+/// Free space box, whose content is irrelevant.
+#[allow(non_camel_case_types)]
+#[derive(Default, PartialEq)]
+pub struct free {
+    pub data: BytesMut,
+}
+
+impl free {
+    pub const BOX_TYPE: u32 = types::free;
+
+    /// Zero-filled free space of `size` bytes, box header excluded.
+    pub fn new(size: usize) -> Self {
+        Self {
+            data: BytesMut::from(&vec![0_u8; size][..]),
+        }
+    }
+}
+
+impl Debug for free {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\tsize: {:?}", self.data.len()))?;
+
+        Ok(())
+    }
+}
+
+impl IO for free {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            data: r.split_to(r.len()),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.data.chunk());
+
+        w
+    }
+}
+
+/// Free space box, laid out exactly like `free`.
+#[allow(non_camel_case_types)]
+#[derive(Default, PartialEq)]
+pub struct skip(pub free);
+
+impl skip {
+    pub const BOX_TYPE: u32 = types::skip;
+
+    pub fn new(size: usize) -> Self {
+        Self(free::new(size))
+    }
+}
+
+impl Deref for skip {
+    type Target = free;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for skip {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Debug for skip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl IO for skip {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self(free::parse(r))
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        self.0.as_bytes()
+    }
+}
+
+/// Rewrites the top-level `moov` of `file` in place, into the space of the
+/// existing `moov` and the `free` or `skip` boxes right around it, and pads
+/// what is left with a `free` box.
+///
+/// Media data doesn't move, so chunk offsets stay valid. Returns `false`,
+/// leaving `file` untouched, when there is no `moov` or the new one doesn't
+/// fit.
+pub fn rewrite_moov<F: Read + Write + Seek>(file: &mut F, moov: &mut moov) -> std::io::Result<bool> {
+    let boxes = scan(file)?;
+    let Some(i) = boxes.iter().position(|it| moov::BOX_TYPE == it.2) else {
+        return Ok(false);
+    };
+    let is_free = |it: &(u64, u64, u32)| free::BOX_TYPE == it.2 || skip::BOX_TYPE == it.2;
+
+    let first = boxes[..i].iter().rposition(|it| !is_free(it)).map_or(0, |n| n + 1);
+    let last = boxes[i + 1..].iter().position(|it| !is_free(it)).map_or(boxes.len(), |n| i + 1 + n) - 1;
+    let start = boxes[first].0;
+    let end = boxes[last].0 + boxes[last].1;
+
+    let w = Object::new(moov::BOX_TYPE, moov.as_bytes()).as_bytes();
+    let size = w.len() as u64;
+    if end - start != size && end - start < size + 8 {
+        return Ok(false);
+    }
+
+    file.seek(SeekFrom::Start(start))?;
+    file.write_all(&w)?;
+    let padding = end - start - size;
+    if 0 < padding {
+        let mut header = BytesMut::new();
+        if (u32::MAX as u64) < padding {
+            header.put_u32(1);
+            header.put_u32(free::BOX_TYPE);
+            header.put_u64(padding);
+        } else {
+            header.put_u32(padding as u32);
+            header.put_u32(free::BOX_TYPE);
+        }
+        file.write_all(&header)?;
+        std::io::copy(&mut std::io::repeat(0).take(padding - header.len() as u64), file)?;
+    }
+
+    Ok(true)
+}
+
+// offset, size and type of every top-level box
+fn scan<F: Read + Seek>(file: &mut F) -> std::io::Result<Vec<(u64, u64, u32)>> {
+    let file_size = file.seek(SeekFrom::End(0))?;
+    let mut rst = vec![];
+
+    let mut offset = 0;
+    while offset + 8 <= file_size {
+        let mut buf = [0_u8; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buf)?;
+        let mut size = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64;
+        let box_type = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+        if 1 == size {
+            file.read_exact(&mut buf)?;
+            size = u64::from_be_bytes(buf);
+        } else if 0 == size {
+            size = file_size - offset;
+        }
+        if size < 8 || file_size - offset < size {
+            break;
+        }
+
+        rst.push((offset, size, box_type));
+        offset += size;
+    }
+
+    Ok(rst)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bytes::{BufMut, BytesMut};
+
+    use crate::{IO, Object};
+    use crate::free::{free, rewrite_moov, skip};
+    use crate::moov::{moov, mvex, trex};
+    use crate::types::types;
+
+    fn new_trex(track_id: u32) -> trex {
+        let mut v = trex::default();
+
+        v.track_id = track_id;
+
+        v
+    }
+
+    #[test]
+    fn chk_free() {
+        let mut b = free::new(16);
+        let mut obj = Object::parse(&mut Object::new(free::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(free::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, free::parse(&mut obj.payload));
+
+        let mut moov = moov::default();
+        let mut file = BytesMut::new();
+        file.put(Object::new(moov::BOX_TYPE, moov.as_bytes()).as_bytes());
+        file.put(Object::new(skip::BOX_TYPE, skip::new(100).as_bytes()).as_bytes());
+        file.put(Object::new(types::mdat, BytesMut::from(&[0x5a_u8; 64][..])).as_bytes());
+        let mdat_offset = file.len() - 72;
+
+        let mut cursor = Cursor::new(file.to_vec());
+        moov.mvex = Some(mvex {
+            trexs: (1..=2).map(new_trex).collect(),
+            ..mvex::default()
+        });
+        assert!(rewrite_moov(&mut cursor, &mut moov).unwrap());
+
+        let mut r = BytesMut::from(&cursor.get_ref()[..]);
+        assert_eq!(file.len(), r.len());
+        let mut obj = Object::parse(&mut r);
+        assert_eq!(moov, moov::parse(&mut obj.payload));
+        let obj = Object::parse(&mut r);
+        assert_eq!(free::BOX_TYPE, obj.box_type);
+        assert_eq!(mdat_offset, cursor.get_ref().len() - r.len());
+
+        let before = cursor.get_ref().clone();
+        moov.mvex.as_mut().unwrap().trexs.extend((3..=4).map(new_trex));
+        assert!(!rewrite_moov(&mut cursor, &mut moov).unwrap());
+        assert_eq!(&before, cursor.get_ref());
+    }
+}
@@ -91,10 +91,7 @@ mod tests {
                 0x64617368,
             ],
         };
-        let mut obj = Object::parse(&mut Object {
-            box_type: ftyp::BOX_TYPE,
-            payload: b.as_bytes(),
-        }.as_bytes());
+        let mut obj = Object::parse(&mut Object::new(ftyp::BOX_TYPE, b.as_bytes()).as_bytes());
 
         assert_eq!(ftyp::BOX_TYPE, obj.box_type);
         assert_eq!(b.len(), obj.payload.len());
@@ -1,9 +1,26 @@
+pub use crate::error::Error;
 pub use crate::object::{FullBox, Object};
 pub use crate::traits::IO;
 
+mod error;
 mod traits;
 mod object;
+mod bits;
 pub mod types;
 pub mod ftyp;
 pub mod moov;
 pub mod moof;
+pub mod sidx;
+pub mod styp;
+pub mod emsg;
+pub mod scte35;
+pub mod prft;
+pub mod mfra;
+pub mod free;
+pub mod uuid;
+pub mod piff;
+pub mod udta;
+pub mod remux;
+pub mod annexb;
+pub mod adts;
+pub mod segment;
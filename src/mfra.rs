@@ -0,0 +1,499 @@
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, SeekFrom};
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{FullBox, IO, Object};
+use crate::moof::moof;
+use crate::moov::trex;
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> mfra {
+    mfra::parse(r)
+}
+
+/// Reads the `mfra` at the end of a file, located from its trailing `mfro`.
+///
+/// Returns `None` when the file doesn't end with a `mfro` pointing at a
+/// `mfra`.
+pub fn read<R: Read + Seek>(r: &mut R) -> std::io::Result<Option<mfra>> {
+    let file_size = r.seek(SeekFrom::End(0))?;
+    if file_size < 16 {
+        return Ok(None);
+    }
+
+    let mut buf = [0_u8; 16];
+    r.seek(SeekFrom::End(-16))?;
+    r.read_exact(&mut buf)?;
+    let mut b = BytesMut::from(&buf[..]);
+    if 16 != b.get_u32() || mfro::BOX_TYPE != b.get_u32() {
+        return Ok(None);
+    }
+    let size = mfro::parse(&mut b).size as u64;
+    if size < 8 + 16 || file_size < size {
+        return Ok(None);
+    }
+
+    let mut buf = vec![0_u8; size as usize];
+    r.seek(SeekFrom::End(-(size as i64)))?;
+    r.read_exact(&mut buf)?;
+    let mut b = BytesMut::from(&buf[..]);
+    if size != b.get_u32() as u64 || mfra::BOX_TYPE != b.get_u32() {
+        return Ok(None);
+    }
+
+    Ok(Some(mfra::parse(&mut b)))
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Default, PartialEq)]
+pub struct mfra {
+    pub tfras: Vec<tfra>,
+    pub mfro: mfro,
+}
+
+impl mfra {
+    pub const BOX_TYPE: u32 = types::mfra;
+
+    /// Indexes the first sync sample of every track fragment in
+    /// `fragments`, each given as the file offset of a `moof` and the
+    /// `moof` itself.
+    ///
+    /// `trexs` are the track defaults from `mvex`.
+    pub fn build<'a, I>(trexs: &[trex], fragments: I) -> Self
+        where I: IntoIterator<Item = (u64, &'a moof)> {
+        let mut rst = Self::default();
+        // decode time after the last fragment of each track, for track
+        // fragments without tfdt
+        let mut next_decode_times: Vec<(u32, u64)> = vec![];
+
+        for (moof_offset, moof) in fragments {
+            for (i, traf) in moof.trafs.iter().enumerate() {
+                let track_id = traf.tfhd.track_id;
+                let trex = trexs.iter().find(|it| track_id == it.track_id);
+                let samples = traf.samples(trex);
+
+                let n = match next_decode_times.iter().position(|it| track_id == it.0) {
+                    Some(n) => { n }
+                    None => {
+                        next_decode_times.push((track_id, 0));
+                        next_decode_times.len() - 1
+                    }
+                };
+                let mut dts = traf.tfdt.as_ref().map_or(next_decode_times[n].1, |it| it.base_media_decode_time);
+                next_decode_times[n].1 = dts + samples.iter().map(|it| it.0 as u64).sum::<u64>();
+
+                let mut trun_number = 1;
+                let mut sample_number = 1;
+                for (duration, _, flags, composition_time_offset) in samples {
+                    while traf.truns[trun_number - 1].samples.len() < sample_number {
+                        trun_number += 1;
+                        sample_number = 1;
+                    }
+
+                    // sample_is_non_sync_sample
+                    if 0 == (0x00010000 & flags) {
+                        let entry = TrackFragmentRandomAccessEntry {
+                            time: (dts as i64 + composition_time_offset as i64).max(0) as u64,
+                            moof_offset,
+                            traf_number: 1 + i as u32,
+                            trun_number: trun_number as u32,
+                            sample_number: sample_number as u32,
+                        };
+
+                        match rst.tfras.iter_mut().find(|it| track_id == it.track_id) {
+                            Some(it) => { it.entries.push(entry) }
+                            None => {
+                                rst.tfras.push(tfra {
+                                    track_id,
+                                    entries: vec![entry],
+                                    ..tfra::default()
+                                });
+                            }
+                        }
+                        break;
+                    }
+
+                    dts += duration as u64;
+                    sample_number += 1;
+                }
+            }
+        }
+
+        rst
+    }
+
+    /// Random access entry of `track_id` to start from for presenting
+    /// `time`, in the media timescale of the track.
+    pub fn lookup(&self, track_id: u32, time: u64) -> Option<&TrackFragmentRandomAccessEntry> {
+        self.tfras.iter().find(|it| track_id == it.track_id)?.lookup(time)
+    }
+}
+
+impl Debug for mfra {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for it in &self.tfras {
+            f.write_fmt(format_args!("\t0x{:08x?}: \"tfra\"", tfra::BOX_TYPE))?;
+            f.write_fmt(format_args!("\n{:?}\n", it))?;
+        }
+
+        f.write_fmt(format_args!("\t0x{:08x?}: \"mfro\"", mfro::BOX_TYPE))?;
+        f.write_fmt(format_args!("\n{:?}", self.mfro))?;
+
+        Ok(())
+    }
+}
+
+impl IO for mfra {
+    fn len(&self) -> usize {
+        let mut v = 8 + self.mfro.len();
+
+        for it in &self.tfras {
+            v += 8 + it.len();
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self::default();
+
+        while 8 <= r.len() {
+            let mut b = Object::parse(r);
+
+            match b.box_type {
+                // tfra: Track Fragment Random Access
+                tfra::BOX_TYPE => {
+                    rst.tfras.push(tfra::parse(&mut b.payload));
+                }
+                // mfro: Movie Fragment Random Access Offset
+                mfro::BOX_TYPE => {
+                    rst.mfro = mfro::parse(&mut b.payload);
+                }
+                _ => {}
+            }
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        for it in self.tfras.iter_mut() {
+            w.put(Object::new(tfra::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+
+        self.mfro.size = (8 + self.len()) as u32;
+        w.put(Object::new(mfro::BOX_TYPE, self.mfro.as_bytes()).as_bytes());
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct tfra {
+    base: FullBox,
+
+    pub track_id: u32,
+    pub entries: Vec<TrackFragmentRandomAccessEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackFragmentRandomAccessEntry {
+    pub time: u64,
+    pub moof_offset: u64,
+    pub traf_number: u32,
+    pub trun_number: u32,
+    pub sample_number: u32,
+}
+
+impl tfra {
+    pub const BOX_TYPE: u32 = types::tfra;
+
+    /// Last entry at or before `time`.
+    pub fn lookup(&self, time: u64) -> Option<&TrackFragmentRandomAccessEntry> {
+        self.entries.iter().take_while(|it| it.time <= time).last()
+    }
+
+    fn version(&self) -> u8 {
+        if self.entries.iter().any(|it| (u32::MAX as u64) < it.time || (u32::MAX as u64) < it.moof_offset) {
+            1
+        } else {
+            0
+        }
+    }
+
+    // length_size_of_traf_num, length_size_of_trun_num and
+    // length_size_of_sample_num, the smallest fitting every entry
+    fn length_sizes(&self) -> (u8, u8, u8) {
+        let size_of = |v: u32| -> u8 {
+            match v {
+                0..=0xFF => { 0 }
+                0x100..=0xFFFF => { 1 }
+                0x10000..=0xFFFFFF => { 2 }
+                _ => { 3 }
+            }
+        };
+
+        self.entries.iter().fold((0, 0, 0), |v, it| {
+            (v.0.max(size_of(it.traf_number)), v.1.max(size_of(it.trun_number)), v.2.max(size_of(it.sample_number)))
+        })
+    }
+}
+
+impl Default for tfra {
+    //! extends FullBox(‘tfra’, version, 0) {
+    //!     unsigned int(32) track_ID;
+    //!     const unsigned int(26) reserved = 0;
+    //!     unsigned int(2) length_size_of_traf_num;
+    //!     unsigned int(2) length_size_of_trun_num;
+    //!     unsigned int(2) length_size_of_sample_num;
+    //!     unsigned int(32) number_of_entry;
+    //!     for(i=1; i <= number_of_entry; i++){
+    //!         if(version==1){
+    //!             unsigned int(64) time;
+    //!             unsigned int(64) moof_offset;
+    //!         }else{
+    //!             unsigned int(32) time;
+    //!             unsigned int(32) moof_offset;
+    //!         }
+    //!         unsigned int((length_size_of_traf_num+1) * 8) traf_number;
+    //!         unsigned int((length_size_of_trun_num+1) * 8) trun_number;
+    //!         unsigned int((length_size_of_sample_num+1) * 8) sample_number;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            track_id: 0,
+            entries: vec![],
+        }
+    }
+}
+
+impl Debug for tfra {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\ttrack_id: {:?}", self.track_id))?;
+        f.write_fmt(format_args!("\n\t\tnumber_of_entry: {:?}", self.entries.len()))?;
+        for it in &self.entries {
+            f.write_fmt(format_args!("\n\t\t\t{{"))?;
+            f.write_fmt(format_args!("\n\t\t\t\ttime: {:?}", it.time))?;
+            f.write_fmt(format_args!("\n\t\t\t\tmoof_offset: {:?}", it.moof_offset))?;
+            f.write_fmt(format_args!("\n\t\t\t\ttraf_number: {:?}", it.traf_number))?;
+            f.write_fmt(format_args!("\n\t\t\t\ttrun_number: {:?}", it.trun_number))?;
+            f.write_fmt(format_args!("\n\t\t\t\tsample_number: {:?}", it.sample_number))?;
+            f.write_fmt(format_args!("\n\t\t\t}}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for tfra {
+    fn len(&self) -> usize {
+        let (traf_num, trun_num, sample_num) = self.length_sizes();
+
+        self.base.len() + 12 + self.entries.len() * (
+            if 1 == self.version() { 16 } else { 8 } +
+                (traf_num + 1 + trun_num + 1 + sample_num + 1) as usize
+        )
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let track_id = r.get_u32();
+        let v = r.get_u32();
+        let (traf_num, trun_num, sample_num) = (
+            1 + (0b11 & (v >> 4)) as usize,
+            1 + (0b11 & (v >> 2)) as usize,
+            1 + (0b11 & v) as usize,
+        );
+        let number_of_entry = r.get_u32();
+
+        let mut entries = Vec::with_capacity(number_of_entry as usize);
+        for _ in 0..number_of_entry {
+            let (time, moof_offset) = if 1 == base.version {
+                (r.get_u64(), r.get_u64())
+            } else {
+                (r.get_u32() as u64, r.get_u32() as u64)
+            };
+
+            entries.push(TrackFragmentRandomAccessEntry {
+                time,
+                moof_offset,
+                traf_number: r.get_uint(traf_num) as u32,
+                trun_number: r.get_uint(trun_num) as u32,
+                sample_number: r.get_uint(sample_num) as u32,
+            });
+        }
+
+        Self {
+            base,
+            track_id,
+            entries,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+        let (traf_num, trun_num, sample_num) = self.length_sizes();
+
+        self.base.version = self.version();
+        w.put(self.base.as_bytes());
+
+        w.put_u32(self.track_id);
+        w.put_u32((traf_num as u32) << 4 | (trun_num as u32) << 2 | sample_num as u32);
+        w.put_u32(self.entries.len() as u32);
+        for it in &self.entries {
+            if 1 == self.base.version {
+                w.put_u64(it.time);
+                w.put_u64(it.moof_offset);
+            } else {
+                w.put_u32(it.time as u32);
+                w.put_u32(it.moof_offset as u32);
+            }
+            w.put_uint(it.traf_number as u64, 1 + traf_num as usize);
+            w.put_uint(it.trun_number as u64, 1 + trun_num as usize);
+            w.put_uint(it.sample_number as u64, 1 + sample_num as usize);
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct mfro {
+    base: FullBox,
+
+    /// size of the enclosing `mfra`, box header included
+    pub size: u32,
+}
+
+impl mfro {
+    pub const BOX_TYPE: u32 = types::mfro;
+}
+
+impl Default for mfro {
+    //! extends FullBox(‘mfro’, version, 0) {
+    //!     unsigned int(32) parent_size;
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            size: 0,
+        }
+    }
+}
+
+impl Debug for mfro {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\tsize: {:?}", self.size))?;
+
+        Ok(())
+    }
+}
+
+impl IO for mfro {
+    fn len(&self) -> usize {
+        self.base.len() + 4
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let size = r.get_u32();
+
+        Self {
+            base,
+            size,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+        w.put_u32(self.size);
+
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bytes::{BufMut, BytesMut};
+
+    use crate::{IO, Object};
+    use crate::mfra::{mfra, read, TrackFragmentRandomAccessEntry};
+    use crate::moof::{moof, tfdt, traf, trun};
+
+    #[test]
+    fn chk_mfra() {
+        let mut fragments = vec![];
+        for i in 0..3_u64 {
+            let mut v = moof::default();
+
+            v.mfhd.sequence_number = 1 + i as u32;
+            v.trafs.push({
+                let mut v = traf::default();
+
+                v.tfhd.track_id = 1;
+                v.tfhd.default_sample_duration = Some(3000);
+                v.tfhd.default_sample_flags = Some(0x00010000);
+                v.tfdt = Some(tfdt::new(90000 * 2 * i));
+                for n in 0..2 {
+                    v.truns.push({
+                        let mut v = trun::default();
+
+                        for _ in 0..30 {
+                            v.samples.push((None, Some(1000), None, None));
+                        }
+                        // the sync sample is the 11th of the second run
+                        if 1 == n {
+                            v.samples[10].2 = Some(0x02000000);
+                        }
+
+                        v
+                    });
+                }
+
+                v
+            });
+
+            fragments.push((1000 + 100000 * i, v));
+        }
+
+        let mut b = mfra::build(&[], fragments.iter().map(|(offset, moof)| (*offset, moof)));
+        assert_eq!(1, b.tfras.len());
+        assert_eq!(TrackFragmentRandomAccessEntry {
+            time: 180000 + 3000 * 40,
+            moof_offset: 101000,
+            traf_number: 1,
+            trun_number: 2,
+            sample_number: 11,
+        }, b.tfras[0].entries[1]);
+
+        let w = Object::new(mfra::BOX_TYPE, b.as_bytes()).as_bytes();
+        assert_eq!(8 + b.len(), b.mfro.size as usize);
+
+        let mut obj = Object::parse(&mut w.clone());
+        assert_eq!(mfra::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, mfra::parse(&mut obj.payload));
+
+        let mut file = BytesMut::new();
+        file.put_bytes(0, 1000);
+        file.put(&w[..]);
+        let b = read(&mut Cursor::new(&file[..])).unwrap().unwrap();
+
+        assert_eq!(None, b.lookup(1, 100000).map(|it| it.moof_offset));
+        assert_eq!(Some(101000), b.lookup(1, 300000).map(|it| it.moof_offset));
+        assert_eq!(Some(201000), b.lookup(1, 1000000).map(|it| it.moof_offset));
+        assert_eq!(None, b.lookup(2, 300000));
+        assert_eq!(None, read(&mut Cursor::new(&file[..1000])).unwrap());
+    }
+}
@@ -1,8 +1,11 @@
 use std::fmt::{Debug, Formatter};
+use std::io::ErrorKind;
 
 use bytes::{Buf, BufMut, BytesMut};
 
-use crate::{FullBox, IO, Object};
+use crate::{Error, FullBox, IO, Object};
+use crate::moov::trex;
+use crate::piff;
 use crate::types::types;
 
 pub fn parse(r: &mut BytesMut) -> moof {
@@ -79,16 +82,10 @@ impl IO for moof {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        w.put(Object {
-            box_type: mfhd::BOX_TYPE,
-            payload: self.mfhd.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(mfhd::BOX_TYPE, self.mfhd.as_bytes()).as_bytes());
 
         for it in self.trafs.iter_mut() {
-            w.put(Object {
-                box_type: traf::BOX_TYPE,
-                payload: it.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(traf::BOX_TYPE, it.as_bytes()).as_bytes());
         }
 
         w
@@ -163,10 +160,107 @@ pub struct traf {
     pub tfhd: tfhd,
     pub tfdt: Option<tfdt>,
     pub truns: Vec<trun>,
+    pub senc: Option<senc>,
+
+    /// Smooth Streaming absolute fragment time
+    pub tfxd: Option<piff::tfxd>,
+    /// Smooth Streaming lookahead fragments
+    pub tfrf: Option<piff::tfrf>,
+    /// PIFF sample encryption
+    pub piff_senc: Option<piff::senc>,
 }
 
 impl traf {
     pub const BOX_TYPE: u32 = types::traf;
+
+    /// Duration, size, flags and composition time offset of every sample in
+    /// the track fragment, with absent fields taken from `tfhd` and then
+    /// from the track's `trex`.
+    pub fn samples(&self, trex: Option<&trex>) -> Vec<(u32, u32, u32, i32)> {
+        let default_sample_duration = self.tfhd.default_sample_duration
+            .unwrap_or_else(|| trex.map_or(0, |v| v.default_sample_duration));
+        let default_sample_size = self.tfhd.default_sample_size
+            .unwrap_or_else(|| trex.map_or(0, |v| v.default_sample_size));
+        let default_sample_flags = self.tfhd.default_sample_flags
+            .unwrap_or_else(|| trex.map_or(0, |v| v.default_sample_flags));
+
+        let mut rst = vec![];
+        for it in &self.truns {
+            for (i, (
+                sample_duration,
+                sample_size,
+                sample_flags,
+                sample_composition_time_offset,
+            )) in it.samples.iter().enumerate() {
+                let flags = match (i, it.first_sample_flags, sample_flags) {
+                    (0, Some(v), _) => { v }
+                    (_, _, Some(v)) => { *v }
+                    _ => { default_sample_flags }
+                };
+
+                rst.push((
+                    sample_duration.unwrap_or(default_sample_duration),
+                    sample_size.unwrap_or(default_sample_size),
+                    flags,
+                    sample_composition_time_offset.map_or(0, |v| v as i32),
+                ));
+            }
+        }
+
+        rst
+    }
+
+    /// Earliest presentation time of the track fragment in media timescale,
+    /// counted from the `tfdt` base media decode time.
+    pub fn earliest_presentation_time(&self, trex: Option<&trex>) -> Option<u64> {
+        let mut dts = self.tfdt.as_ref()?.base_media_decode_time as i64;
+        let mut rst = None;
+
+        for (duration, _, _, composition_time_offset) in self.samples(trex) {
+            let pts = dts + composition_time_offset as i64;
+            rst = Some(rst.map_or(pts, |v: i64| v.min(pts)));
+            dts += duration as i64;
+        }
+
+        rst.map(|v| v.max(0) as u64)
+    }
+
+    /// Replaces the PIFF boxes by their CMAF counterparts: `tfxd` by `tfdt`
+    /// unless one is already present, and the PIFF `senc` by the CENC one.
+    ///
+    /// Fails, leaving the boxes as they are, when the PIFF `senc` overrides
+    /// the track encryption parameters, which the CENC `senc` cannot carry.
+    pub fn to_cmaf(&mut self) -> Result<(), Error> {
+        if self.piff_senc.as_ref().is_some_and(|it| it.parameters.is_some()) {
+            return Err(Error::UnsupportedEncryptionOverride { track_id: self.tfhd.track_id });
+        }
+
+        if let Some(tfxd) = self.tfxd.take() {
+            if self.tfdt.is_none() {
+                self.tfdt = Some(tfxd.to_tfdt());
+            }
+        }
+        if let Some(senc) = self.piff_senc.take() {
+            self.senc = senc.to_cenc();
+        }
+
+        Ok(())
+    }
+
+    /// Replaces `tfdt` and the CENC `senc` by their PIFF counterparts, for
+    /// Smooth Streaming clients.
+    ///
+    /// `trex` is the track's defaults from `mvex`, used for the fragment
+    /// duration.
+    pub fn to_piff(&mut self, trex: Option<&trex>) {
+        if let Some(tfdt) = self.tfdt.take() {
+            let fragment_duration = self.samples(trex).iter().map(|it| it.0 as u64).sum();
+            self.tfxd = Some(piff::tfxd::from_tfdt(&tfdt, fragment_duration));
+        }
+        if let Some(senc) = self.senc.take() {
+            self.piff_senc = Some(piff::senc::from_cenc(&senc));
+        }
+    }
 }
 
 impl Default for traf {
@@ -175,6 +269,10 @@ impl Default for traf {
             tfhd: Default::default(),
             tfdt: None,
             truns: vec![],
+            senc: None,
+            tfxd: None,
+            tfrf: None,
+            piff_senc: None,
         }
     }
 }
@@ -194,6 +292,26 @@ impl Debug for traf {
             f.write_fmt(format_args!("\n{:?}", it))?;
         }
 
+        if let Some(senc) = &self.senc {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"senc\"", senc::BOX_TYPE))?;
+            f.write_fmt(format_args!("\n{:?}", senc))?;
+        }
+
+        if let Some(tfxd) = &self.tfxd {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"uuid\" tfxd", types::uuid))?;
+            f.write_fmt(format_args!("\n{:?}", tfxd))?;
+        }
+
+        if let Some(tfrf) = &self.tfrf {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"uuid\" tfrf", types::uuid))?;
+            f.write_fmt(format_args!("\n{:?}", tfrf))?;
+        }
+
+        if let Some(senc) = &self.piff_senc {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"uuid\" senc", types::uuid))?;
+            f.write_fmt(format_args!("\n{:?}", senc))?;
+        }
+
         Ok(())
     }
 }
@@ -210,6 +328,22 @@ impl IO for traf {
             v += 8 + it.len();
         }
 
+        if let Some(senc) = &self.senc {
+            v += 8 + senc.len();
+        }
+
+        if let Some(tfxd) = &self.tfxd {
+            v += 24 + tfxd.len();
+        }
+
+        if let Some(tfrf) = &self.tfrf {
+            v += 24 + tfrf.len();
+        }
+
+        if let Some(senc) = &self.piff_senc {
+            v += 24 + senc.len();
+        }
+
         v
     }
 
@@ -232,6 +366,25 @@ impl IO for traf {
                 trun::BOX_TYPE => {
                     rst.truns.push(trun::parse(&mut b.payload));
                 }
+                // senc: Sample Encryption
+                senc::BOX_TYPE => {
+                    rst.senc = Some(senc::parse(&mut b.payload));
+                }
+                // uuid: PIFF boxes
+                types::uuid => {
+                    match b.usertype {
+                        Some(piff::tfxd::USERTYPE) => {
+                            rst.tfxd = Some(piff::tfxd::parse(&mut b.payload));
+                        }
+                        Some(piff::tfrf::USERTYPE) => {
+                            rst.tfrf = Some(piff::tfrf::parse(&mut b.payload));
+                        }
+                        Some(piff::senc::USERTYPE) => {
+                            rst.piff_senc = Some(piff::senc::parse(&mut b.payload));
+                        }
+                        _ => {}
+                    }
+                }
                 _ => {}
             }
         }
@@ -242,16 +395,10 @@ impl IO for traf {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        w.put(Object {
-            box_type: tfhd::BOX_TYPE,
-            payload: self.tfhd.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(tfhd::BOX_TYPE, self.tfhd.as_bytes()).as_bytes());
 
         if let Some(mut tfdt) = self.tfdt.clone() {
-            w.put(Object {
-                box_type: tfdt::BOX_TYPE,
-                payload: tfdt.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(tfdt::BOX_TYPE, tfdt.as_bytes()).as_bytes());
         }
 
         if let Some(trun) = self.truns.first_mut() {
@@ -259,10 +406,23 @@ impl IO for traf {
         }
 
         for it in self.truns.iter_mut() {
-            w.put(Object {
-                box_type: trun::BOX_TYPE,
-                payload: it.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(trun::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+
+        if let Some(senc) = &mut self.senc {
+            w.put(Object::new(senc::BOX_TYPE, senc.as_bytes()).as_bytes());
+        }
+
+        if let Some(tfxd) = &mut self.tfxd {
+            w.put(Object::uuid(piff::tfxd::USERTYPE, tfxd.as_bytes()).as_bytes());
+        }
+
+        if let Some(tfrf) = &mut self.tfrf {
+            w.put(Object::uuid(piff::tfrf::USERTYPE, tfrf.as_bytes()).as_bytes());
+        }
+
+        if let Some(senc) = &mut self.piff_senc {
+            w.put(Object::uuid(piff::senc::USERTYPE, senc.as_bytes()).as_bytes());
         }
 
         w
@@ -283,6 +443,13 @@ pub struct tfhd {
 
 impl tfhd {
     pub const BOX_TYPE: u32 = types::tfhd;
+
+    /// Whether data offsets without `base_data_offset` are relative to the
+    /// enclosing `moof`, rather than to the end of the previous track
+    /// fragment's data.
+    pub fn default_base_is_moof(&self) -> bool {
+        0 != (self.base.flags & tfhd_flags::DEFAULT_BASE_IS_MOOF)
+    }
 }
 
 #[allow(dead_code)]
@@ -350,7 +517,7 @@ impl IO for tfhd {
         let mut v = self.base.len() + 4;
 
         if let Some(_) = self.base_data_offset {
-            v += 4;
+            v += 8;
         }
         if let Some(_) = self.sample_description_index {
             v += 4;
@@ -762,6 +929,174 @@ impl IO for trun {
     }
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct senc {
+    base: FullBox,
+
+    /// `sample_count` and the entries, kept as stored: their IV size is
+    /// only known from the track encryption parameters
+    data: BytesMut,
+}
+
+/// Initialization vector and subsamples of an encrypted sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SampleEncryptionEntry {
+    /// empty when the track uses a constant IV
+    pub iv: Vec<u8>,
+    /// `BytesOfClearData` and `BytesOfProtectedData` of each subsample
+    pub subsamples: Vec<(u16, u32)>,
+}
+
+impl senc {
+    pub const BOX_TYPE: u32 = types::senc;
+
+    /// `flags`: subsample information follows each IV
+    pub const USE_SUBSAMPLE_ENCRYPTION: u32 = 0x000002;
+
+    pub fn new(samples: Vec<SampleEncryptionEntry>) -> Self {
+        let subsamples = samples.iter().any(|it| !it.subsamples.is_empty());
+        let mut data = BytesMut::new();
+        put_sample_encryption_entries(&mut data, &samples, subsamples);
+
+        Self {
+            base: FullBox::new(0, if subsamples { senc::USE_SUBSAMPLE_ENCRYPTION } else { 0 }),
+            data,
+        }
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        sample_count(&self.data)
+    }
+
+    /// Entries of the samples, with IVs of `per_sample_iv_size` bytes as set
+    /// by the track's `tenc` or the `seig` sample group entry of the
+    /// fragment, failing when they don't span the box.
+    pub fn samples(&self, per_sample_iv_size: u8) -> Result<Vec<SampleEncryptionEntry>, Error> {
+        parse_sample_encryption_entries(
+            &self.data,
+            0 != (senc::USE_SUBSAMPLE_ENCRYPTION & self.base.flags),
+            per_sample_iv_size as usize,
+        )
+    }
+
+    pub(crate) fn entries(&self) -> (bool, &BytesMut) {
+        (0 != (senc::USE_SUBSAMPLE_ENCRYPTION & self.base.flags), &self.data)
+    }
+
+    pub(crate) fn from_entries(subsamples: bool, data: BytesMut) -> Self {
+        Self {
+            base: FullBox::new(0, if subsamples { senc::USE_SUBSAMPLE_ENCRYPTION } else { 0 }),
+            data,
+        }
+    }
+}
+
+impl Default for senc {
+    //! extends FullBox(‘senc’, version, flags) {
+    //!     unsigned int(32) sample_count;
+    //!     {
+    //!         unsigned int(Per_Sample_IV_Size*8) InitializationVector;
+    //!         if (flags & 0x000002) {
+    //!             unsigned int(16) subsample_count;
+    //!             {
+    //!                 unsigned int(16) BytesOfClearData;
+    //!                 unsigned int(32) BytesOfProtectedData;
+    //!             } [subsample_count]
+    //!         }
+    //!     } [sample_count]
+    //! }
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl Debug for senc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\tsample_count: {:?}", self.sample_count()))
+    }
+}
+
+impl IO for senc {
+    fn len(&self) -> usize {
+        self.base.len() + self.data.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            base: FullBox::parse(r),
+            data: r.split_to(r.len()),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+        w.put_slice(&self.data);
+
+        w
+    }
+}
+
+pub(crate) fn sample_count(data: &[u8]) -> u32 {
+    data.get(..4).map_or(0, |it| u32::from_be_bytes([it[0], it[1], it[2], it[3]]))
+}
+
+pub(crate) fn parse_sample_encryption_entries(mut r: &[u8], subsamples: bool, iv_size: usize) -> Result<Vec<SampleEncryptionEntry>, Error> {
+    let invalid = || Error::Io(ErrorKind::InvalidData);
+
+    if r.remaining() < 4 {
+        return Err(invalid());
+    }
+    let sample_count = r.get_u32();
+
+    let mut rst = vec![];
+    for _ in 0..sample_count {
+        if r.remaining() < iv_size + if subsamples { 2 } else { 0 } {
+            return Err(invalid());
+        }
+        let iv = r[..iv_size].to_vec();
+        r.advance(iv_size);
+
+        let mut v = vec![];
+        if subsamples {
+            let subsample_count = r.get_u16() as usize;
+            if r.remaining() < 6 * subsample_count {
+                return Err(invalid());
+            }
+            for _ in 0..subsample_count {
+                v.push((r.get_u16(), r.get_u32()));
+            }
+        }
+
+        rst.push(SampleEncryptionEntry {
+            iv,
+            subsamples: v,
+        });
+    }
+
+    if r.has_remaining() {
+        return Err(invalid());
+    }
+
+    Ok(rst)
+}
+
+pub(crate) fn put_sample_encryption_entries(w: &mut BytesMut, samples: &[SampleEncryptionEntry], subsamples: bool) {
+    w.put_u32(samples.len() as u32);
+    for it in samples {
+        w.put_slice(&it.iv);
+        if subsamples {
+            w.put_u16(it.subsamples.len() as u16);
+            for (clear, protected) in &it.subsamples {
+                w.put_u16(*clear);
+                w.put_u32(*protected);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{IO, Object};
@@ -826,10 +1161,7 @@ mod tests {
                 },
             ],
         };
-        let mut obj = Object::parse(&mut Object {
-            box_type: moof::BOX_TYPE,
-            payload: b.as_bytes(),
-        }.as_bytes());
+        let mut obj = Object::parse(&mut Object::new(moof::BOX_TYPE, b.as_bytes()).as_bytes());
 
         assert_eq!(moof::BOX_TYPE, obj.box_type);
         assert_eq!(b.len(), obj.payload.len());
@@ -1,10 +1,14 @@
 use std::cmp::min;
 use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::time::Duration;
 
 use bytes::{Buf, BufMut, BytesMut};
 
-use crate::{FullBox, IO, Object};
+use crate::{Error, FullBox, IO, Object};
 use crate::types::types;
+use crate::udta::{Chapter, chpl, ilst, meta, MetadataValue, udta};
 
 pub fn parse(r: &mut BytesMut) -> moov {
     moov::parse(r)
@@ -15,11 +19,253 @@ pub fn parse(r: &mut BytesMut) -> moov {
 pub struct moov {
     pub mvhd: mvhd,
     pub traks: Vec<trak>,
-    pub mvex: mvex,
+    /// present in fragmented files only
+    pub mvex: Option<mvex>,
+    pub udta: Option<udta>,
 }
 
 impl moov {
     pub const BOX_TYPE: u32 = types::moov;
+
+    /// iTunes-style metadata of the movie, if any.
+    pub fn ilst(&self) -> Option<&ilst> {
+        self.udta.as_ref()?.meta.as_ref()?.ilst.as_ref()
+    }
+
+    /// iTunes-style metadata of the movie, adding the `udta`, `meta` and
+    /// `ilst` boxes when missing.
+    pub fn ilst_mut(&mut self) -> &mut ilst {
+        let meta = self.udta.get_or_insert_with(Default::default).meta.get_or_insert_with(meta::mdir);
+
+        meta.ilst.get_or_insert_with(Default::default)
+    }
+
+    /// Value of the `item_type` tag, such as `types::_nam` for the title.
+    pub fn tag(&self, item_type: u32) -> Option<&MetadataValue> {
+        self.ilst()?.get(item_type)
+    }
+
+    pub fn set_tag(&mut self, item_type: u32, value: MetadataValue) {
+        self.ilst_mut().set(item_type, value);
+    }
+
+    pub fn remove_tag(&mut self, item_type: u32) {
+        if let Some(it) = self.udta.as_mut().and_then(|it| it.meta.as_mut()).and_then(|it| it.ilst.as_mut()) {
+            it.remove(item_type);
+        }
+    }
+
+    /// Track ID for a new track: `mvhd::next_track_id`, unless a track
+    /// already uses it or a larger ID, or when it is 0xFFFFFFFF, one past
+    /// the largest track ID. Once that is taken, the smallest unused ID.
+    pub fn next_track_id(&self) -> u32 {
+        let max = self.traks.iter().map(|it| it.tkhd.track_id).max().unwrap_or(0);
+
+        match max.checked_add(1) {
+            // 0xFFFFFFFF: search for an unused track ID
+            Some(v) if u32::MAX == self.mvhd.next_track_id => { v }
+            Some(v) => { v.max(self.mvhd.next_track_id).max(1) }
+            None => {
+                (1..u32::MAX).find(|v| self.traks.iter().all(|it| *v != it.tkhd.track_id)).unwrap_or(u32::MAX)
+            }
+        }
+    }
+
+    /// Updates `mvhd::next_track_id` to the next track ID, or to 0xFFFFFFFF
+    /// when a track uses a larger ID.
+    fn update_next_track_id(&mut self) {
+        let v = self.next_track_id();
+
+        self.mvhd.next_track_id = if self.traks.iter().any(|it| v < it.tkhd.track_id) { u32::MAX } else { v };
+    }
+
+    /// Checks that every track referenced from a `tref` is in the movie.
+    pub fn validate_track_references(&self) -> Result<(), Error> {
+        for it in &self.traks {
+            let Some(tref) = &it.tref else { continue };
+
+            for reference in &tref.references {
+                for track_id in &reference.track_ids {
+                    if !self.traks.iter().any(|it| *track_id == it.tkhd.track_id) {
+                        return Err(Error::UnknownTrackReference {
+                            track_id: it.tkhd.track_id,
+                            reference_type: reference.reference_type.into(),
+                            reference_track_id: *track_id,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// QuickTime chapter track, a `text` track referenced through `chap`.
+    pub fn chapter_track(&self) -> Option<&trak> {
+        let track_id = self.traks.iter()
+            .filter_map(|it| it.tref.as_ref())
+            .find_map(|it| it.track_ids(TrackReferenceType::Chap).first())?;
+
+        self.traks.iter().find(|it| *track_id == it.tkhd.track_id && types::text == it.mdia.hdlr.handler_type)
+    }
+
+    /// Chapters of the movie, read from the samples of the QuickTime chapter
+    /// track in `r`, or taken from the Nero `chpl` box when there is none.
+    pub fn chapters<R: Read + Seek>(&self, r: &mut R) -> std::io::Result<Vec<Chapter>> {
+        let Some(trak) = self.chapter_track() else {
+            return Ok(self.udta.as_ref().and_then(|it| it.chpl.as_ref()).map_or_else(Vec::new, |it| it.chapters.clone()));
+        };
+        let timescale = trak.mdia.mdhd.timescale.max(1) as u128;
+
+        let mut rst = vec![];
+        for (i, it) in trak.mdia.minf.stbl.samples().into_iter().enumerate() {
+            let mut buf = vec![0; it.size as usize];
+            r.seek(SeekFrom::Start(it.offset))?;
+            r.read_exact(&mut buf)?;
+
+            let title = chapter_title(&buf);
+            // the empty sample in front of a first chapter starting later
+            if 0 == i && title.is_empty() && 0 < it.duration {
+                continue;
+            }
+
+            rst.push(Chapter {
+                start: Duration::from_nanos((it.decode_time as u128 * 1_000_000_000 / timescale) as u64),
+                title,
+            });
+        }
+
+        Ok(rst)
+    }
+
+    /// Sets the chapters of the movie, both as a Nero `chpl` box and as a
+    /// QuickTime chapter track referenced by every video track, or by the
+    /// first track when there is no video.
+    ///
+    /// Chapters are ordered by their start, and an empty text sample spans
+    /// the time before the first one.
+    ///
+    /// Returns the samples of the chapter track, a single chunk that must be
+    /// written at the file `offset`, typically in an `mdat`.
+    pub fn set_chapters(&mut self, chapters: &[Chapter], offset: u64) -> BytesMut {
+        if let Some(track_id) = self.chapter_track().map(|it| it.tkhd.track_id) {
+            self.traks.retain(|it| track_id != it.tkhd.track_id);
+        }
+        for it in self.traks.iter_mut() {
+            if let Some(tref) = &mut it.tref {
+                tref.references.retain(|it| TrackReferenceType::Chap != it.reference_type);
+                if tref.references.is_empty() {
+                    it.tref = None;
+                }
+            }
+        }
+
+        if chapters.is_empty() {
+            if let Some(it) = &mut self.udta {
+                it.chpl = None;
+            }
+
+            return BytesMut::new();
+        }
+        let mut chapters = chapters.to_vec();
+        chapters.sort_by_key(|it| it.start);
+        self.udta.get_or_insert_with(Default::default).chpl = Some(chpl::new(&chapters));
+
+        let track_id = self.next_track_id();
+
+        let duration = self.mvhd.duration as u128 * 1000 / self.mvhd.timescale.max(1) as u128;
+        let mut w = BytesMut::new();
+        let mut trak = trak::default();
+
+        trak.tkhd.base.flags = 0;
+        trak.tkhd.track_id = track_id;
+        trak.tkhd.duration = self.mvhd.duration;
+        trak.tkhd.volume = 0;
+        trak.mdia.mdhd.timescale = 1000;
+        trak.mdia.mdhd.duration = duration as u64;
+        trak.mdia.hdlr = hdlr::text("Chapters");
+        trak.mdia.minf.mhd = MediaInformationHeader::nmhd(nmhd::default());
+
+        let stbl = &mut trak.mdia.minf.stbl;
+        stbl.stsd.entries.push(SampleEntry::Unknown {
+            base: std::boxed::Box::new(SampleEntry::Base {
+                handler_type: types::text,
+                data_reference_index: 1,
+            }),
+            // QuickTime text sample description, all defaults
+            ext: BytesMut::from(&[0; 44][..]),
+        });
+        let mut samples = vec![];
+        let first = chapters[0].start.as_millis();
+        if 0 < first {
+            w.put_u16(0);
+            samples.push(Sample {
+                offset,
+                size: 2,
+                decode_time: 0,
+                duration: first.min(u32::MAX as u128) as u32,
+                composition_offset: 0,
+                is_sync: true,
+                description_index: 1,
+            });
+        }
+        for (i, it) in chapters.iter().enumerate() {
+            let start = it.start.as_millis();
+            let end = chapters.get(i + 1).map_or(duration, |it| it.start.as_millis());
+            let delta = end.saturating_sub(start).min(u32::MAX as u128) as u32;
+
+            let title = &it.title.as_bytes()[..it.title.len().min(u16::MAX as usize)];
+            let size = w.len();
+            w.put_u16(title.len() as u16);
+            w.put_slice(title);
+            // encd: text encoding, UTF-8
+            w.put_u32(12);
+            w.put_u32(types::encd);
+            w.put_u32(0x00000100);
+
+            samples.push(Sample {
+                offset: offset + size as u64,
+                size: (w.len() - size) as u32,
+                decode_time: start as u64,
+                duration: delta,
+                composition_offset: 0,
+                is_sync: true,
+                description_index: 1,
+            });
+        }
+        stbl.set_samples(&samples);
+
+        let video = self.traks.iter().any(|it| types::vide == it.mdia.hdlr.handler_type);
+        for (i, it) in self.traks.iter_mut().enumerate() {
+            if if video { types::vide == it.mdia.hdlr.handler_type } else { 0 == i } {
+                it.tref.get_or_insert_with(Default::default).add(TrackReferenceType::Chap, track_id);
+            }
+        }
+        self.traks.push(trak);
+        self.update_next_track_id();
+
+        w
+    }
+}
+
+/// Title of a QuickTime text sample: a 16-bit length followed by UTF-8, or
+/// UTF-16 when it starts with a byte order mark, then optional atoms.
+fn chapter_title(sample: &[u8]) -> String {
+    if sample.len() < 2 {
+        return "".to_owned();
+    }
+    let n = min(u16::from_be_bytes([sample[0], sample[1]]) as usize, sample.len() - 2);
+    let text = &sample[2..2 + n];
+
+    match text {
+        [0xFE, 0xFF, v @ ..] => {
+            String::from_utf16_lossy(&v.chunks_exact(2).map(|it| u16::from_be_bytes([it[0], it[1]])).collect::<Vec<_>>())
+        }
+        [0xFF, 0xFE, v @ ..] => {
+            String::from_utf16_lossy(&v.chunks_exact(2).map(|it| u16::from_le_bytes([it[0], it[1]])).collect::<Vec<_>>())
+        }
+        _ => { String::from_utf8_lossy(text).into_owned() }
+    }
 }
 
 impl Default for moov {
@@ -27,7 +273,8 @@ impl Default for moov {
         Self {
             mvhd: Default::default(),
             traks: vec![],
-            mvex: Default::default()
+            mvex: None,
+            udta: None,
         }
     }
 }
@@ -40,8 +287,14 @@ impl Debug for moov {
             f.write_fmt(format_args!("\n\t0x{:08x?}: \"trak\"\n", trak::BOX_TYPE))?;
             it.fmt(f)?;
         }
-        f.write_fmt(format_args!("\n\t0x{:08x?}: \"mvex\"\n", mvex::BOX_TYPE))?;
-        self.mvex.fmt(f)?;
+        if let Some(it) = &self.mvex {
+            f.write_fmt(format_args!("\n\t0x{:08x?}: \"mvex\"\n", mvex::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
+        if let Some(it) = &self.udta {
+            f.write_fmt(format_args!("\n\t0x{:08x?}: \"udta\"\n", udta::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
 
         Ok(())
     }
@@ -49,7 +302,14 @@ impl Debug for moov {
 
 impl IO for moov {
     fn len(&self) -> usize {
-        let mut v = 16 + self.mvhd.len() + self.mvex.len();
+        let mut v = 8 + self.mvhd.len();
+
+        if let Some(it) = &self.mvex {
+            v += 8 + it.len();
+        }
+        if let Some(it) = &self.udta {
+            v += 8 + it.len();
+        }
 
         for it in &self.traks {
             v += 8 + it.len();
@@ -74,7 +334,11 @@ impl IO for moov {
                 }
                 // mvex: MovieExtends
                 mvex::BOX_TYPE => {
-                    rst.mvex = mvex::parse(&mut b.payload);
+                    rst.mvex = Some(mvex::parse(&mut b.payload));
+                }
+                // udta: User Data
+                udta::BOX_TYPE => {
+                    rst.udta = Some(udta::parse(&mut b.payload));
                 }
                 _ => {}
             }
@@ -86,24 +350,18 @@ impl IO for moov {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        if self.mvhd.next_track_id < self.traks.len() as u32 {
-            self.mvhd.next_track_id = self.traks.len() as u32;
-        }
+        self.update_next_track_id();
 
-        w.put(Object {
-            box_type: mvhd::BOX_TYPE,
-            payload: self.mvhd.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(mvhd::BOX_TYPE, self.mvhd.as_bytes()).as_bytes());
         for it in self.traks.iter_mut() {
-            w.put(Object {
-                box_type: trak::BOX_TYPE,
-                payload: it.as_bytes(),
-            }.as_bytes());
-        }
-        w.put(Object {
-            box_type: mvex::BOX_TYPE,
-            payload: self.mvex.as_bytes(),
-        }.as_bytes());
+            w.put(Object::new(trak::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        if let Some(it) = &mut self.mvex {
+            w.put(Object::new(mvex::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        if let Some(it) = &mut self.udta {
+            w.put(Object::new(udta::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
 
         w
     }
@@ -287,20 +545,142 @@ impl IO for mvhd {
 #[derive(PartialEq)]
 pub struct trak {
     pub tkhd: tkhd,
+    pub tref: Option<tref>,
     pub edts: Option<edts>,
     pub mdia: mdia,
+    pub udta: Option<udta>,
 }
 
 impl trak {
     pub const BOX_TYPE: u32 = types::trak;
+
+    /// Display aspect ratio of the track's first visual sample entry.
+    pub fn display_aspect_ratio(&self) -> Option<(u64, u64)> {
+        self.mdia.minf.stbl.stsd.entries.first()?.display_aspect_ratio()
+    }
+
+    /// HDR transfer function signalled by the track's first visual sample
+    /// entry, if any.
+    pub fn hdr_transfer(&self) -> Option<HdrTransfer> {
+        self.mdia.minf.stbl.stsd.entries.first()?.hdr_transfer()
+    }
+
+    /// Presentation timeline of the track, one entry per `elst` edit, or a
+    /// single edit presenting the whole media when there is no edit list.
+    ///
+    /// `movie_timescale` is the timescale of `mvhd`.
+    pub fn edits(&self, movie_timescale: u32) -> Vec<Edit> {
+        let samples = self.mdia.minf.stbl.samples();
+        let media_timescale = self.mdia.mdhd.timescale.max(1) as u128;
+        let movie_timescale = movie_timescale.max(1) as u128;
+
+        let entries = match self.edts.as_ref().and_then(|it| it.elst.as_ref()) {
+            Some(it) => { it.entries.clone() }
+            None => {
+                let duration = samples.iter().map(|it| it.duration as u64).sum::<u64>().max(self.mdia.mdhd.duration);
+                vec![((duration as u128 * movie_timescale / media_timescale) as u64, 0, 1)]
+            }
+        };
+
+        let mut rst = vec![];
+        let mut start = 0;
+        for (segment_duration, media_time, media_rate) in entries {
+            let edit = if media_time < 0 {
+                Edit {
+                    start,
+                    duration: segment_duration,
+                    media_time: None,
+                    media_rate,
+                    samples: 0..0,
+                }
+            } else {
+                let begin = media_time;
+                let end = if 0 == media_rate {
+                    begin + 1
+                } else {
+                    begin + (segment_duration as u128 * media_timescale / movie_timescale) as i64
+                };
+                let presented = samples.iter().enumerate()
+                    .filter(|(_, it)| it.composition_time() < end && begin < it.composition_time() + it.duration.max(1) as i64)
+                    .map(|(i, _)| i);
+                let (first, last) = presented.fold((usize::MAX, 0), |(first, last), i| (first.min(i), last.max(i + 1)));
+
+                Edit {
+                    start,
+                    duration: segment_duration,
+                    media_time: Some(media_time as u64),
+                    media_rate,
+                    samples: if first < last { first..last } else { 0..0 },
+                }
+            };
+
+            start += segment_duration;
+            rst.push(edit);
+        }
+
+        rst
+    }
+
+    /// Movie presentation time, in `movie_timescale`, of the media
+    /// composition time `media_time`, or `None` when no edit presents it.
+    pub fn presentation_time(&self, media_time: u64, movie_timescale: u32) -> Option<u64> {
+        let media_timescale = self.mdia.mdhd.timescale.max(1) as u128;
+
+        self.edits(movie_timescale).iter().find_map(|it| {
+            let begin = it.media_time?;
+            if 0 == it.media_rate {
+                return if begin == media_time { Some(it.start) } else { None };
+            }
+            let end = begin + (it.duration as u128 * media_timescale / movie_timescale.max(1) as u128) as u64;
+
+            if begin <= media_time && media_time < end {
+                Some(it.start + ((media_time - begin) as u128 * movie_timescale as u128 / media_timescale) as u64)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Media composition time presented at the movie time `presentation_time`,
+    /// in `movie_timescale`, or `None` during an empty edit or past the end.
+    pub fn media_time(&self, presentation_time: u64, movie_timescale: u32) -> Option<u64> {
+        let media_timescale = self.mdia.mdhd.timescale.max(1) as u128;
+        let edit = self.edits(movie_timescale).into_iter()
+            .find(|it| it.start <= presentation_time && presentation_time < it.start + it.duration)?;
+        let begin = edit.media_time?;
+
+        if 0 == edit.media_rate {
+            Some(begin)
+        } else {
+            Some(begin + ((presentation_time - edit.start) as u128 * media_timescale / movie_timescale.max(1) as u128) as u64)
+        }
+    }
+}
+
+/// An edit of the presentation timeline of a track.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit {
+    /// presentation start, in the movie timescale
+    pub start: u64,
+    /// in the movie timescale
+    pub duration: u64,
+    /// media composition time presented at `start`, in the media timescale;
+    /// `None` for an empty edit
+    pub media_time: Option<u64>,
+    /// 0 for a dwell edit, holding the sample at `media_time` for `duration`
+    pub media_rate: i16,
+    /// samples presented during the edit, in decoding order
+    pub samples: Range<usize>,
 }
 
 impl Default for trak {
     fn default() -> Self {
         Self {
             tkhd: Default::default(),
+            tref: None,
             edts: None,
             mdia: Default::default(),
+            udta: None,
         }
     }
 }
@@ -309,12 +689,20 @@ impl Debug for trak {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("\t\t0x{:08x?}: \"tkhd\"\n", tkhd::BOX_TYPE))?;
         self.tkhd.fmt(f)?;
+        if let Some(it) = &self.tref {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"tref\"\n", tref::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
         if let Some(edts) = &self.edts {
             f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"edts\"\n", edts::BOX_TYPE))?;
             edts.fmt(f)?;
         }
         f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"mdia\"\n", mdia::BOX_TYPE))?;
         self.mdia.fmt(f)?;
+        if let Some(it) = &self.udta {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"udta\"\n", udta::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
 
         Ok(())
     }
@@ -322,7 +710,10 @@ impl Debug for trak {
 
 impl IO for trak {
     fn len(&self) -> usize {
-        16 + self.tkhd.len() + self.mdia.len() + if let Some(edts) = &self.edts { 8 + edts.len() } else { 0 }
+        16 + self.tkhd.len() + self.mdia.len()
+            + if let Some(tref) = &self.tref { 8 + tref.len() } else { 0 }
+            + if let Some(edts) = &self.edts { 8 + edts.len() } else { 0 }
+            + if let Some(udta) = &self.udta { 8 + udta.len() } else { 0 }
     }
 
     fn parse(r: &mut BytesMut) -> Self {
@@ -344,6 +735,14 @@ impl IO for trak {
                 edts::BOX_TYPE => {
                     rst.edts = Some(edts::parse(&mut b.payload));
                 }
+                // tref: Track Reference
+                tref::BOX_TYPE => {
+                    rst.tref = Some(tref::parse(&mut b.payload));
+                }
+                // udta: User Data
+                udta::BOX_TYPE => {
+                    rst.udta = Some(udta::parse(&mut b.payload));
+                }
                 _ => {}
             }
         }
@@ -354,22 +753,159 @@ impl IO for trak {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        w.put(Object {
-            box_type: tkhd::BOX_TYPE,
-            payload: self.tkhd.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(tkhd::BOX_TYPE, self.tkhd.as_bytes()).as_bytes());
+
+        if let Some(it) = &mut self.tref {
+            w.put(Object::new(tref::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
 
         if let Some(mut edts) = self.edts.clone() {
-            w.put(Object {
-                box_type: edts::BOX_TYPE,
-                payload: edts.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(edts::BOX_TYPE, edts.as_bytes()).as_bytes());
         }
 
-        w.put(Object {
-            box_type: mdia::BOX_TYPE,
-            payload: self.mdia.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(mdia::BOX_TYPE, self.mdia.as_bytes()).as_bytes());
+
+        if let Some(it) = &mut self.udta {
+            w.put(Object::new(udta::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Default, PartialEq)]
+pub struct tref {
+    pub references: Vec<TrackReference>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackReference {
+    pub reference_type: TrackReferenceType,
+    pub track_ids: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrackReferenceType {
+    /// QuickTime chapter track
+    Chap,
+    /// original media of a hint track
+    Hint,
+    /// the track describes the referenced track, such as timed metadata
+    Cdsc,
+    /// synchronization source
+    Sync,
+    /// auxiliary depth video
+    Vdep,
+    /// subtitle, timed text or overlay graphics
+    Subt,
+    Other(u32),
+}
+
+impl From<u32> for TrackReferenceType {
+    fn from(v: u32) -> Self {
+        match v {
+            types::chap => { TrackReferenceType::Chap }
+            types::hint => { TrackReferenceType::Hint }
+            types::cdsc => { TrackReferenceType::Cdsc }
+            types::sync => { TrackReferenceType::Sync }
+            types::vdep => { TrackReferenceType::Vdep }
+            types::subt => { TrackReferenceType::Subt }
+            _ => { TrackReferenceType::Other(v) }
+        }
+    }
+}
+
+impl From<TrackReferenceType> for u32 {
+    fn from(v: TrackReferenceType) -> Self {
+        match v {
+            TrackReferenceType::Chap => { types::chap }
+            TrackReferenceType::Hint => { types::hint }
+            TrackReferenceType::Cdsc => { types::cdsc }
+            TrackReferenceType::Sync => { types::sync }
+            TrackReferenceType::Vdep => { types::vdep }
+            TrackReferenceType::Subt => { types::subt }
+            TrackReferenceType::Other(v) => { v }
+        }
+    }
+}
+
+impl tref {
+    pub const BOX_TYPE: u32 = types::tref;
+
+    /// Tracks referenced with `reference_type`.
+    pub fn track_ids(&self, reference_type: TrackReferenceType) -> &[u32] {
+        self.references.iter().find(|it| reference_type == it.reference_type).map_or(&[], |it| &it.track_ids)
+    }
+
+    /// Adds a reference to `track_id`, unless it is already there.
+    pub fn add(&mut self, reference_type: TrackReferenceType, track_id: u32) {
+        match self.references.iter_mut().find(|it| reference_type == it.reference_type) {
+            Some(it) if it.track_ids.contains(&track_id) => {}
+            Some(it) => { it.track_ids.push(track_id) }
+            None => {
+                self.references.push(TrackReference {
+                    reference_type,
+                    track_ids: vec![track_id],
+                })
+            }
+        }
+    }
+}
+
+impl Debug for tref {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, it) in self.references.iter().enumerate() {
+            if 0 < i {
+                f.write_fmt(format_args!("\n"))?;
+            }
+            let reference_type = u32::from(it.reference_type);
+            if let Ok(str) = std::str::from_utf8(&reference_type.to_be_bytes()) {
+                f.write_fmt(format_args!("\t\t\t{:}: {:?}", str, it.track_ids))?;
+            } else {
+                f.write_fmt(format_args!("\t\t\t0x{:08x?}: {:?}", reference_type, it.track_ids))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for tref {
+    fn len(&self) -> usize {
+        self.references.iter().map(|it| 8 + 4 * it.track_ids.len()).sum()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self::default();
+
+        while 8 <= r.len() {
+            let mut b = Object::parse(r);
+
+            let mut track_ids = vec![];
+            while 4 <= b.payload.len() {
+                track_ids.push(b.payload.get_u32());
+            }
+            rst.references.push(TrackReference {
+                reference_type: b.box_type.into(),
+                track_ids,
+            });
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        for it in &self.references {
+            let mut payload = BytesMut::new();
+            for track_id in &it.track_ids {
+                payload.put_u32(*track_id);
+            }
+
+            w.put(Object::new(it.reference_type.into(), payload).as_bytes());
+        }
 
         w
     }
@@ -627,10 +1163,7 @@ impl IO for edts {
         let mut w = BytesMut::new();
 
         if let Some(mut elst) = self.elst.clone() {
-            w.put(Object {
-                box_type: elst::BOX_TYPE,
-                payload: elst.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(elst::BOX_TYPE, elst.as_bytes()).as_bytes());
         }
 
         w
@@ -646,6 +1179,14 @@ pub struct elst {
 
 impl elst {
     pub const BOX_TYPE: u32 = types::elst;
+
+    /// Version 1 when an entry does not fit in 32 bits.
+    fn version(&self) -> u8 {
+        let large = self.entries.iter()
+            .any(|it| (u32::MAX as u64) < it.0 || it.1 < i32::MIN as i64 || (i32::MAX as i64) < it.1);
+
+        if large { 1 } else { self.base.version }
+    }
 }
 
 impl Default for elst {
@@ -670,7 +1211,7 @@ impl Debug for elst {
 
 impl IO for elst {
     fn len(&self) -> usize {
-        self.base.len() + 4 + self.entries.len() * (12 + 8 * self.base.version as usize)
+        self.base.len() + 4 + self.entries.len() * (12 + 8 * self.version() as usize)
     }
 
     fn parse(r: &mut BytesMut) -> Self {
@@ -701,6 +1242,7 @@ impl IO for elst {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
+        self.base.version = self.version();
         w.put(self.base.as_bytes());
 
         w.put_u32(self.entries.len() as u32);
@@ -762,6 +1304,7 @@ impl IO for mdia {
 
     fn parse(r: &mut BytesMut) -> Self {
         let mut rst = Self::default();
+        let mut minf_payload = None;
 
         while 0 < r.len() {
             let mut b = Object::parse(r);
@@ -777,30 +1320,32 @@ impl IO for mdia {
                 }
                 // minf: Media Information
                 minf::BOX_TYPE => {
-                    rst.minf = minf::parse(&mut b.payload);
+                    minf_payload = Some(b.payload);
                 }
                 _ => {}
             }
         }
 
+        // QuickTime sound descriptions extend the entries of a version 0
+        // `stsd` under a QuickTime media handler
+        let quicktime = types::mhlr == rst.hdlr.component_type;
+        if let Some(mut v) = minf_payload {
+            rst.minf = minf::parse_with(&mut v, quicktime);
+        }
+        let quicktime = quicktime && 0 == rst.minf.stbl.stsd.base.version;
+        for it in rst.minf.stbl.stsd.entries.iter_mut() {
+            it.resolve(rst.hdlr.handler_type, quicktime);
+        }
+
         rst
     }
 
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        w.put(Object {
-            box_type: mdhd::BOX_TYPE,
-            payload: self.mdhd.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: hdlr::BOX_TYPE,
-            payload: self.hdlr.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: minf::BOX_TYPE,
-            payload: self.minf.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(mdhd::BOX_TYPE, self.mdhd.as_bytes()).as_bytes());
+        w.put(Object::new(hdlr::BOX_TYPE, self.hdlr.as_bytes()).as_bytes());
+        w.put(Object::new(minf::BOX_TYPE, self.minf.as_bytes()).as_bytes());
 
         w
     }
@@ -965,6 +1510,9 @@ impl IO for mdhd {
 pub struct hdlr {
     base: FullBox,
 
+    /// QuickTime component type, `types::mhlr` for a media handler and 0
+    /// in ISO files
+    pub component_type: u32,
     pub handler_type: u32,
     pub name: String,
 }
@@ -990,6 +1538,13 @@ impl hdlr {
 
         v
     }
+    pub fn text(name: &str) -> Self {
+        Self {
+            handler_type: types::text,
+            name: format!("{}\0", name),
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for hdlr {
@@ -1002,6 +1557,7 @@ impl Default for hdlr {
     fn default() -> Self {
         Self {
             base: FullBox::new(0, 0),
+            component_type: 0,
             handler_type: 0,
             name: "".to_owned(),
         }
@@ -1029,13 +1585,14 @@ impl IO for hdlr {
     fn parse(r: &mut BytesMut) -> Self {
         let base = FullBox::parse(r);
 
-        let _ = r.get_u32();
+        let component_type = r.get_u32();
         let handler_type = r.get_u32();
         let _ = r.split_to(12);
         let name = std::str::from_utf8(r.split_to(r.len()).chunk()).unwrap().to_owned();
 
         Self {
             base,
+            component_type,
             handler_type,
             name,
         }
@@ -1046,7 +1603,7 @@ impl IO for hdlr {
 
         w.put(self.base.as_bytes());
 
-        w.put_u32(0);
+        w.put_u32(self.component_type);
         w.put_u32(self.handler_type);
         w.put_u32(0);
         w.put_u32(0);
@@ -1067,6 +1624,46 @@ pub struct minf {
 
 impl minf {
     pub const BOX_TYPE: u32 = types::minf;
+
+    /// Parses the box, reading the sample descriptions with the QuickTime
+    /// sound description layouts when `quicktime`.
+    fn parse_with(r: &mut BytesMut, quicktime: bool) -> Self {
+        let mut rst = Self::default();
+
+        while 0 < r.len() {
+            let mut b = Object::parse(r);
+
+            match b.box_type {
+                // vmhd: Video Media Header
+                vmhd::BOX_TYPE => {
+                    rst.mhd = MediaInformationHeader::vmhd(vmhd::parse(&mut b.payload));
+                }
+                // smhd: Sound Media Header
+                smhd::BOX_TYPE => {
+                    rst.mhd = MediaInformationHeader::smhd(smhd::parse(&mut b.payload));
+                }
+                // hmhd: Hint Media Header
+                hmhd::BOX_TYPE => {
+                    rst.mhd = MediaInformationHeader::hmhd(hmhd::parse(&mut b.payload));
+                }
+                // nmhd: Null Media Header
+                nmhd::BOX_TYPE => {
+                    rst.mhd = MediaInformationHeader::nmhd(nmhd::parse(&mut b.payload));
+                }
+                // dinf: Data Information
+                dinf::BOX_TYPE => {
+                    rst.dinf = dinf::parse(&mut b.payload);
+                }
+                // stbl: Sample Table
+                stbl::BOX_TYPE => {
+                    rst.stbl = stbl::parse_with(&mut b.payload, quicktime);
+                }
+                _ => {}
+            }
+        }
+
+        rst
+    }
 }
 
 impl Default for minf {
@@ -1125,82 +1722,30 @@ impl IO for minf {
     }
 
     fn parse(r: &mut BytesMut) -> Self {
-        let mut rst = Self::default();
+        Self::parse_with(r, false)
+    }
 
-        while 0 < r.len() {
-            let mut b = Object::parse(r);
-
-            match b.box_type {
-                // vmhd: Video Media Header
-                vmhd::BOX_TYPE => {
-                    rst.mhd = MediaInformationHeader::vmhd(vmhd::parse(&mut b.payload));
-                }
-                // smhd: Sound Media Header
-                smhd::BOX_TYPE => {
-                    rst.mhd = MediaInformationHeader::smhd(smhd::parse(&mut b.payload));
-                }
-                // hmhd: Hint Media Header
-                hmhd::BOX_TYPE => {
-                    rst.mhd = MediaInformationHeader::hmhd(hmhd::parse(&mut b.payload));
-                }
-                // nmhd: Null Media Header
-                nmhd::BOX_TYPE => {
-                    rst.mhd = MediaInformationHeader::nmhd(nmhd::parse(&mut b.payload));
-                }
-                // dinf: Data Information
-                dinf::BOX_TYPE => {
-                    rst.dinf = dinf::parse(&mut b.payload);
-                }
-                // stbl: Sample Table
-                stbl::BOX_TYPE => {
-                    rst.stbl = stbl::parse(&mut b.payload);
-                }
-                _ => {}
-            }
-        }
-
-        rst
-    }
-
-    fn as_bytes(&mut self) -> BytesMut {
-        let mut w = BytesMut::new();
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
 
         match self.mhd.clone() {
             MediaInformationHeader::Unknown => {}
             MediaInformationHeader::vmhd(mut v) => {
-                w.put(Object {
-                    box_type: vmhd::BOX_TYPE,
-                    payload: v.as_bytes(),
-                }.as_bytes());
+                w.put(Object::new(vmhd::BOX_TYPE, v.as_bytes()).as_bytes());
             }
             MediaInformationHeader::smhd(mut v) => {
-                w.put(Object {
-                    box_type: smhd::BOX_TYPE,
-                    payload: v.as_bytes(),
-                }.as_bytes());
+                w.put(Object::new(smhd::BOX_TYPE, v.as_bytes()).as_bytes());
             }
             MediaInformationHeader::hmhd(mut v) => {
-                w.put(Object {
-                    box_type: hmhd::BOX_TYPE,
-                    payload: v.as_bytes(),
-                }.as_bytes());
+                w.put(Object::new(hmhd::BOX_TYPE, v.as_bytes()).as_bytes());
             }
             MediaInformationHeader::nmhd(mut v) => {
-                w.put(Object {
-                    box_type: nmhd::BOX_TYPE,
-                    payload: v.as_bytes(),
-                }.as_bytes());
+                w.put(Object::new(nmhd::BOX_TYPE, v.as_bytes()).as_bytes());
             }
         }
 
-        w.put(Object {
-            box_type: dinf::BOX_TYPE,
-            payload: self.dinf.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: stbl::BOX_TYPE,
-            payload: self.stbl.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(dinf::BOX_TYPE, self.dinf.as_bytes()).as_bytes());
+        w.put(Object::new(stbl::BOX_TYPE, self.stbl.as_bytes()).as_bytes());
 
         w
     }
@@ -1570,10 +2115,7 @@ impl IO for dinf {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        w.put(Object {
-            box_type: dref::BOX_TYPE,
-            payload: self.dref.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(dref::BOX_TYPE, self.dref.as_bytes()).as_bytes());
 
         w
     }
@@ -1627,18 +2169,15 @@ impl IO for DataEntry {
 
         w.put(match self {
             DataEntry::url_ { base, location } => {
-                Object {
-                    box_type: url_::BOX_TYPE,
-                    payload: {
-                        let mut w = BytesMut::new();
+                Object::new(url_::BOX_TYPE, {
+                    let mut w = BytesMut::new();
 
-                        w.put(base.as_bytes());
+                    w.put(base.as_bytes());
 
-                        w.put(location.as_bytes());
+                    w.put(location.as_bytes());
 
-                        w
-                    },
-                }
+                    w
+                })
             }
         }.as_bytes());
 
@@ -1727,9 +2266,14 @@ impl IO for dref {
 pub struct stbl {
     pub stsd: stsd,
     pub stts: stts,
+    pub ctts: Option<ctts>,
+    /// `None` when every sample is a sync sample
+    pub stss: Option<stss>,
     pub stsc: stsc,
     pub stsz: stsz,
+    /// ignored when `co64` is present
     pub stco: stco,
+    pub co64: Option<co64>,
 }
 
 impl Default for stbl {
@@ -1737,40 +2281,22 @@ impl Default for stbl {
         Self {
             stsd: Default::default(),
             stts: Default::default(),
+            ctts: None,
+            stss: None,
             stsc: Default::default(),
             stsz: Default::default(),
             stco: Default::default(),
+            co64: None,
         }
     }
 }
 
 impl stbl {
     pub const BOX_TYPE: u32 = types::stbl;
-}
-
-impl Debug for stbl {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("\t\t\t\t\t0x{:08x?}: \"stsd\"", stsd::BOX_TYPE))?;
-        f.write_fmt(format_args!("\n{:?}", self.stsd))?;
-        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stts\"", stts::BOX_TYPE))?;
-        f.write_fmt(format_args!("\n{:?}", self.stts))?;
-        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stsz\"", stsz::BOX_TYPE))?;
-        f.write_fmt(format_args!("\n{:?}", self.stsz))?;
-        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stsc\"", stsc::BOX_TYPE))?;
-        f.write_fmt(format_args!("\n{:?}", self.stsc))?;
-        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stco\"", stco::BOX_TYPE))?;
-        f.write_fmt(format_args!("\n{:?}", self.stco))?;
-
-        Ok(())
-    }
-}
-
-impl IO for stbl {
-    fn len(&self) -> usize {
-        40 + self.stsd.len() + self.stts.len() + self.stsc.len() + self.stsz.len() + self.stco.len()
-    }
 
-    fn parse(r: &mut BytesMut) -> Self {
+    /// Parses the box, reading the sample descriptions with the QuickTime
+    /// sound description layouts when `quicktime`.
+    fn parse_with(r: &mut BytesMut, quicktime: bool) -> Self {
         let mut rst = Self::default();
 
         while 0 < r.len() {
@@ -1779,12 +2305,16 @@ impl IO for stbl {
             match b.box_type {
                 // stsd: Sample Description
                 stsd::BOX_TYPE => {
-                    rst.stsd = stsd::parse(&mut b.payload);
+                    rst.stsd = stsd::parse_with(&mut b.payload, quicktime);
                 }
                 // stts: Decoding Time to Sample
                 stts::BOX_TYPE => {
                     rst.stts = stts::parse(&mut b.payload);
                 }
+                // ctts: Composition Time to Sample
+                ctts::BOX_TYPE => {
+                    rst.ctts = Some(ctts::parse(&mut b.payload));
+                }
                 // stsc: Sample To Chunk
                 stsc::BOX_TYPE => {
                     rst.stsc = stsc::parse(&mut b.payload);
@@ -1797,6 +2327,14 @@ impl IO for stbl {
                 stco::BOX_TYPE => {
                     rst.stco = stco::parse(&mut b.payload);
                 }
+                // co64: Chunk Large Offset
+                co64::BOX_TYPE => {
+                    rst.co64 = Some(co64::parse(&mut b.payload));
+                }
+                // stss: Sync Sample
+                stss::BOX_TYPE => {
+                    rst.stss = Some(stss::parse(&mut b.payload));
+                }
                 _ => {}
             }
         }
@@ -1804,29 +2342,201 @@ impl IO for stbl {
         rst
     }
 
+    /// Samples in decoding order, located through the chunk tables.
+    pub fn samples(&self) -> Vec<Sample> {
+        let sample_count = if 0 == self.stsz.sample_size {
+            self.stsz.entries.len()
+        } else {
+            self.stts.entries.iter().map(|it| it.0 as usize).sum()
+        };
+
+        let mut rst = Vec::with_capacity(sample_count);
+        let mut decode_time = 0;
+        let mut deltas = self.stts.entries.iter().flat_map(|(count, delta)| (0..*count).map(move |_| *delta));
+        let mut offsets = self.ctts.iter().flat_map(|it| &it.entries).flat_map(|(count, offset)| (0..*count).map(move |_| *offset));
+        let mut sync_samples = self.stss.as_ref().map(|it| it.entries.iter().peekable());
+        let chunk_offsets = match &self.co64 {
+            Some(it) => { it.entries.clone() }
+            None => { self.stco.entries.iter().map(|it| *it as u64).collect() }
+        };
+
+        'chunks: for (i, (first_chunk, samples_per_chunk, description_index)) in self.stsc.entries.iter().enumerate() {
+            let last_chunk = self.stsc.entries.get(i + 1).map_or(chunk_offsets.len() as u32, |it| it.0.saturating_sub(1));
+
+            for chunk in *first_chunk..=last_chunk {
+                let Some(offset) = chunk_offsets.get((chunk as usize).wrapping_sub(1)) else { break 'chunks };
+                let mut offset = *offset;
+
+                for _ in 0..*samples_per_chunk {
+                    if sample_count <= rst.len() {
+                        break 'chunks;
+                    }
+                    let size = self.stsz.entries.get(rst.len()).copied().unwrap_or(self.stsz.sample_size);
+                    let duration = deltas.next().unwrap_or(0);
+                    let is_sync = match &mut sync_samples {
+                        Some(it) => { it.next_if(|it| rst.len() + 1 == **it as usize).is_some() }
+                        None => { true }
+                    };
+
+                    rst.push(Sample {
+                        offset,
+                        size,
+                        decode_time,
+                        duration,
+                        composition_offset: offsets.next().unwrap_or(0),
+                        is_sync,
+                        description_index: *description_index,
+                    });
+                    offset += size as u64;
+                    decode_time += duration as u64;
+                }
+            }
+        }
+
+        rst
+    }
+
+    /// Rebuilds the sample tables from `samples` in decoding order, keeping
+    /// `stsd`. Runs of adjacent samples with the same sample entry make up
+    /// the chunks.
+    pub fn set_samples(&mut self, samples: &[Sample]) {
+        let mut chunks: Vec<(u64, u32, u32)> = vec![];
+
+        self.stts.entries.clear();
+        self.stsc.entries.clear();
+        self.stsz.sample_size = 0;
+        self.stsz.entries.clear();
+
+        let mut ctts = ctts::default();
+        let mut stss = stss::default();
+        for (i, it) in samples.iter().enumerate() {
+            match self.stts.entries.last_mut() {
+                Some((count, delta)) if it.duration == *delta => { *count += 1 }
+                _ => { self.stts.entries.push((1, it.duration)) }
+            }
+            match ctts.entries.last_mut() {
+                Some((count, offset)) if it.composition_offset == *offset => { *count += 1 }
+                _ => { ctts.entries.push((1, it.composition_offset)) }
+            }
+            if it.is_sync {
+                stss.entries.push(i as u32 + 1);
+            }
+            self.stsz.entries.push(it.size);
+
+            match chunks.last_mut() {
+                Some((_, count, description_index))
+                if it.description_index == *description_index && samples[i - 1].offset + samples[i - 1].size as u64 == it.offset => {
+                    *count += 1
+                }
+                _ => { chunks.push((it.offset, 1, it.description_index)) }
+            }
+        }
+
+        for (i, (_, samples_per_chunk, description_index)) in chunks.iter().enumerate() {
+            match self.stsc.entries.last() {
+                Some((_, count, index)) if samples_per_chunk == count && description_index == index => {}
+                _ => { self.stsc.entries.push((i as u32 + 1, *samples_per_chunk, *description_index)) }
+            }
+        }
+
+        self.ctts = if ctts.entries.iter().any(|it| 0 != it.1) { Some(ctts) } else { None };
+        self.stss = if stss.entries.len() < samples.len() { Some(stss) } else { None };
+        if chunks.iter().any(|it| (u32::MAX as u64) < it.0) {
+            self.stco.entries.clear();
+            self.co64 = Some(co64 {
+                base: FullBox::new(0, 0),
+                entries: chunks.iter().map(|it| it.0).collect(),
+            });
+        } else {
+            self.stco.entries = chunks.iter().map(|it| it.0 as u32).collect();
+            self.co64 = None;
+        }
+    }
+}
+
+/// A sample of a track, flattened from its sample table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// file offset
+    pub offset: u64,
+    pub size: u32,
+    /// in the media timescale
+    pub decode_time: u64,
+    pub duration: u32,
+    /// composition time minus decoding time
+    pub composition_offset: i32,
+    pub is_sync: bool,
+    /// 1-based index of the sample entry in `stsd`
+    pub description_index: u32,
+}
+
+impl Sample {
+    /// Composition time, in the media timescale.
+    pub fn composition_time(&self) -> i64 {
+        self.decode_time as i64 + self.composition_offset as i64
+    }
+}
+
+impl Debug for stbl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t0x{:08x?}: \"stsd\"", stsd::BOX_TYPE))?;
+        f.write_fmt(format_args!("\n{:?}", self.stsd))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stts\"", stts::BOX_TYPE))?;
+        f.write_fmt(format_args!("\n{:?}", self.stts))?;
+        if let Some(it) = &self.ctts {
+            f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"ctts\"", ctts::BOX_TYPE))?;
+            f.write_fmt(format_args!("\n{:?}", it))?;
+        }
+        if let Some(it) = &self.stss {
+            f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stss\"", stss::BOX_TYPE))?;
+            f.write_fmt(format_args!("\n{:?}", it))?;
+        }
+        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stsz\"", stsz::BOX_TYPE))?;
+        f.write_fmt(format_args!("\n{:?}", self.stsz))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stsc\"", stsc::BOX_TYPE))?;
+        f.write_fmt(format_args!("\n{:?}", self.stsc))?;
+        if let Some(it) = &self.co64 {
+            f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"co64\"", co64::BOX_TYPE))?;
+            f.write_fmt(format_args!("\n{:?}", it))?;
+        } else {
+            f.write_fmt(format_args!("\n\t\t\t\t\t0x{:08x?}: \"stco\"", stco::BOX_TYPE))?;
+            f.write_fmt(format_args!("\n{:?}", self.stco))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for stbl {
+    fn len(&self) -> usize {
+        32 + self.stsd.len() + self.stts.len() + self.stsc.len() + self.stsz.len()
+            + if let Some(ctts) = &self.ctts { 8 + ctts.len() } else { 0 }
+            + if let Some(stss) = &self.stss { 8 + stss.len() } else { 0 }
+            + if let Some(co64) = &self.co64 { 8 + co64.len() } else { 8 + self.stco.len() }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self::parse_with(r, false)
+    }
+
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
-        w.put(Object {
-            box_type: stsd::BOX_TYPE,
-            payload: self.stsd.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: stts::BOX_TYPE,
-            payload: self.stts.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: stsc::BOX_TYPE,
-            payload: self.stsc.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: stsz::BOX_TYPE,
-            payload: self.stsz.as_bytes(),
-        }.as_bytes());
-        w.put(Object {
-            box_type: stco::BOX_TYPE,
-            payload: self.stco.as_bytes(),
-        }.as_bytes());
+        w.put(Object::new(stsd::BOX_TYPE, self.stsd.as_bytes()).as_bytes());
+        w.put(Object::new(stts::BOX_TYPE, self.stts.as_bytes()).as_bytes());
+        if let Some(it) = &mut self.ctts {
+            w.put(Object::new(ctts::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        w.put(Object::new(stsc::BOX_TYPE, self.stsc.as_bytes()).as_bytes());
+        if let Some(it) = &mut self.stss {
+            w.put(Object::new(stss::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        w.put(Object::new(stsz::BOX_TYPE, self.stsz.as_bytes()).as_bytes());
+        if let Some(it) = &mut self.co64 {
+            w.put(Object::new(co64::BOX_TYPE, it.as_bytes()).as_bytes());
+        } else {
+            w.put(Object::new(stco::BOX_TYPE, self.stco.as_bytes()).as_bytes());
+        }
 
         w
     }
@@ -1842,6 +2552,23 @@ pub struct stsd {
 
 impl stsd {
     pub const BOX_TYPE: u32 = types::stsd;
+
+    /// Parses the box, reading the entries of a version 0 box with the
+    /// QuickTime sound description layouts when `quicktime`.
+    fn parse_with(r: &mut BytesMut, quicktime: bool) -> Self {
+        let mut rst = Self {
+            base: FullBox::parse(r),
+            entries: vec![],
+        };
+
+        let quicktime = quicktime && 0 == rst.base.version;
+        let entry_count = r.get_u32();
+        for _ in 0..entry_count {
+            rst.entries.push(SampleEntry::parse_with(r, quicktime));
+        }
+
+        rst
+    }
 }
 
 impl Default for stsd {
@@ -1893,17 +2620,7 @@ impl IO for stsd {
     }
 
     fn parse(r: &mut BytesMut) -> Self {
-        let mut rst = Self {
-            base: FullBox::parse(r),
-            entries: vec![],
-        };
-
-        let entry_count = r.get_u32();
-        for _ in 0..entry_count {
-            rst.entries.push(SampleEntry::parse(r));
-        }
-
-        rst
+        Self::parse_with(r, false)
     }
 
     fn as_bytes(&mut self) -> BytesMut {
@@ -1914,10 +2631,7 @@ impl IO for stsd {
         w.put_u32(self.entries.len() as u32);
 
         for it in self.entries.iter_mut() {
-            w.put(Object {
-                box_type: it.get_handler_type(),
-                payload: it.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(it.get_handler_type(), it.as_bytes()).as_bytes());
         }
 
         w
@@ -1944,8 +2658,16 @@ pub enum SampleEntry {
     Audio {
         base: std::boxed::Box<SampleEntry>,
 
+        version: SoundDescriptionVersion,
+        /// QuickTime revision level and vendor, reserved in ISO files
+        revision_level: u16,
+        vendor: u32,
         channel_count: u16,
         sample_size: u16,
+        /// QuickTime compression ID and packet size, reserved in ISO files;
+        /// the compression ID is -2 in a version 2 sound description
+        compression_id: i16,
+        packet_size: u16,
         sample_rate: u32,
     },
     #[allow(non_camel_case_types)]
@@ -1957,11 +2679,61 @@ pub enum SampleEntry {
     },
     #[allow(non_camel_case_types)]
     #[allow(non_snake_case)]
+    hvc1 {
+        base: std::boxed::Box<SampleEntry>,
+
+        ext: BytesMut,
+    },
+    #[allow(non_camel_case_types)]
+    #[allow(non_snake_case)]
     mp4a {
         base: std::boxed::Box<SampleEntry>,
 
         ext: BytesMut,
     },
+    #[allow(non_snake_case)]
+    Opus {
+        base: std::boxed::Box<SampleEntry>,
+
+        dOps: Option<dOps>,
+        ext: BytesMut,
+    },
+    #[allow(non_camel_case_types)]
+    #[allow(non_snake_case)]
+    fLaC {
+        base: std::boxed::Box<SampleEntry>,
+
+        dfLa: Option<dfLa>,
+        ext: BytesMut,
+    },
+    #[allow(non_camel_case_types)]
+    ac_3 {
+        base: std::boxed::Box<SampleEntry>,
+
+        dac3: Option<dac3>,
+        ext: BytesMut,
+    },
+    #[allow(non_camel_case_types)]
+    ec_3 {
+        base: std::boxed::Box<SampleEntry>,
+
+        dec3: Option<dec3>,
+        ext: BytesMut,
+    },
+    Unknown {
+        base: std::boxed::Box<SampleEntry>,
+
+        ext: BytesMut,
+    },
+}
+
+impl Default for SampleEntry {
+    fn default() -> Self {
+        SampleEntry::Base {
+            handler_type: 0,
+            data_reference_index: 1,
+        }
+    }
 }
 
 impl Debug for SampleEntry {
@@ -1996,21 +2768,45 @@ impl Debug for SampleEntry {
             }
             SampleEntry::Audio {
                 base,
+                version,
+                revision_level,
+                vendor,
                 channel_count,
                 sample_size,
+                compression_id,
+                packet_size,
                 sample_rate,
             } => {
                 base.fmt(f)?;
 
+                if 0 != *revision_level || 0 != *vendor {
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\trevision_level: {:?}", revision_level))?;
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tvendor: 0x{:08x?}", vendor))?;
+                }
                 f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tchannel_count: {:?}", channel_count))?;
                 f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tsample_size: {:?}", sample_size))?;
+                if 0 != *compression_id || 0 != *packet_size {
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tcompression_id: {:?}", compression_id))?;
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tpacket_size: {:?}", packet_size))?;
+                }
                 f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tsample_rate: {:?}", sample_rate))?;
+                version.fmt(f)?;
             }
             SampleEntry::avc1 {
                 base,
                 ..
             } => {
                 base.fmt(f)?;
+
+                self.fmt_visual_children(f)?;
+            }
+            SampleEntry::hvc1 {
+                base,
+                ..
+            } => {
+                base.fmt(f)?;
+
+                self.fmt_visual_children(f)?;
             }
             SampleEntry::mp4a {
                 base,
@@ -2018,6 +2814,62 @@ impl Debug for SampleEntry {
             } => {
                 base.fmt(f)?;
             }
+            SampleEntry::Opus {
+                base,
+                dOps,
+                ..
+            } => {
+                base.fmt(f)?;
+
+                if let Some(it) = dOps {
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"dOps\"\n", dOps::BOX_TYPE))?;
+                    it.fmt(f)?;
+                }
+            }
+            SampleEntry::fLaC {
+                base,
+                dfLa,
+                ..
+            } => {
+                base.fmt(f)?;
+
+                if let Some(it) = dfLa {
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"dfLa\"\n", dfLa::BOX_TYPE))?;
+                    it.fmt(f)?;
+                }
+            }
+            SampleEntry::ac_3 {
+                base,
+                dac3,
+                ..
+            } => {
+                base.fmt(f)?;
+
+                if let Some(it) = dac3 {
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"dac3\"\n", dac3::BOX_TYPE))?;
+                    it.fmt(f)?;
+                }
+            }
+            SampleEntry::ec_3 {
+                base,
+                dec3,
+                ..
+            } => {
+                base.fmt(f)?;
+
+                if let Some(it) = dec3 {
+                    f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"dec3\"\n", dec3::BOX_TYPE))?;
+                    it.fmt(f)?;
+                }
+            }
+            SampleEntry::Unknown {
+                base,
+                ..
+            } => {
+                base.fmt(f)?;
+
+                self.fmt_visual_children(f)?;
+            }
         }
 
         Ok(())
@@ -2029,99 +2881,29 @@ impl IO for SampleEntry {
         match self {
             SampleEntry::Visual { base, .. } => { base.len() + 70 }
             SampleEntry::Base { .. } => { 16 }
-            SampleEntry::Audio { base, .. } => { base.len() + 20 }
+            SampleEntry::Audio { base, version, .. } => { base.len() + 20 + version.len() }
             SampleEntry::avc1 { base, ext } => { base.len() + ext.len() }
+            SampleEntry::hvc1 { base, ext } => { base.len() + ext.len() }
             SampleEntry::mp4a { base, ext } => { base.len() + ext.len() }
+            SampleEntry::Opus { base, dOps, ext } => { base.len() + dOps.as_ref().map_or(0, |it| 8 + it.len()) + ext.len() }
+            SampleEntry::fLaC { base, dfLa, ext } => { base.len() + dfLa.as_ref().map_or(0, |it| 8 + it.len()) + ext.len() }
+            SampleEntry::ac_3 { base, dac3, ext } => { base.len() + dac3.as_ref().map_or(0, |it| 8 + it.len()) + ext.len() }
+            SampleEntry::ec_3 { base, dec3, ext } => { base.len() + dec3.as_ref().map_or(0, |it| 8 + it.len()) + ext.len() }
+            SampleEntry::Unknown { base, ext } => { base.len() + ext.len() }
         }
     }
 
     fn parse(r: &mut BytesMut) -> Self {
-        let mut b = Object::parse(r);
-        let handler_type = b.box_type;
-        let _ = b.payload.split_to(6);
-        let data_reference_index = b.payload.get_u16();
-
-        let base = SampleEntry::Base {
-            handler_type,
-            data_reference_index,
-        };
-
-        match handler_type {
-            // avc1
-            types::avc1 => {
-                let _ = b.payload.get_u16();
-                let _ = b.payload.get_u16();
-                let _ = b.payload.split_to(12);
-                let width = b.payload.get_u16();
-                let height = b.payload.get_u16();
-                let horiz_resolution = b.payload.get_u32();
-                let vert_resolution = b.payload.get_u32();
-                let _ = b.payload.get_u32();
-                let frame_count = b.payload.get_u16();
-                let compressor_name = {
-                    let len = min(31, b.payload.get_u8());
-                    let mut rst = String::with_capacity(len as usize);
-
-                    rst.push_str(std::str::from_utf8(b.payload.split_to(len as usize).chunk()).unwrap());
-                    if 31 > len {
-                        let _ = b.payload.split_to((31 - len) as usize);
-                    }
-
-                    rst
-                };
-                let depth = b.payload.get_u16();
-                let _ = b.payload.get_u16();
+        Self::parse_with(r, false)
+    }
 
-                let vide = SampleEntry::Visual {
-                    base: std::boxed::Box::new(base),
-                    width,
-                    height,
-                    horiz_resolution,
-                    vert_resolution,
-                    frame_count,
-                    compressor_name,
-                    depth,
-                };
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
 
-                SampleEntry::avc1 {
-                    base: std::boxed::Box::new(vide),
-                    ext: b.payload.split_to(b.payload.len()),
-                }
-            }
-            // mp4a
-            types::mp4a => {
-                let _version = b.payload.get_u16();
-                let _ = b.payload.split_to(6);
-                let channel_count = b.payload.get_u16();
-                let sample_size = b.payload.get_u16();
-                let _ = b.payload.get_u32();
-                let sample_rate = b.payload.get_u32();
-
-                let soun = SampleEntry::Audio {
-                    base: std::boxed::Box::new(base),
-                    channel_count,
-                    sample_size,
-                    sample_rate,
-                };
-
-                SampleEntry::mp4a {
-                    base: std::boxed::Box::new(soun),
-                    ext: b.payload.split_to(b.payload.len()),
-                }
-            }
-            _ => {
-                base
-            }
-        }
-    }
-
-    fn as_bytes(&mut self) -> BytesMut {
-        let mut w = BytesMut::new();
-
-        match self {
-            SampleEntry::Base { data_reference_index, .. } => {
-                w.put_bytes(0, 6);
-                w.put_u16(*data_reference_index);
+        match self {
+            SampleEntry::Base { data_reference_index, .. } => {
+                w.put_bytes(0, 6);
+                w.put_u16(*data_reference_index);
             }
             SampleEntry::Visual {
                 base,
@@ -2157,17 +2939,26 @@ impl IO for SampleEntry {
             }
             SampleEntry::Audio {
                 base,
+                version,
+                revision_level,
+                vendor,
                 channel_count,
                 sample_size,
+                compression_id,
+                packet_size,
                 sample_rate,
             } => {
                 w.put(base.as_bytes());
 
-                w.put_u64(0);
+                w.put_u16(version.version());
+                w.put_u16(*revision_level);
+                w.put_u32(*vendor);
                 w.put_u16(*channel_count);
                 w.put_u16(*sample_size);
-                w.put_u32(0);
+                w.put_i16(*compression_id);
+                w.put_u16(*packet_size);
                 w.put_u32(*sample_rate);
+                w.put(version.as_bytes());
             }
             SampleEntry::avc1 {
                 base,
@@ -2175,7 +2966,15 @@ impl IO for SampleEntry {
             } => {
                 w.put(base.as_bytes());
 
-                w.put(ext);
+                w.put(ext.chunk());
+            }
+            SampleEntry::hvc1 {
+                base,
+                ext,
+            } => {
+                w.put(base.as_bytes());
+
+                w.put(ext.chunk());
             }
             SampleEntry::mp4a {
                 base,
@@ -2183,34 +2982,2239 @@ impl IO for SampleEntry {
             } => {
                 w.put(base.as_bytes());
 
-                w.put(ext);
-            }
-        }
+                w.put(ext.chunk());
+            }
+            SampleEntry::Opus {
+                base,
+                dOps,
+                ext,
+            } => {
+                w.put(base.as_bytes());
+
+                if let Some(it) = dOps {
+                    w.put(Object::new(dOps::BOX_TYPE, it.as_bytes()).as_bytes());
+                }
+                w.put(ext.chunk());
+            }
+            SampleEntry::fLaC {
+                base,
+                dfLa,
+                ext,
+            } => {
+                w.put(base.as_bytes());
+
+                if let Some(it) = dfLa {
+                    w.put(Object::new(dfLa::BOX_TYPE, it.as_bytes()).as_bytes());
+                }
+                w.put(ext.chunk());
+            }
+            SampleEntry::ac_3 {
+                base,
+                dac3,
+                ext,
+            } => {
+                w.put(base.as_bytes());
+
+                if let Some(it) = dac3 {
+                    w.put(Object::new(dac3::BOX_TYPE, it.as_bytes()).as_bytes());
+                }
+                w.put(ext.chunk());
+            }
+            SampleEntry::ec_3 {
+                base,
+                dec3,
+                ext,
+            } => {
+                w.put(base.as_bytes());
+
+                if let Some(it) = dec3 {
+                    w.put(Object::new(dec3::BOX_TYPE, it.as_bytes()).as_bytes());
+                }
+                w.put(ext.chunk());
+            }
+            SampleEntry::Unknown {
+                base,
+                ext,
+            } => {
+                w.put(base.as_bytes());
+
+                w.put(ext.chunk());
+            }
+        }
+
+        w
+    }
+}
+
+impl SampleEntry {
+    /// Parses an entry, with the QuickTime sound description layouts when
+    /// `quicktime`.
+    fn parse_with(r: &mut BytesMut, quicktime: bool) -> Self {
+        let mut b = Object::parse(r);
+        let handler_type = b.box_type;
+        let _ = b.payload.split_to(6);
+        let data_reference_index = b.payload.get_u16();
+
+        let base = SampleEntry::Base {
+            handler_type,
+            data_reference_index,
+        };
+
+        match handler_type {
+            // avc1
+            types::avc1 => {
+                let vide = SampleEntry::parse_visual(base, &mut b.payload);
+
+                SampleEntry::avc1 {
+                    base: std::boxed::Box::new(vide),
+                    ext: b.payload.split_to(b.payload.len()),
+                }
+            }
+            // hvc1
+            types::hvc1 => {
+                let vide = SampleEntry::parse_visual(base, &mut b.payload);
+
+                SampleEntry::hvc1 {
+                    base: std::boxed::Box::new(vide),
+                    ext: b.payload.split_to(b.payload.len()),
+                }
+            }
+            // mp4a
+            types::mp4a => {
+                let soun = SampleEntry::parse_audio(base, &mut b.payload, quicktime);
+
+                SampleEntry::mp4a {
+                    base: std::boxed::Box::new(soun),
+                    ext: b.payload.split_to(b.payload.len()),
+                }
+            }
+            // Opus
+            types::Opus => {
+                let soun = SampleEntry::parse_audio(base, &mut b.payload, quicktime);
+                let (v, ext) = take_child(&mut b.payload, dOps::BOX_TYPE);
+
+                SampleEntry::Opus {
+                    base: std::boxed::Box::new(soun),
+                    dOps: v.map(|mut v| dOps::parse(&mut v)),
+                    ext,
+                }
+            }
+            // fLaC
+            types::fLaC => {
+                let soun = SampleEntry::parse_audio(base, &mut b.payload, quicktime);
+                let (v, ext) = take_child(&mut b.payload, dfLa::BOX_TYPE);
+
+                SampleEntry::fLaC {
+                    base: std::boxed::Box::new(soun),
+                    dfLa: v.map(|mut v| dfLa::parse(&mut v)),
+                    ext,
+                }
+            }
+            // ac-3
+            types::ac_3 => {
+                let soun = SampleEntry::parse_audio(base, &mut b.payload, quicktime);
+                let (v, ext) = take_child(&mut b.payload, dac3::BOX_TYPE);
+
+                SampleEntry::ac_3 {
+                    base: std::boxed::Box::new(soun),
+                    dac3: v.map(|mut v| dac3::parse(&mut v)),
+                    ext,
+                }
+            }
+            // ec-3
+            types::ec_3 => {
+                let soun = SampleEntry::parse_audio(base, &mut b.payload, quicktime);
+                let (v, ext) = take_child(&mut b.payload, dec3::BOX_TYPE);
+
+                SampleEntry::ec_3 {
+                    base: std::boxed::Box::new(soun),
+                    dec3: v.map(|mut v| dec3::parse(&mut v)),
+                    ext,
+                }
+            }
+            _ => {
+                SampleEntry::Unknown {
+                    base: std::boxed::Box::new(base),
+                    ext: b.payload.split_to(b.payload.len()),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn get_handler_type(&self) -> u32 {
+        match self {
+            SampleEntry::Base { handler_type, .. } => {
+                *handler_type
+            }
+            SampleEntry::Visual { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::Audio { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::avc1 { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::hvc1 { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::mp4a { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::Opus { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::fLaC { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::ac_3 { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::ec_3 { base, .. } => {
+                base.get_handler_type()
+            }
+            SampleEntry::Unknown { base, .. } => {
+                base.get_handler_type()
+            }
+        }
+    }
+
+    /// Interprets an entry of an unknown format as a generic visual or audio
+    /// sample entry once the handler type of the enclosing track is known,
+    /// leaving its child boxes in `ext`.
+    fn resolve(&mut self, handler_type: u32, quicktime: bool) {
+        if let SampleEntry::Unknown { base, ext } = self {
+            if !matches!(**base, SampleEntry::Base { .. }) {
+                return;
+            }
+
+            match handler_type {
+                types::vide if 70 <= ext.len() => {
+                    let b = std::mem::take(base.as_mut());
+                    **base = SampleEntry::parse_visual(b, ext);
+                }
+                types::soun if 20 <= ext.len() => {
+                    let b = std::mem::take(base.as_mut());
+                    **base = SampleEntry::parse_audio(b, ext, quicktime);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // class VisualSampleEntry(codingname) extends SampleEntry (codingname){
+    //     unsigned int(16) pre_defined = 0;
+    //     const unsigned int(16) reserved = 0;
+    //     unsigned int(32)[3] pre_defined = 0;
+    //     unsigned int(16) width;
+    //     unsigned int(16) height;
+    //     template unsigned int(32) horizresolution = 0x00480000; // 72 dpi
+    //     template unsigned int(32) vertresolution = 0x00480000; // 72 dpi
+    //     const unsigned int(32) reserved = 0;
+    //     template unsigned int(16) frame_count = 1;
+    //     string[32] compressorname;
+    //     template unsigned int(16) depth = 0x0018;
+    //     int(16) pre_defined = -1;
+    //     // other boxes from derived specifications
+    //     CleanApertureBox clap; // optional
+    //     PixelAspectRatioBox pasp; // optional
+    // }
+    //
+    // The colour, aspect ratio and HDR boxes are taken out of the trailing
+    // boxes, and the others are left in `r` for the derived sample entry.
+    fn parse_visual(base: SampleEntry, r: &mut BytesMut) -> Self {
+        let _ = r.get_u16();
+        let _ = r.get_u16();
+        let _ = r.split_to(12);
+        let width = r.get_u16();
+        let height = r.get_u16();
+        let horiz_resolution = r.get_u32();
+        let vert_resolution = r.get_u32();
+        let _ = r.get_u32();
+        let frame_count = r.get_u16();
+        let compressor_name = {
+            let len = min(31, r.get_u8());
+            let mut rst = String::with_capacity(len as usize);
+
+            rst.push_str(std::str::from_utf8(r.split_to(len as usize).chunk()).unwrap());
+            if 31 > len {
+                let _ = r.split_to((31 - len) as usize);
+            }
+
+            rst
+        };
+        let depth = r.get_u16();
+        let _ = r.get_u16();
+
+        SampleEntry::Visual {
+            base: std::boxed::Box::new(base),
+            width,
+            height,
+            horiz_resolution,
+            vert_resolution,
+            frame_count,
+            compressor_name,
+            depth,
+        }
+    }
+
+    fn visual(&self) -> Option<&SampleEntry> {
+        match self {
+            SampleEntry::Base { .. } => { None }
+            SampleEntry::Visual { .. } => { Some(self) }
+            SampleEntry::Audio { .. } => { None }
+            SampleEntry::avc1 { base, .. } |
+            SampleEntry::hvc1 { base, .. } |
+            SampleEntry::mp4a { base, .. } |
+            SampleEntry::Opus { base, .. } |
+            SampleEntry::fLaC { base, .. } |
+            SampleEntry::ac_3 { base, .. } |
+            SampleEntry::ec_3 { base, .. } |
+            SampleEntry::Unknown { base, .. } => {
+                base.visual()
+            }
+        }
+    }
+
+    /// Payload of the child box `box_type` of a visual sample entry, left
+    /// in place among the entry's trailing boxes.
+    fn visual_child(&self, box_type: u32) -> Option<BytesMut> {
+        self.visual()?;
+
+        match self {
+            SampleEntry::avc1 { ext, .. } |
+            SampleEntry::hvc1 { ext, .. } |
+            SampleEntry::Unknown { ext, .. } => {
+                take_child(&mut ext.clone(), box_type).0
+            }
+            _ => { None }
+        }
+    }
+
+    pub fn colr(&self) -> Option<colr> {
+        self.visual_child(colr::BOX_TYPE).map(|mut v| colr::parse(&mut v))
+    }
+
+    pub fn mdcv(&self) -> Option<mdcv> {
+        self.visual_child(mdcv::BOX_TYPE).map(|mut v| mdcv::parse(&mut v))
+    }
+
+    pub fn clli(&self) -> Option<clli> {
+        self.visual_child(clli::BOX_TYPE).map(|mut v| clli::parse(&mut v))
+    }
+
+    pub fn pasp(&self) -> Option<pasp> {
+        self.visual_child(pasp::BOX_TYPE).map(|mut v| pasp::parse(&mut v))
+    }
+
+    pub fn clap(&self) -> Option<clap> {
+        self.visual_child(clap::BOX_TYPE).map(|mut v| clap::parse(&mut v))
+    }
+
+    fn fmt_visual_children(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(v) = self.colr() {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"colr\"\n", colr::BOX_TYPE))?;
+            v.fmt(f)?;
+        }
+        if let Some(v) = self.mdcv() {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"mdcv\"\n", mdcv::BOX_TYPE))?;
+            v.fmt(f)?;
+        }
+        if let Some(v) = self.clli() {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"clli\"\n", clli::BOX_TYPE))?;
+            v.fmt(f)?;
+        }
+        if let Some(v) = self.pasp() {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"pasp\"\n", pasp::BOX_TYPE))?;
+            v.fmt(f)?;
+        }
+        if let Some(v) = self.clap() {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t0x{:08x?}: \"clap\"\n", clap::BOX_TYPE))?;
+            v.fmt(f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Coded width and height of a visual sample entry.
+    pub fn dimensions(&self) -> Option<(u16, u16)> {
+        if let Some(SampleEntry::Visual { width, height, .. }) = self.visual() {
+            Some((*width, *height))
+        } else {
+            None
+        }
+    }
+
+    /// Display aspect ratio of a visual sample entry, from the clean
+    /// aperture when present (or the coded size) scaled by the pixel aspect
+    /// ratio, reduced to lowest terms.
+    pub fn display_aspect_ratio(&self) -> Option<(u64, u64)> {
+        if let Some(SampleEntry::Visual { width, height, .. }) = self.visual() {
+            let (mut n, mut d) = if let Some(clap) = self.clap() {
+                (
+                    clap.clean_aperture_width_n as u64 * clap.clean_aperture_height_d as u64,
+                    clap.clean_aperture_width_d as u64 * clap.clean_aperture_height_n as u64,
+                )
+            } else {
+                (*width as u64, *height as u64)
+            };
+            if let Some(pasp) = self.pasp() {
+                n *= pasp.h_spacing as u64;
+                d *= pasp.v_spacing as u64;
+            }
+            if 0 == n || 0 == d {
+                return None;
+            }
+
+            let (mut a, mut b) = (n, d);
+            while 0 != b {
+                (a, b) = (b, a % b);
+            }
+
+            Some((n / a, d / a))
+        } else {
+            None
+        }
+    }
+
+    /// Transfer characteristics (ISO/IEC 23091-2) signalled by the `colr`
+    /// box of a visual sample entry.
+    pub fn transfer_characteristics(&self) -> Option<u16> {
+        match self.colr() {
+            Some(colr::nclx { transfer_characteristics, .. }) |
+            Some(colr::nclc { transfer_characteristics, .. }) => {
+                Some(transfer_characteristics)
+            }
+            _ => { None }
+        }
+    }
+
+    pub fn hdr_transfer(&self) -> Option<HdrTransfer> {
+        match self.transfer_characteristics() {
+            // SMPTE ST 2084
+            Some(16) => { Some(HdrTransfer::Pq) }
+            // ARIB STD-B67
+            Some(18) => { Some(HdrTransfer::Hlg) }
+            _ => { None }
+        }
+    }
+
+    // class AudioSampleEntry(codingname) extends SampleEntry (codingname){
+    //     const unsigned int(32)[2] reserved = 0;
+    //     template unsigned int(16) channelcount = 2;
+    //     template unsigned int(16) samplesize = 16;
+    //     unsigned int(16) pre_defined = 0;
+    //     const unsigned int(16) reserved = 0 ;
+    //     template unsigned int(32) samplerate = { default samplerate of media}<<16;
+    // }
+    //
+    // The first reserved word holds the entry version, and the QuickTime
+    // revision level and vendor. In QuickTime files, version 1 and 2 append
+    // the fields of `SoundDescriptionVersion`.
+    fn parse_audio(base: SampleEntry, r: &mut BytesMut, quicktime: bool) -> Self {
+        let version = r.get_u16();
+        let revision_level = r.get_u16();
+        let vendor = r.get_u32();
+        let channel_count = r.get_u16();
+        let sample_size = r.get_u16();
+        let compression_id = r.get_i16();
+        let packet_size = r.get_u16();
+        let sample_rate = r.get_u32();
+
+        SampleEntry::Audio {
+            base: std::boxed::Box::new(base),
+            version: SoundDescriptionVersion::parse(version, quicktime, r),
+            revision_level,
+            vendor,
+            channel_count,
+            sample_size,
+            compression_id,
+            packet_size,
+            sample_rate,
+        }
+    }
+
+    /// Sample rate of an audio sample entry, taken from the 64-bit field of
+    /// a version 2 sound description when present.
+    pub fn sample_rate(&self) -> Option<f64> {
+        match self {
+            SampleEntry::Base { .. } => { None }
+            SampleEntry::Visual { .. } => { None }
+            SampleEntry::Audio { version: SoundDescriptionVersion::V2 { audio_sample_rate, .. }, .. } => {
+                Some(*audio_sample_rate)
+            }
+            SampleEntry::Audio { sample_rate, .. } => {
+                Some(*sample_rate as f64 / 65536.0)
+            }
+            SampleEntry::avc1 { base, .. } |
+            SampleEntry::hvc1 { base, .. } |
+            SampleEntry::mp4a { base, .. } |
+            SampleEntry::Opus { base, .. } |
+            SampleEntry::fLaC { base, .. } |
+            SampleEntry::ac_3 { base, .. } |
+            SampleEntry::ec_3 { base, .. } |
+            SampleEntry::Unknown { base, .. } => {
+                base.sample_rate()
+            }
+        }
+    }
+
+    /// Channel count of an audio sample entry, taken from a version 2 sound
+    /// description when present.
+    pub fn channel_count(&self) -> Option<u32> {
+        match self {
+            SampleEntry::Base { .. } => { None }
+            SampleEntry::Visual { .. } => { None }
+            SampleEntry::Audio { version: SoundDescriptionVersion::V2 { num_audio_channels, .. }, .. } => {
+                Some(*num_audio_channels)
+            }
+            SampleEntry::Audio { channel_count, .. } => {
+                Some(*channel_count as u32)
+            }
+            SampleEntry::avc1 { base, .. } |
+            SampleEntry::hvc1 { base, .. } |
+            SampleEntry::mp4a { base, .. } |
+            SampleEntry::Opus { base, .. } |
+            SampleEntry::fLaC { base, .. } |
+            SampleEntry::ac_3 { base, .. } |
+            SampleEntry::ec_3 { base, .. } |
+            SampleEntry::Unknown { base, .. } => {
+                base.channel_count()
+            }
+        }
+    }
+
+    /// Decoder configuration of an `avc1` sample entry.
+    #[allow(non_snake_case)]
+    pub fn avcC(&self) -> Option<avcC> {
+        if let SampleEntry::avc1 { ext, .. } = self {
+            let (v, _) = take_child(&mut ext.clone(), avcC::BOX_TYPE);
+
+            v.map(|mut v| avcC::parse(&mut v))
+        } else {
+            None
+        }
+    }
+
+    /// Decoder configuration of an `hvc1` sample entry.
+    #[allow(non_snake_case)]
+    pub fn hvcC(&self) -> Option<hvcC> {
+        if let SampleEntry::hvc1 { ext, .. } = self {
+            let (v, _) = take_child(&mut ext.clone(), hvcC::BOX_TYPE);
+
+            v.map(|mut v| hvcC::parse(&mut v))
+        } else {
+            None
+        }
+    }
+
+    /// `DecoderSpecificInfo` of the `esds` of an `mp4a` sample entry, which
+    /// for AAC is the AudioSpecificConfig.
+    pub fn decoder_specific_info(&self) -> Option<BytesMut> {
+        // class ES_Descriptor extends BaseDescriptor : bit(8) tag=ES_DescrTag {
+        //     bit(16) ES_ID;
+        //     bit(1) streamDependenceFlag;
+        //     bit(1) URL_Flag;
+        //     bit(1) OCRstreamFlag;
+        //     bit(5) streamPriority;
+        //     if (streamDependenceFlag)
+        //         bit(16) dependsOn_ES_ID;
+        //     if (URL_Flag) {
+        //         bit(8) URLlength;
+        //         bit(8) URLstring[URLlength];
+        //     }
+        //     if (OCRstreamFlag)
+        //         bit(16) OCR_ES_Id;
+        //     DecoderConfigDescriptor decConfigDescr;
+        //     ...
+        // }
+        let SampleEntry::mp4a { ext, .. } = self else { return None };
+        let (esds, _) = take_child(&mut ext.clone(), types::esds);
+        let mut r = esds?;
+        if r.len() < 4 {
+            return None;
+        }
+        let _ = r.split_to(4);
+
+        // ES_DescrTag
+        let mut r = take_descriptor(&mut r, 0x03)?;
+        if r.len() < 3 {
+            return None;
+        }
+        let _ = r.get_u16();
+        let flags = r.get_u8();
+        let mut skip = 0;
+        if 0 != flags & 0x80 {
+            skip += 2;
+        }
+        if 0 != flags & 0x40 {
+            skip += 1 + *r.get(skip)? as usize;
+        }
+        if 0 != flags & 0x20 {
+            skip += 2;
+        }
+        if r.len() < skip {
+            return None;
+        }
+        let _ = r.split_to(skip);
+
+        // DecoderConfigDescrTag
+        let mut r = take_descriptor(&mut r, 0x04)?;
+        if r.len() < 13 {
+            return None;
+        }
+        let _ = r.split_to(13);
+
+        // DecSpecificInfoTag
+        take_descriptor(&mut r, 0x05)
+    }
+}
+
+/// Splits the first descriptor out of `r`, returning its payload when its
+/// tag is `tag`.
+fn take_descriptor(r: &mut BytesMut, tag: u8) -> Option<BytesMut> {
+    if r.is_empty() || tag != r.get_u8() {
+        return None;
+    }
+
+    let mut size = 0;
+    for _ in 0..4 {
+        if r.is_empty() {
+            return None;
+        }
+        let v = r.get_u8();
+        size = size << 7 | (v & 0x7f) as usize;
+        if 0 == v & 0x80 {
+            break;
+        }
+    }
+    if r.len() < size {
+        return None;
+    }
+
+    Some(r.split_to(size))
+}
+
+/// QuickTime sound description layout of an audio sample entry.
+///
+/// `V1` and `V2` are only read from a version 0 `stsd` under a QuickTime
+/// media handler; other entries are `V0` or `Iso`.
+#[derive(Clone, PartialEq)]
+pub enum SoundDescriptionVersion {
+    V0,
+    V1 {
+        samples_per_packet: u32,
+        bytes_per_packet: u32,
+        bytes_per_frame: u32,
+        bytes_per_sample: u32,
+    },
+    V2 {
+        audio_sample_rate: f64,
+        num_audio_channels: u32,
+        const_bits_per_channel: u32,
+        format_specific_flags: u32,
+        const_bytes_per_audio_packet: u32,
+        const_lpcm_frames_per_audio_packet: u32,
+    },
+    /// A version without QuickTime fields, such as the
+    /// `AudioSampleEntryV1` of a version 1 `stsd`
+    Iso {
+        version: u16,
+    },
+}
+
+impl SoundDescriptionVersion {
+    //! SoundDescriptionV1 {
+    //!     ...
+    //!     UInt32 samplesPerPacket;
+    //!     UInt32 bytesPerPacket;
+    //!     UInt32 bytesPerFrame;
+    //!     UInt32 bytesPerSample;
+    //! }
+    //! SoundDescriptionV2 {
+    //!     ...
+    //!     UInt32 sizeOfStructOnly;
+    //!     Float64 audioSampleRate;
+    //!     UInt32 numAudioChannels;
+    //!     SInt32 always7F000000;
+    //!     UInt32 constBitsPerChannel;
+    //!     UInt32 formatSpecificFlags;
+    //!     UInt32 constBytesPerAudioPacket;
+    //!     UInt32 constLPCMFramesPerAudioPacket;
+    //! }
+    fn parse(version: u16, quicktime: bool, r: &mut BytesMut) -> Self {
+        match version {
+            0 => {
+                SoundDescriptionVersion::V0
+            }
+            1 if quicktime && 16 <= r.len() => {
+                SoundDescriptionVersion::V1 {
+                    samples_per_packet: r.get_u32(),
+                    bytes_per_packet: r.get_u32(),
+                    bytes_per_frame: r.get_u32(),
+                    bytes_per_sample: r.get_u32(),
+                }
+            }
+            2 if quicktime && 36 <= r.len() => {
+                let _ = r.get_u32();
+                let audio_sample_rate = r.get_f64();
+                let num_audio_channels = r.get_u32();
+                let _ = r.get_u32();
+
+                SoundDescriptionVersion::V2 {
+                    audio_sample_rate,
+                    num_audio_channels,
+                    const_bits_per_channel: r.get_u32(),
+                    format_specific_flags: r.get_u32(),
+                    const_bytes_per_audio_packet: r.get_u32(),
+                    const_lpcm_frames_per_audio_packet: r.get_u32(),
+                }
+            }
+            _ => {
+                SoundDescriptionVersion::Iso { version }
+            }
+        }
+    }
+
+    fn version(&self) -> u16 {
+        match self {
+            SoundDescriptionVersion::V0 => { 0 }
+            SoundDescriptionVersion::V1 { .. } => { 1 }
+            SoundDescriptionVersion::V2 { .. } => { 2 }
+            SoundDescriptionVersion::Iso { version } => { *version }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            SoundDescriptionVersion::V0 => { 0 }
+            SoundDescriptionVersion::V1 { .. } => { 16 }
+            SoundDescriptionVersion::V2 { .. } => { 36 }
+            SoundDescriptionVersion::Iso { .. } => { 0 }
+        }
+    }
+
+    fn as_bytes(&self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        match self {
+            SoundDescriptionVersion::V0 => {}
+            SoundDescriptionVersion::Iso { .. } => {}
+            SoundDescriptionVersion::V1 {
+                samples_per_packet,
+                bytes_per_packet,
+                bytes_per_frame,
+                bytes_per_sample,
+                ..
+            } => {
+                w.put_u32(*samples_per_packet);
+                w.put_u32(*bytes_per_packet);
+                w.put_u32(*bytes_per_frame);
+                w.put_u32(*bytes_per_sample);
+            }
+            SoundDescriptionVersion::V2 {
+                audio_sample_rate,
+                num_audio_channels,
+                const_bits_per_channel,
+                format_specific_flags,
+                const_bytes_per_audio_packet,
+                const_lpcm_frames_per_audio_packet,
+            } => {
+                w.put_u32(72);
+                w.put_f64(*audio_sample_rate);
+                w.put_u32(*num_audio_channels);
+                w.put_u32(0x7F000000);
+                w.put_u32(*const_bits_per_channel);
+                w.put_u32(*format_specific_flags);
+                w.put_u32(*const_bytes_per_audio_packet);
+                w.put_u32(*const_lpcm_frames_per_audio_packet);
+            }
+        }
+
+        w
+    }
+}
+
+impl Debug for SoundDescriptionVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoundDescriptionVersion::V0 => {}
+            SoundDescriptionVersion::Iso { version } => {
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tversion: {:?}", version))?;
+            }
+            SoundDescriptionVersion::V1 {
+                samples_per_packet,
+                bytes_per_packet,
+                bytes_per_frame,
+                bytes_per_sample,
+            } => {
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tversion: 1"))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tsamples_per_packet: {:?}", samples_per_packet))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tbytes_per_packet: {:?}", bytes_per_packet))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tbytes_per_frame: {:?}", bytes_per_frame))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tbytes_per_sample: {:?}", bytes_per_sample))?;
+            }
+            SoundDescriptionVersion::V2 {
+                audio_sample_rate,
+                num_audio_channels,
+                const_bits_per_channel,
+                format_specific_flags,
+                const_bytes_per_audio_packet,
+                const_lpcm_frames_per_audio_packet,
+            } => {
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tversion: 2"))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\taudio_sample_rate: {:?}", audio_sample_rate))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tnum_audio_channels: {:?}", num_audio_channels))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tconst_bits_per_channel: {:?}", const_bits_per_channel))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tformat_specific_flags: 0x{:08x?}", format_specific_flags))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tconst_bytes_per_audio_packet: {:?}", const_bytes_per_audio_packet))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\tconst_lpcm_frames_per_audio_packet: {:?}", const_lpcm_frames_per_audio_packet))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the child box `box_type` out of a sample entry's trailing boxes,
+/// returning its payload and the remaining boxes untouched.
+fn take_child(r: &mut BytesMut, box_type: u32) -> (Option<BytesMut>, BytesMut) {
+    let mut child = None;
+    let mut rest = BytesMut::new();
+
+    while 8 <= r.len() {
+        let mut b = Object::parse(r);
+
+        if box_type == b.box_type && child.is_none() {
+            child = Some(b.payload);
+        } else {
+            rest.put(b.as_bytes());
+        }
+    }
+    rest.put(r.split_to(r.len()));
+
+    (child, rest)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct avcC {
+    pub configuration_version: u8,
+    pub profile_indication: u8,
+    pub profile_compatibility: u8,
+    pub level_indication: u8,
+    pub length_size_minus_one: u8,
+    pub sequence_parameter_sets: Vec<BytesMut>,
+    pub picture_parameter_sets: Vec<BytesMut>,
+    pub ext: BytesMut,
+}
+
+impl avcC {
+    pub const BOX_TYPE: u32 = types::avcC;
+
+    /// Size in bytes of the length field preceding each NAL unit of a
+    /// sample.
+    pub fn nal_length_size(&self) -> usize {
+        self.length_size_minus_one as usize + 1
+    }
+}
+
+impl Default for avcC {
+    //! aligned(8) class AVCDecoderConfigurationRecord {
+    //!     unsigned int(8) configurationVersion = 1;
+    //!     unsigned int(8) AVCProfileIndication;
+    //!     unsigned int(8) profile_compatibility;
+    //!     unsigned int(8) AVCLevelIndication;
+    //!     bit(6) reserved = '111111'b;
+    //!     unsigned int(2) lengthSizeMinusOne;
+    //!     bit(3) reserved = '111'b;
+    //!     unsigned int(5) numOfSequenceParameterSets;
+    //!     for (i=0; i< numOfSequenceParameterSets; i++) {
+    //!         unsigned int(16) sequenceParameterSetLength ;
+    //!         bit(8*sequenceParameterSetLength) sequenceParameterSetNALUnit;
+    //!     }
+    //!     unsigned int(8) numOfPictureParameterSets;
+    //!     for (i=0; i< numOfPictureParameterSets; i++) {
+    //!         unsigned int(16) pictureParameterSetLength;
+    //!         bit(8*pictureParameterSetLength) pictureParameterSetNALUnit;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            configuration_version: 1,
+            profile_indication: 0,
+            profile_compatibility: 0,
+            level_indication: 0,
+            length_size_minus_one: 3,
+            sequence_parameter_sets: vec![],
+            picture_parameter_sets: vec![],
+            ext: BytesMut::new(),
+        }
+    }
+}
+
+impl Debug for avcC {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tconfiguration_version: {:?}", self.configuration_version))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tprofile_indication: {:?}", self.profile_indication))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tprofile_compatibility: {:?}", self.profile_compatibility))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tlevel_indication: {:?}", self.level_indication))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tlength_size_minus_one: {:?}", self.length_size_minus_one))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tsequence_parameter_sets: {:?}", self.sequence_parameter_sets.len()))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tpicture_parameter_sets: {:?}", self.picture_parameter_sets.len()))?;
+
+        Ok(())
+    }
+}
+
+impl IO for avcC {
+    fn len(&self) -> usize {
+        7 + self.sequence_parameter_sets.iter().map(|it| 2 + it.len()).sum::<usize>()
+            + self.picture_parameter_sets.iter().map(|it| 2 + it.len()).sum::<usize>()
+            + self.ext.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let configuration_version = r.get_u8();
+        let profile_indication = r.get_u8();
+        let profile_compatibility = r.get_u8();
+        let level_indication = r.get_u8();
+        let length_size_minus_one = r.get_u8() & 0x03;
+
+        let mut sequence_parameter_sets = vec![];
+        for _ in 0..(r.get_u8() & 0x1f) {
+            let n = r.get_u16() as usize;
+            sequence_parameter_sets.push(r.split_to(n));
+        }
+        let mut picture_parameter_sets = vec![];
+        for _ in 0..r.get_u8() {
+            let n = r.get_u16() as usize;
+            picture_parameter_sets.push(r.split_to(n));
+        }
+
+        Self {
+            configuration_version,
+            profile_indication,
+            profile_compatibility,
+            level_indication,
+            length_size_minus_one,
+            sequence_parameter_sets,
+            picture_parameter_sets,
+            ext: r.split_to(r.len()),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u8(self.configuration_version);
+        w.put_u8(self.profile_indication);
+        w.put_u8(self.profile_compatibility);
+        w.put_u8(self.level_indication);
+        w.put_u8(0xfc | self.length_size_minus_one);
+        w.put_u8(0xe0 | self.sequence_parameter_sets.len() as u8);
+        for it in &self.sequence_parameter_sets {
+            w.put_u16(it.len() as u16);
+            w.put_slice(it);
+        }
+        w.put_u8(self.picture_parameter_sets.len() as u8);
+        for it in &self.picture_parameter_sets {
+            w.put_u16(it.len() as u16);
+            w.put_slice(it);
+        }
+        w.put_slice(&self.ext);
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct hvcC {
+    pub configuration_version: u8,
+    /// general_profile_space, general_tier_flag and general_profile_idc
+    pub general_profile: u8,
+    pub general_profile_compatibility_flags: u32,
+    pub general_constraint_indicator_flags: u64,
+    pub general_level_idc: u8,
+    pub min_spatial_segmentation_idc: u16,
+    pub parallelism_type: u8,
+    pub chroma_format: u8,
+    pub bit_depth_luma_minus8: u8,
+    pub bit_depth_chroma_minus8: u8,
+    pub avg_frame_rate: u16,
+    /// constantFrameRate, numTemporalLayers and temporalIdNested
+    pub frame_rate_flags: u8,
+    pub length_size_minus_one: u8,
+    /// (array_completeness, NAL_unit_type, nalUnits)
+    pub arrays: Vec<(bool, u8, Vec<BytesMut>)>,
+}
+
+impl hvcC {
+    pub const BOX_TYPE: u32 = types::hvcC;
+
+    /// Size in bytes of the length field preceding each NAL unit of a
+    /// sample.
+    pub fn nal_length_size(&self) -> usize {
+        self.length_size_minus_one as usize + 1
+    }
+}
+
+impl Default for hvcC {
+    //! aligned(8) class HEVCDecoderConfigurationRecord {
+    //!     unsigned int(8) configurationVersion = 1;
+    //!     unsigned int(2) general_profile_space;
+    //!     unsigned int(1) general_tier_flag;
+    //!     unsigned int(5) general_profile_idc;
+    //!     unsigned int(32) general_profile_compatibility_flags;
+    //!     unsigned int(48) general_constraint_indicator_flags;
+    //!     unsigned int(8) general_level_idc;
+    //!     bit(4) reserved = '1111'b;
+    //!     unsigned int(12) min_spatial_segmentation_idc;
+    //!     bit(6) reserved = '111111'b;
+    //!     unsigned int(2) parallelismType;
+    //!     bit(6) reserved = '111111'b;
+    //!     unsigned int(2) chromaFormat;
+    //!     bit(5) reserved = '11111'b;
+    //!     unsigned int(3) bitDepthLumaMinus8;
+    //!     bit(5) reserved = '11111'b;
+    //!     unsigned int(3) bitDepthChromaMinus8;
+    //!     bit(16) avgFrameRate;
+    //!     bit(2) constantFrameRate;
+    //!     bit(3) numTemporalLayers;
+    //!     bit(1) temporalIdNested;
+    //!     unsigned int(2) lengthSizeMinusOne;
+    //!     unsigned int(8) numOfArrays;
+    //!     for (j=0; j < numOfArrays; j++) {
+    //!         bit(1) array_completeness;
+    //!         unsigned int(1) reserved = 0;
+    //!         unsigned int(6) NAL_unit_type;
+    //!         unsigned int(16) numNalus;
+    //!         for (i=0; i< numNalus; i++) {
+    //!             unsigned int(16) nalUnitLength;
+    //!             bit(8*nalUnitLength) nalUnit;
+    //!         }
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            configuration_version: 1,
+            general_profile: 0,
+            general_profile_compatibility_flags: 0,
+            general_constraint_indicator_flags: 0,
+            general_level_idc: 0,
+            min_spatial_segmentation_idc: 0,
+            parallelism_type: 0,
+            chroma_format: 1,
+            bit_depth_luma_minus8: 0,
+            bit_depth_chroma_minus8: 0,
+            avg_frame_rate: 0,
+            frame_rate_flags: 0,
+            length_size_minus_one: 3,
+            arrays: vec![],
+        }
+    }
+}
+
+impl Debug for hvcC {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tconfiguration_version: {:?}", self.configuration_version))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tgeneral_profile: {:?}", self.general_profile))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tgeneral_profile_compatibility_flags: 0x{:08x?}", self.general_profile_compatibility_flags))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tgeneral_constraint_indicator_flags: 0x{:012x?}", self.general_constraint_indicator_flags))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tgeneral_level_idc: {:?}", self.general_level_idc))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tmin_spatial_segmentation_idc: {:?}", self.min_spatial_segmentation_idc))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tparallelism_type: {:?}", self.parallelism_type))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tchroma_format: {:?}", self.chroma_format))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tbit_depth_luma_minus8: {:?}", self.bit_depth_luma_minus8))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tbit_depth_chroma_minus8: {:?}", self.bit_depth_chroma_minus8))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tavg_frame_rate: {:?}", self.avg_frame_rate))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tframe_rate_flags: {:?}", self.frame_rate_flags))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tlength_size_minus_one: {:?}", self.length_size_minus_one))?;
+        for (array_completeness, nal_unit_type, nal_units) in &self.arrays {
+            f.write_fmt(format_args!(
+                "\n\t\t\t\t\t\t\t\t\tarray: {:?}, {:?}, {:?}",
+                array_completeness, nal_unit_type, nal_units.len(),
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for hvcC {
+    fn len(&self) -> usize {
+        23 + self.arrays.iter()
+            .map(|(_, _, it)| 3 + it.iter().map(|it| 2 + it.len()).sum::<usize>())
+            .sum::<usize>()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self {
+            configuration_version: r.get_u8(),
+            general_profile: r.get_u8(),
+            general_profile_compatibility_flags: r.get_u32(),
+            general_constraint_indicator_flags: (r.get_u16() as u64) << 32 | r.get_u32() as u64,
+            general_level_idc: r.get_u8(),
+            min_spatial_segmentation_idc: r.get_u16() & 0x0fff,
+            parallelism_type: r.get_u8() & 0x03,
+            chroma_format: r.get_u8() & 0x03,
+            bit_depth_luma_minus8: r.get_u8() & 0x07,
+            bit_depth_chroma_minus8: r.get_u8() & 0x07,
+            avg_frame_rate: r.get_u16(),
+            frame_rate_flags: 0,
+            length_size_minus_one: 0,
+            arrays: vec![],
+        };
+
+        let v = r.get_u8();
+        rst.frame_rate_flags = v >> 2;
+        rst.length_size_minus_one = v & 0x03;
+
+        for _ in 0..r.get_u8() {
+            let v = r.get_u8();
+            let mut nal_units = vec![];
+            for _ in 0..r.get_u16() {
+                let n = r.get_u16() as usize;
+                nal_units.push(r.split_to(n));
+            }
+
+            rst.arrays.push((0 != v & 0x80, v & 0x3f, nal_units));
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u8(self.configuration_version);
+        w.put_u8(self.general_profile);
+        w.put_u32(self.general_profile_compatibility_flags);
+        w.put_u16((self.general_constraint_indicator_flags >> 32) as u16);
+        w.put_u32(self.general_constraint_indicator_flags as u32);
+        w.put_u8(self.general_level_idc);
+        w.put_u16(0xf000 | self.min_spatial_segmentation_idc);
+        w.put_u8(0xfc | self.parallelism_type);
+        w.put_u8(0xfc | self.chroma_format);
+        w.put_u8(0xf8 | self.bit_depth_luma_minus8);
+        w.put_u8(0xf8 | self.bit_depth_chroma_minus8);
+        w.put_u16(self.avg_frame_rate);
+        w.put_u8(self.frame_rate_flags << 2 | self.length_size_minus_one);
+        w.put_u8(self.arrays.len() as u8);
+        for (array_completeness, nal_unit_type, nal_units) in &self.arrays {
+            w.put_u8(if *array_completeness { 0x80 } else { 0 } | nal_unit_type);
+            w.put_u16(nal_units.len() as u16);
+            for it in nal_units {
+                w.put_u16(it.len() as u16);
+                w.put_slice(it);
+            }
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct dOps {
+    pub version: u8,
+    pub output_channel_count: u8,
+    pub pre_skip: u16,
+    pub input_sample_rate: u32,
+    pub output_gain: i16,
+    pub channel_mapping_family: u8,
+    pub stream_count: u8,
+    pub coupled_count: u8,
+    pub channel_mapping: Vec<u8>,
+}
+
+impl dOps {
+    pub const BOX_TYPE: u32 = types::dOps;
+}
+
+impl Default for dOps {
+    //! extends Box('dOps') {
+    //!     unsigned int(8) Version;
+    //!     unsigned int(8) OutputChannelCount;
+    //!     unsigned int(16) PreSkip;
+    //!     unsigned int(32) InputSampleRate;
+    //!     signed int(16) OutputGain;
+    //!     unsigned int(8) ChannelMappingFamily;
+    //!     if (ChannelMappingFamily != 0) {
+    //!         unsigned int(8) StreamCount;
+    //!         unsigned int(8) CoupledCount;
+    //!         unsigned int(8 * OutputChannelCount) ChannelMapping;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            version: 0,
+            output_channel_count: 2,
+            pre_skip: 0,
+            input_sample_rate: 48000,
+            output_gain: 0,
+            channel_mapping_family: 0,
+            stream_count: 1,
+            coupled_count: 1,
+            channel_mapping: vec![],
+        }
+    }
+}
+
+impl Debug for dOps {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tversion: {:?}", self.version))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\toutput_channel_count: {:?}", self.output_channel_count))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tpre_skip: {:?}", self.pre_skip))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tinput_sample_rate: {:?}", self.input_sample_rate))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\toutput_gain: {:?}", self.output_gain))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tchannel_mapping_family: {:?}", self.channel_mapping_family))?;
+        if 0 != self.channel_mapping_family {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tstream_count: {:?}", self.stream_count))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tcoupled_count: {:?}", self.coupled_count))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tchannel_mapping: {:?}", self.channel_mapping))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for dOps {
+    fn len(&self) -> usize {
+        let mut v = 11;
+
+        if 0 != self.channel_mapping_family {
+            v += 2 + self.output_channel_count as usize;
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self {
+            version: r.get_u8(),
+            output_channel_count: r.get_u8(),
+            pre_skip: r.get_u16(),
+            input_sample_rate: r.get_u32(),
+            output_gain: r.get_i16(),
+            channel_mapping_family: r.get_u8(),
+            stream_count: 1,
+            coupled_count: 0,
+            channel_mapping: vec![],
+        };
+
+        if 0 != rst.channel_mapping_family {
+            rst.stream_count = r.get_u8();
+            rst.coupled_count = r.get_u8();
+            rst.channel_mapping = r.split_to(rst.output_channel_count as usize).to_vec();
+        } else if 2 == rst.output_channel_count {
+            rst.coupled_count = 1;
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u8(self.version);
+        w.put_u8(self.output_channel_count);
+        w.put_u16(self.pre_skip);
+        w.put_u32(self.input_sample_rate);
+        w.put_i16(self.output_gain);
+        w.put_u8(self.channel_mapping_family);
+        if 0 != self.channel_mapping_family {
+            self.channel_mapping.resize(self.output_channel_count as usize, 0);
+
+            w.put_u8(self.stream_count);
+            w.put_u8(self.coupled_count);
+            w.put_slice(&self.channel_mapping);
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct dfLa {
+    base: FullBox,
+
+    pub blocks: Vec<FLACMetadataBlock>,
+}
+
+impl dfLa {
+    pub const BOX_TYPE: u32 = types::dfLa;
+
+    pub fn stream_info(&self) -> Option<&FLACMetadataBlock> {
+        self.blocks.iter().find(|it| matches!(it, FLACMetadataBlock::StreamInfo { .. }))
+    }
+}
+
+impl Default for dfLa {
+    //! extends FullBox('dfLa', version = 0, 0) {
+    //!     for (i=0; ; i++) { // to end of box
+    //!         FLACMetadataBlock();
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            blocks: vec![],
+        }
+    }
+}
+
+impl Debug for dfLa {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tblock_count: {:?}", self.blocks.len()))?;
+        for it in &self.blocks {
+            f.write_fmt(format_args!("\n{:?}", it))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for dfLa {
+    fn len(&self) -> usize {
+        let mut v = self.base.len();
+
+        for it in &self.blocks {
+            v += it.len();
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self {
+            base: FullBox::parse(r),
+            blocks: vec![],
+        };
+
+        while !r.is_empty() {
+            let last = 0 != (0x80 & r[0]);
+
+            rst.blocks.push(FLACMetadataBlock::parse(r));
+            if last {
+                break;
+            }
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+
+        let count = self.blocks.len();
+        for (i, it) in self.blocks.iter_mut().enumerate() {
+            let mut b = it.as_bytes();
+            if i + 1 == count {
+                b[0] |= 0x80;
+            }
+            w.put(b);
+        }
+
+        w
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum FLACMetadataBlock {
+    StreamInfo {
+        min_block_size: u16,
+        max_block_size: u16,
+        min_frame_size: u32,
+        max_frame_size: u32,
+        sample_rate: u32,
+        channels: u8,
+        bits_per_sample: u8,
+        total_samples: u64,
+        md5: [u8; 16],
+    },
+    Unknown {
+        block_type: u8,
+        data: BytesMut,
+    },
+}
+
+impl FLACMetadataBlock {
+    pub const STREAMINFO: u8 = 0;
+}
+
+impl Debug for FLACMetadataBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FLACMetadataBlock::StreamInfo {
+                min_block_size,
+                max_block_size,
+                min_frame_size,
+                max_frame_size,
+                sample_rate,
+                channels,
+                bits_per_sample,
+                total_samples,
+                md5,
+            } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\t\tSTREAMINFO"))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tmin_block_size: {:?}", min_block_size))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tmax_block_size: {:?}", max_block_size))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tmin_frame_size: {:?}", min_frame_size))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tmax_frame_size: {:?}", max_frame_size))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tsample_rate: {:?}", sample_rate))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tchannels: {:?}", channels))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tbits_per_sample: {:?}", bits_per_sample))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\ttotal_samples: {:?}", total_samples))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tmd5: {:02x?}", md5))?;
+            }
+            FLACMetadataBlock::Unknown {
+                block_type,
+                data,
+            } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\t\tblock_type: {:?}", block_type))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tlength: {:?}", data.len()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for FLACMetadataBlock {
+    //! aligned(8) class FLACMetadataBlock {
+    //!     unsigned int(1) LastMetadataBlockFlag;
+    //!     unsigned int(7) BlockType;
+    //!     unsigned int(24) Length;
+    //!     unsigned int(8) BlockData[Length];
+    //! }
+    fn len(&self) -> usize {
+        4 + match self {
+            FLACMetadataBlock::StreamInfo { .. } => { 34 }
+            FLACMetadataBlock::Unknown { data, .. } => { data.len() }
+        }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let t = r.get_u32();
+        let block_type = (0x7F & (t >> 24)) as u8;
+        let mut data = r.split_to((t & 0x00FFFFFF) as usize);
+
+        match block_type {
+            FLACMetadataBlock::STREAMINFO => {
+                let min_block_size = data.get_u16();
+                let max_block_size = data.get_u16();
+                let min_frame_size = (data.get_u16() as u32) << 8 | data.get_u8() as u32;
+                let max_frame_size = (data.get_u16() as u32) << 8 | data.get_u8() as u32;
+                let t = data.get_u64();
+                let mut md5 = [0_u8; 16];
+                data.copy_to_slice(&mut md5);
+
+                FLACMetadataBlock::StreamInfo {
+                    min_block_size,
+                    max_block_size,
+                    min_frame_size,
+                    max_frame_size,
+                    sample_rate: (t >> 44) as u32,
+                    channels: 1 + (0b111 & (t >> 41)) as u8,
+                    bits_per_sample: 1 + (0b11111 & (t >> 36)) as u8,
+                    total_samples: t & 0xF_FFFF_FFFF,
+                    md5,
+                }
+            }
+            _ => {
+                FLACMetadataBlock::Unknown {
+                    block_type,
+                    data,
+                }
+            }
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        match self {
+            FLACMetadataBlock::StreamInfo {
+                min_block_size,
+                max_block_size,
+                min_frame_size,
+                max_frame_size,
+                sample_rate,
+                channels,
+                bits_per_sample,
+                total_samples,
+                md5,
+            } => {
+                w.put_u32((FLACMetadataBlock::STREAMINFO as u32) << 24 | 34);
+
+                w.put_u16(*min_block_size);
+                w.put_u16(*max_block_size);
+                w.put_uint(*min_frame_size as u64, 3);
+                w.put_uint(*max_frame_size as u64, 3);
+                w.put_u64(
+                    (0xF_FFFF & *sample_rate as u64) << 44 |
+                        (0b111 & channels.saturating_sub(1) as u64) << 41 |
+                        (0b11111 & bits_per_sample.saturating_sub(1) as u64) << 36 |
+                        (0xF_FFFF_FFFF & *total_samples)
+                );
+                w.put_slice(md5);
+            }
+            FLACMetadataBlock::Unknown {
+                block_type,
+                data,
+            } => {
+                w.put_u32((0x7F & *block_type as u32) << 24 | data.len() as u32);
+
+                w.put(data.chunk());
+            }
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct dac3 {
+    pub fscod: u8,
+    pub bsid: u8,
+    pub bsmod: u8,
+    pub acmod: u8,
+    pub lfeon: bool,
+    pub bit_rate_code: u8,
+}
+
+impl dac3 {
+    pub const BOX_TYPE: u32 = types::dac3;
+}
+
+impl Default for dac3 {
+    //! extends Box('dac3') {
+    //!     unsigned int(2) fscod;
+    //!     unsigned int(5) bsid;
+    //!     unsigned int(3) bsmod;
+    //!     unsigned int(3) acmod;
+    //!     unsigned int(1) lfeon;
+    //!     unsigned int(5) bit_rate_code;
+    //!     unsigned int(5) reserved = 0;
+    //! }
+    fn default() -> Self {
+        Self {
+            fscod: 0,
+            bsid: 8,
+            bsmod: 0,
+            acmod: 2,
+            lfeon: false,
+            bit_rate_code: 0,
+        }
+    }
+}
+
+impl Debug for dac3 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tfscod: {:?}", self.fscod))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tbsid: {:?}", self.bsid))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tbsmod: {:?}", self.bsmod))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tacmod: {:?}", self.acmod))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tlfeon: {:?}", self.lfeon))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tbit_rate_code: {:?}", self.bit_rate_code))?;
+
+        Ok(())
+    }
+}
+
+impl IO for dac3 {
+    fn len(&self) -> usize {
+        3
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let t = r.get_uint(3) as u32;
+
+        Self {
+            fscod: (0b11 & (t >> 22)) as u8,
+            bsid: (0b11111 & (t >> 17)) as u8,
+            bsmod: (0b111 & (t >> 14)) as u8,
+            acmod: (0b111 & (t >> 11)) as u8,
+            lfeon: 0 != (0b1 & (t >> 10)),
+            bit_rate_code: (0b11111 & (t >> 5)) as u8,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_uint((
+            (0b11 & self.fscod as u32) << 22 |
+                (0b11111 & self.bsid as u32) << 17 |
+                (0b111 & self.bsmod as u32) << 14 |
+                (0b111 & self.acmod as u32) << 11 |
+                (self.lfeon as u32) << 10 |
+                (0b11111 & self.bit_rate_code as u32) << 5
+        ) as u64, 3);
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct dec3 {
+    pub data_rate: u16,
+    pub substreams: Vec<EC3IndependentSubstream>,
+    pub complexity_index_type_a: Option<u8>,
+}
+
+impl dec3 {
+    pub const BOX_TYPE: u32 = types::dec3;
+}
+
+impl Default for dec3 {
+    //! extends Box('dec3') {
+    //!     unsigned int(13) data_rate;
+    //!     unsigned int(3) num_ind_sub;
+    //!     for (i = 0; i < num_ind_sub + 1; i++) {
+    //!         unsigned int(2) fscod;
+    //!         unsigned int(5) bsid;
+    //!         unsigned int(1) reserved = 0;
+    //!         unsigned int(1) asvc;
+    //!         unsigned int(3) bsmod;
+    //!         unsigned int(3) acmod;
+    //!         unsigned int(1) lfeon;
+    //!         unsigned int(3) reserved = 0;
+    //!         unsigned int(4) num_dep_sub;
+    //!         if (num_dep_sub > 0) {
+    //!             unsigned int(9) chan_loc;
+    //!         } else {
+    //!             unsigned int(1) reserved = 0;
+    //!         }
+    //!     }
+    //!     // optional
+    //!     unsigned int(7) reserved = 0;
+    //!     unsigned int(1) flag_ec3_extension_type_a;
+    //!     unsigned int(8) complexity_index_type_a;
+    //! }
+    fn default() -> Self {
+        Self {
+            data_rate: 0,
+            substreams: vec![EC3IndependentSubstream {
+                fscod: 0,
+                bsid: 16,
+                asvc: false,
+                bsmod: 0,
+                acmod: 2,
+                lfeon: false,
+                num_dep_sub: 0,
+                chan_loc: 0,
+            }],
+            complexity_index_type_a: None,
+        }
+    }
+}
+
+impl Debug for dec3 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tdata_rate: {:?}", self.data_rate))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tnum_ind_sub: {:?}", self.substreams.len()))?;
+        for it in &self.substreams {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t{{"))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tfscod: {:?}", it.fscod))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tbsid: {:?}", it.bsid))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tasvc: {:?}", it.asvc))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tbsmod: {:?}", it.bsmod))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tacmod: {:?}", it.acmod))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tlfeon: {:?}", it.lfeon))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tnum_dep_sub: {:?}", it.num_dep_sub))?;
+            if 0 < it.num_dep_sub {
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t\tchan_loc: 0x{:03x?}", it.chan_loc))?;
+            }
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\t\t}}"))?;
+        }
+        if let Some(v) = self.complexity_index_type_a {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tcomplexity_index_type_a: {:?}", v))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for dec3 {
+    fn len(&self) -> usize {
+        let mut v = 2;
+
+        for it in self.substreams.iter().take(8) {
+            v += if 0 < it.num_dep_sub { 4 } else { 3 };
+        }
+        if self.complexity_index_type_a.is_some() {
+            v += 2;
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let t = r.get_u16();
+        let mut rst = Self {
+            data_rate: t >> 3,
+            substreams: Vec::with_capacity(1 + (0b111 & t) as usize),
+            complexity_index_type_a: None,
+        };
+
+        for _ in 0..rst.substreams.capacity() {
+            let t = r.get_uint(3) as u32;
+            let num_dep_sub = (0b1111 & (t >> 1)) as u8;
+
+            rst.substreams.push(EC3IndependentSubstream {
+                fscod: (0b11 & (t >> 22)) as u8,
+                bsid: (0b11111 & (t >> 17)) as u8,
+                asvc: 0 != (0b1 & (t >> 15)),
+                bsmod: (0b111 & (t >> 12)) as u8,
+                acmod: (0b111 & (t >> 9)) as u8,
+                lfeon: 0 != (0b1 & (t >> 8)),
+                num_dep_sub,
+                chan_loc: if 0 < num_dep_sub {
+                    (0b1 & t as u16) << 8 | r.get_u8() as u16
+                } else {
+                    0
+                },
+            });
+        }
+
+        if 2 <= r.len() && 0 != (0b1 & r.get_u8()) {
+            rst.complexity_index_type_a = Some(r.get_u8());
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        // num_ind_sub has 3 bits
+        w.put_u16(self.data_rate << 3 | (0b111 & self.substreams.len().min(8).saturating_sub(1) as u16));
+        for it in self.substreams.iter().take(8) {
+            let t = (0b11 & it.fscod as u32) << 22 |
+                (0b11111 & it.bsid as u32) << 17 |
+                (it.asvc as u32) << 15 |
+                (0b111 & it.bsmod as u32) << 12 |
+                (0b111 & it.acmod as u32) << 9 |
+                (it.lfeon as u32) << 8 |
+                (0b1111 & it.num_dep_sub as u32) << 1;
+
+            if 0 < it.num_dep_sub {
+                w.put_u32(t << 8 | (0x1FF & it.chan_loc as u32));
+            } else {
+                w.put_uint(t as u64, 3);
+            }
+        }
+        if let Some(v) = self.complexity_index_type_a {
+            w.put_u8(1);
+            w.put_u8(v);
+        }
+
+        w
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct EC3IndependentSubstream {
+    pub fscod: u8,
+    pub bsid: u8,
+    pub asvc: bool,
+    pub bsmod: u8,
+    pub acmod: u8,
+    pub lfeon: bool,
+    pub num_dep_sub: u8,
+    pub chan_loc: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HdrTransfer {
+    /// SMPTE ST 2084 perceptual quantizer
+    Pq,
+    /// ARIB STD-B67 hybrid log-gamma
+    Hlg,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub enum colr {
+    nclx {
+        colour_primaries: u16,
+        transfer_characteristics: u16,
+        matrix_coefficients: u16,
+        full_range_flag: bool,
+    },
+    nclc {
+        colour_primaries: u16,
+        transfer_characteristics: u16,
+        matrix_coefficients: u16,
+    },
+    rICC {
+        icc_profile: BytesMut,
+    },
+    prof {
+        icc_profile: BytesMut,
+    },
+    Unknown {
+        colour_type: u32,
+        data: BytesMut,
+    },
+}
+
+impl colr {
+    pub const BOX_TYPE: u32 = types::colr;
+}
+
+impl Default for colr {
+    //! extends Box('colr'){
+    //!     unsigned int(32) colour_type;
+    //!     if (colour_type == 'nclx') { /* on-screen colours */
+    //!         unsigned int(16) colour_primaries;
+    //!         unsigned int(16) transfer_characteristics;
+    //!         unsigned int(16) matrix_coefficients;
+    //!         unsigned int(1) full_range_flag;
+    //!         unsigned int(7) reserved = 0;
+    //!     }
+    //!     else if (colour_type == 'rICC') {
+    //!         ICC_profile; // restricted ICC profile
+    //!     }
+    //!     else if (colour_type == 'prof') {
+    //!         ICC_profile; // unrestricted ICC profile
+    //!     }
+    //! }
+    fn default() -> Self {
+        colr::nclx {
+            colour_primaries: 1,
+            transfer_characteristics: 1,
+            matrix_coefficients: 1,
+            full_range_flag: false,
+        }
+    }
+}
+
+impl Debug for colr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            colr::nclx {
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+                full_range_flag,
+            } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tcolour_type: \"nclx\""))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tcolour_primaries: {:?}", colour_primaries))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\ttransfer_characteristics: {:?}", transfer_characteristics))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tmatrix_coefficients: {:?}", matrix_coefficients))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tfull_range_flag: {:?}", full_range_flag))?;
+            }
+            colr::nclc {
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+            } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tcolour_type: \"nclc\""))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tcolour_primaries: {:?}", colour_primaries))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\ttransfer_characteristics: {:?}", transfer_characteristics))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tmatrix_coefficients: {:?}", matrix_coefficients))?;
+            }
+            colr::rICC { icc_profile } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tcolour_type: \"rICC\""))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\ticc_profile: {:?} bytes", icc_profile.len()))?;
+            }
+            colr::prof { icc_profile } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tcolour_type: \"prof\""))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\ticc_profile: {:?} bytes", icc_profile.len()))?;
+            }
+            colr::Unknown { colour_type, data } => {
+                f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tcolour_type: 0x{:08x?}", colour_type))?;
+                f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tdata: {:?} bytes", data.len()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for colr {
+    fn len(&self) -> usize {
+        4 + match self {
+            colr::nclx { .. } => { 7 }
+            colr::nclc { .. } => { 6 }
+            colr::rICC { icc_profile } => { icc_profile.len() }
+            colr::prof { icc_profile } => { icc_profile.len() }
+            colr::Unknown { data, .. } => { data.len() }
+        }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let colour_type = r.get_u32();
+
+        match colour_type {
+            types::nclx => {
+                colr::nclx {
+                    colour_primaries: r.get_u16(),
+                    transfer_characteristics: r.get_u16(),
+                    matrix_coefficients: r.get_u16(),
+                    full_range_flag: 0 != (0x80 & r.get_u8()),
+                }
+            }
+            types::nclc => {
+                colr::nclc {
+                    colour_primaries: r.get_u16(),
+                    transfer_characteristics: r.get_u16(),
+                    matrix_coefficients: r.get_u16(),
+                }
+            }
+            types::rICC => {
+                colr::rICC {
+                    icc_profile: r.split_to(r.len()),
+                }
+            }
+            types::prof => {
+                colr::prof {
+                    icc_profile: r.split_to(r.len()),
+                }
+            }
+            _ => {
+                colr::Unknown {
+                    colour_type,
+                    data: r.split_to(r.len()),
+                }
+            }
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        match self {
+            colr::nclx {
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+                full_range_flag,
+            } => {
+                w.put_u32(types::nclx);
+                w.put_u16(*colour_primaries);
+                w.put_u16(*transfer_characteristics);
+                w.put_u16(*matrix_coefficients);
+                w.put_u8(if *full_range_flag { 0x80 } else { 0 });
+            }
+            colr::nclc {
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+            } => {
+                w.put_u32(types::nclc);
+                w.put_u16(*colour_primaries);
+                w.put_u16(*transfer_characteristics);
+                w.put_u16(*matrix_coefficients);
+            }
+            colr::rICC { icc_profile } => {
+                w.put_u32(types::rICC);
+                w.put(icc_profile.chunk());
+            }
+            colr::prof { icc_profile } => {
+                w.put_u32(types::prof);
+                w.put(icc_profile.chunk());
+            }
+            colr::Unknown { colour_type, data } => {
+                w.put_u32(*colour_type);
+                w.put(data.chunk());
+            }
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct mdcv {
+    pub display_primaries: [(u16, u16); 3],
+    pub white_point: (u16, u16),
+    pub max_display_mastering_luminance: u32,
+    pub min_display_mastering_luminance: u32,
+}
+
+impl mdcv {
+    pub const BOX_TYPE: u32 = types::mdcv;
+}
+
+impl Default for mdcv {
+    //! extends Box('mdcv') {
+    //!     for (c = 0; c < 3; c++) {
+    //!         unsigned int(16) display_primaries_x;
+    //!         unsigned int(16) display_primaries_y;
+    //!     }
+    //!     unsigned int(16) white_point_x;
+    //!     unsigned int(16) white_point_y;
+    //!     unsigned int(32) max_display_mastering_luminance;
+    //!     unsigned int(32) min_display_mastering_luminance;
+    //! }
+    fn default() -> Self {
+        Self {
+            display_primaries: [(0, 0); 3],
+            white_point: (0, 0),
+            max_display_mastering_luminance: 0,
+            min_display_mastering_luminance: 0,
+        }
+    }
+}
+
+impl Debug for mdcv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tdisplay_primaries: {:?}", self.display_primaries))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\twhite_point: {:?}", self.white_point))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tmax_display_mastering_luminance: {:?}", self.max_display_mastering_luminance))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tmin_display_mastering_luminance: {:?}", self.min_display_mastering_luminance))?;
+
+        Ok(())
+    }
+}
+
+impl IO for mdcv {
+    fn len(&self) -> usize {
+        24
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self::default();
+
+        for it in rst.display_primaries.iter_mut() {
+            *it = (r.get_u16(), r.get_u16());
+        }
+        rst.white_point = (r.get_u16(), r.get_u16());
+        rst.max_display_mastering_luminance = r.get_u32();
+        rst.min_display_mastering_luminance = r.get_u32();
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        for (x, y) in self.display_primaries {
+            w.put_u16(x);
+            w.put_u16(y);
+        }
+        w.put_u16(self.white_point.0);
+        w.put_u16(self.white_point.1);
+        w.put_u32(self.max_display_mastering_luminance);
+        w.put_u32(self.min_display_mastering_luminance);
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct clli {
+    pub max_content_light_level: u16,
+    pub max_pic_average_light_level: u16,
+}
+
+impl clli {
+    pub const BOX_TYPE: u32 = types::clli;
+}
+
+impl Default for clli {
+    //! extends Box('clli') {
+    //!     unsigned int(16) max_content_light_level;
+    //!     unsigned int(16) max_pic_average_light_level;
+    //! }
+    fn default() -> Self {
+        Self {
+            max_content_light_level: 0,
+            max_pic_average_light_level: 0,
+        }
+    }
+}
+
+impl Debug for clli {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tmax_content_light_level: {:?}", self.max_content_light_level))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tmax_pic_average_light_level: {:?}", self.max_pic_average_light_level))?;
+
+        Ok(())
+    }
+}
+
+impl IO for clli {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            max_content_light_level: r.get_u16(),
+            max_pic_average_light_level: r.get_u16(),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u16(self.max_content_light_level);
+        w.put_u16(self.max_pic_average_light_level);
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct pasp {
+    pub h_spacing: u32,
+    pub v_spacing: u32,
+}
+
+impl pasp {
+    pub const BOX_TYPE: u32 = types::pasp;
+
+    pub fn new(h_spacing: u32, v_spacing: u32) -> Self {
+        Self {
+            h_spacing,
+            v_spacing,
+        }
+    }
+}
+
+impl Default for pasp {
+    //! extends Box(‘pasp’){
+    //!     unsigned int(32) hSpacing;
+    //!     unsigned int(32) vSpacing;
+    //! }
+    fn default() -> Self {
+        Self {
+            h_spacing: 1,
+            v_spacing: 1,
+        }
+    }
+}
+
+impl Debug for pasp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\th_spacing: {:?}", self.h_spacing))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tv_spacing: {:?}", self.v_spacing))?;
+
+        Ok(())
+    }
+}
+
+impl IO for pasp {
+    fn len(&self) -> usize {
+        8
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            h_spacing: r.get_u32(),
+            v_spacing: r.get_u32(),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u32(self.h_spacing);
+        w.put_u32(self.v_spacing);
 
         w
     }
 }
 
-impl SampleEntry {
-    fn get_handler_type(&self) -> u32 {
-        match self {
-            SampleEntry::Base { handler_type, .. } => {
-                *handler_type
-            }
-            SampleEntry::Visual { base, .. } => {
-                base.get_handler_type()
-            }
-            SampleEntry::Audio { base, .. } => {
-                base.get_handler_type()
-            }
-            SampleEntry::avc1 { base, .. } => {
-                base.get_handler_type()
-            }
-            SampleEntry::mp4a { base, .. } => {
-                base.get_handler_type()
-            }
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct clap {
+    pub clean_aperture_width_n: u32,
+    pub clean_aperture_width_d: u32,
+    pub clean_aperture_height_n: u32,
+    pub clean_aperture_height_d: u32,
+    pub horiz_off_n: i32,
+    pub horiz_off_d: u32,
+    pub vert_off_n: i32,
+    pub vert_off_d: u32,
+}
+
+impl clap {
+    pub const BOX_TYPE: u32 = types::clap;
+}
+
+impl Default for clap {
+    //! extends Box(‘clap’){
+    //!     unsigned int(32) cleanApertureWidthN;
+    //!     unsigned int(32) cleanApertureWidthD;
+    //!     unsigned int(32) cleanApertureHeightN;
+    //!     unsigned int(32) cleanApertureHeightD;
+    //!     unsigned int(32) horizOffN;
+    //!     unsigned int(32) horizOffD;
+    //!     unsigned int(32) vertOffN;
+    //!     unsigned int(32) vertOffD;
+    //! }
+    fn default() -> Self {
+        Self {
+            clean_aperture_width_n: 0,
+            clean_aperture_width_d: 1,
+            clean_aperture_height_n: 0,
+            clean_aperture_height_d: 1,
+            horiz_off_n: 0,
+            horiz_off_d: 1,
+            vert_off_n: 0,
+            vert_off_d: 1,
+        }
+    }
+}
+
+impl Debug for clap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\t\t\t\tclean_aperture_width: {:?}/{:?}", self.clean_aperture_width_n, self.clean_aperture_width_d))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tclean_aperture_height: {:?}/{:?}", self.clean_aperture_height_n, self.clean_aperture_height_d))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\thoriz_off: {:?}/{:?}", self.horiz_off_n, self.horiz_off_d))?;
+        f.write_fmt(format_args!("\n\t\t\t\t\t\t\t\t\tvert_off: {:?}/{:?}", self.vert_off_n, self.vert_off_d))?;
+
+        Ok(())
+    }
+}
+
+impl IO for clap {
+    fn len(&self) -> usize {
+        32
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            clean_aperture_width_n: r.get_u32(),
+            clean_aperture_width_d: r.get_u32(),
+            clean_aperture_height_n: r.get_u32(),
+            clean_aperture_height_d: r.get_u32(),
+            horiz_off_n: r.get_i32(),
+            horiz_off_d: r.get_u32(),
+            vert_off_n: r.get_i32(),
+            vert_off_d: r.get_u32(),
         }
     }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u32(self.clean_aperture_width_n);
+        w.put_u32(self.clean_aperture_width_d);
+        w.put_u32(self.clean_aperture_height_n);
+        w.put_u32(self.clean_aperture_height_d);
+        w.put_i32(self.horiz_off_n);
+        w.put_u32(self.horiz_off_d);
+        w.put_i32(self.vert_off_n);
+        w.put_u32(self.vert_off_d);
+
+        w
+    }
 }
 
 #[allow(non_camel_case_types)]
@@ -2289,6 +5293,92 @@ impl IO for stts {
     }
 }
 
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct ctts {
+    base: FullBox,
+
+    pub entries: Vec<(u32, i32)>,
+}
+
+impl ctts {
+    pub const BOX_TYPE: u32 = types::ctts;
+}
+
+impl Default for ctts {
+    //! extends FullBox(‘ctts’, version, 0) {
+    //!     unsigned int(32) entry_count;
+    //!     int i;
+    //!     if (version==0) {
+    //!         for (i=0; i < entry_count; i++) {
+    //!             unsigned int(32) sample_count;
+    //!             unsigned int(32) sample_offset;
+    //!         }
+    //!     }
+    //!     else if (version == 1) {
+    //!         for (i=0; i < entry_count; i++) {
+    //!             unsigned int(32) sample_count;
+    //!             signed int(32) sample_offset;
+    //!         }
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            entries: vec![],
+        }
+    }
+}
+
+impl Debug for ctts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\tentry_count: {:?}", self.entries.len()))?;
+        for (sample_count, sample_offset) in &self.entries {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\tsample_count: {:?}", sample_count))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\tsample_offset: {:?}", sample_offset))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for ctts {
+    fn len(&self) -> usize {
+        self.base.len() + 4 + 8 * self.entries.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self {
+            base: FullBox::parse(r),
+            entries: vec![],
+        };
+
+        // offsets of version 0 above i32::MAX are not expected in practice
+        let entry_count = r.get_u32();
+        for _ in 0..entry_count {
+            rst.entries.push((r.get_u32(), r.get_i32()))
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.version = if self.entries.iter().any(|it| it.1 < 0) { 1 } else { 0 };
+        w.put(self.base.as_bytes());
+
+        w.put_u32(self.entries.len() as u32);
+
+        for (count, offset) in &self.entries {
+            w.put_u32(*count);
+            w.put_i32(*offset);
+        }
+
+        w
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(PartialEq)]
 pub struct stsc {
@@ -2411,29 +5501,176 @@ impl Debug for stsz {
     }
 }
 
-impl IO for stsz {
+impl IO for stsz {
+    fn len(&self) -> usize {
+        let mut v = self.base.len() + 4;
+
+        if 0 == self.sample_size {
+            v += 4 + 4 * self.entries.len()
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self {
+            base: FullBox::parse(r),
+            sample_size: r.get_u32(),
+            entries: vec![],
+        };
+
+        if 0 == rst.sample_size {
+            let sample_count = r.get_u32();
+            for _ in 0..sample_count {
+                rst.entries.push(r.get_u32())
+            }
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+
+        w.put_u32(self.sample_size);
+        w.put_u32(self.entries.len() as u32);
+
+        if 0 == self.sample_size {
+            for entry_size in &self.entries {
+                w.put_u32(*entry_size);
+            }
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct stco {
+    base: FullBox,
+
+    pub entries: Vec<u32>,
+}
+
+impl stco {
+    pub const BOX_TYPE: u32 = types::stco;
+}
+
+impl Default for stco {
+    //! extends FullBox(‘stco’, version = 0, 0) {
+    //!     unsigned int(32) entry_count;
+    //!     for (i=1; i u entry_count; i++) {
+    //!         unsigned int(32) chunk_offset;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            entries: vec![],
+        }
+    }
+}
+
+impl Debug for stco {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\tentry_count: {:?}", self.entries.len()))?;
+        for chunk_offset in &self.entries {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\tchunk_offset: {:?}", chunk_offset))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for stco {
+    fn len(&self) -> usize {
+        self.base.len() + 4 + 4 * self.entries.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self {
+            base: FullBox::parse(r),
+            entries: vec![],
+        };
+
+        let entry_count = r.get_u32();
+        for _ in 0..entry_count {
+            rst.entries.push(r.get_u32())
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+
+        w.put_u32(self.entries.len() as u32);
+
+        for chunk_offset in &self.entries {
+            w.put_u32(*chunk_offset);
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct co64 {
+    base: FullBox,
+
+    pub entries: Vec<u64>,
+}
+
+impl co64 {
+    pub const BOX_TYPE: u32 = types::co64;
+}
+
+impl Default for co64 {
+    //! extends FullBox(‘co64’, version = 0, 0) {
+    //!     unsigned int(32) entry_count;
+    //!     for (i=1; i u entry_count; i++) {
+    //!         unsigned int(64) chunk_offset;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            entries: vec![],
+        }
+    }
+}
+
+impl Debug for co64 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t\t\t\tentry_count: {:?}", self.entries.len()))?;
+        for chunk_offset in &self.entries {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\tchunk_offset: {:?}", chunk_offset))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for co64 {
     fn len(&self) -> usize {
-        let mut v = self.base.len() + 4;
-
-        if 0 == self.sample_size {
-            v += 4 + 4 * self.entries.len()
-        }
-
-        v
+        self.base.len() + 4 + 8 * self.entries.len()
     }
 
     fn parse(r: &mut BytesMut) -> Self {
         let mut rst = Self {
             base: FullBox::parse(r),
-            sample_size: r.get_u32(),
             entries: vec![],
         };
 
-        if 0 == rst.sample_size {
-            let sample_count = r.get_u32();
-            for _ in 0..sample_count {
-                rst.entries.push(r.get_u32())
-            }
+        let entry_count = r.get_u32();
+        for _ in 0..entry_count {
+            rst.entries.push(r.get_u64())
         }
 
         rst
@@ -2444,13 +5681,10 @@ impl IO for stsz {
 
         w.put(self.base.as_bytes());
 
-        w.put_u32(self.sample_size);
         w.put_u32(self.entries.len() as u32);
 
-        if 0 == self.sample_size {
-            for entry_size in &self.entries {
-                w.put_u32(*entry_size);
-            }
+        for chunk_offset in &self.entries {
+            w.put_u64(*chunk_offset);
         }
 
         w
@@ -2459,21 +5693,23 @@ impl IO for stsz {
 
 #[allow(non_camel_case_types)]
 #[derive(PartialEq)]
-pub struct stco {
+pub struct stss {
     base: FullBox,
 
+    /// 1-based numbers of the sync samples, in increasing order
     pub entries: Vec<u32>,
 }
 
-impl stco {
-    pub const BOX_TYPE: u32 = types::stco;
+impl stss {
+    pub const BOX_TYPE: u32 = types::stss;
 }
 
-impl Default for stco {
-    //! extends FullBox(‘stco’, version = 0, 0) {
+impl Default for stss {
+    //! extends FullBox(‘stss’, version = 0, 0) {
     //!     unsigned int(32) entry_count;
-    //!     for (i=1; i u entry_count; i++) {
-    //!         unsigned int(32) chunk_offset;
+    //!     int i;
+    //!     for (i=0; i < entry_count; i++) {
+    //!         unsigned int(32) sample_number;
     //!     }
     //! }
     fn default() -> Self {
@@ -2484,18 +5720,18 @@ impl Default for stco {
     }
 }
 
-impl Debug for stco {
+impl Debug for stss {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("\t\t\t\t\t\tentry_count: {:?}", self.entries.len()))?;
-        for chunk_offset in &self.entries {
-            f.write_fmt(format_args!("\n\t\t\t\t\t\t\tchunk_offset: {:?}", chunk_offset))?;
+        if !self.entries.is_empty() {
+            f.write_fmt(format_args!("\n\t\t\t\t\t\t\tsample_number: {:?}", self.entries))?;
         }
 
         Ok(())
     }
 }
 
-impl IO for stco {
+impl IO for stss {
     fn len(&self) -> usize {
         self.base.len() + 4 + 4 * self.entries.len()
     }
@@ -2521,19 +5757,20 @@ impl IO for stco {
 
         w.put_u32(self.entries.len() as u32);
 
-        for chunk_offset in &self.entries {
-            w.put_u32(*chunk_offset);
+        for sample_number in &self.entries {
+            w.put_u32(*sample_number);
         }
 
         w
     }
 }
 
-
 #[allow(non_camel_case_types)]
 #[derive(PartialEq)]
 pub struct mvex {
+    pub mehd: Option<mehd>,
     pub trexs: Vec<trex>,
+    pub treps: Vec<trep>,
 }
 
 impl mvex {
@@ -2543,17 +5780,27 @@ impl mvex {
 impl Default for mvex {
     fn default() -> Self {
         Self {
-            trexs: vec![]
+            mehd: None,
+            trexs: vec![],
+            treps: vec![],
         }
     }
 }
 
 impl Debug for mvex {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(it) = &self.mehd {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"mehd\"\n", mehd::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
         for it in &self.trexs {
             f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"trex\"\n", trex::BOX_TYPE))?;
             it.fmt(f)?;
         }
+        for it in &self.treps {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"trep\"\n", trep::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
 
         Ok(())
     }
@@ -2563,9 +5810,15 @@ impl IO for mvex {
     fn len(&self) -> usize {
         let mut v = 0;
 
+        if let Some(it) = &self.mehd {
+            v += 8 + it.len();
+        }
         for it in &self.trexs {
             v += 8 + it.len();
         }
+        for it in &self.treps {
+            v += 8 + it.len();
+        }
 
         v
     }
@@ -2576,10 +5829,18 @@ impl IO for mvex {
         while 0 < r.len() {
             let mut b = Object::parse(r);
             match b.box_type {
+                // mehd: Movie Extends Header
+                mehd::BOX_TYPE => {
+                    rst.mehd = Some(mehd::parse(&mut b.payload));
+                }
                 // trex: Track Extends
                 trex::BOX_TYPE => {
                     rst.trexs.push(trex::parse(&mut b.payload));
                 }
+                // trep: Track Extension Properties
+                trep::BOX_TYPE => {
+                    rst.treps.push(trep::parse(&mut b.payload));
+                }
                 _ => {}
             }
         }
@@ -2590,12 +5851,158 @@ impl IO for mvex {
     fn as_bytes(&mut self) -> BytesMut {
         let mut w = BytesMut::new();
 
+        if let Some(it) = &mut self.mehd {
+            w.put(Object::new(mehd::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
         for it in self.trexs.iter_mut() {
-            w.put(Object {
-                box_type: trex::BOX_TYPE,
-                payload: it.as_bytes(),
-            }.as_bytes());
+            w.put(Object::new(trex::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        for it in self.treps.iter_mut() {
+            w.put(Object::new(trep::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct mehd {
+    base: FullBox,
+
+    /// duration of the whole fragmented movie, in the `mvhd` timescale
+    pub fragment_duration: u64,
+}
+
+impl mehd {
+    pub const BOX_TYPE: u32 = types::mehd;
+
+    pub fn new(fragment_duration: u64) -> Self {
+        Self {
+            base: FullBox::new(
+                if (u32::MAX as u64) < fragment_duration { 1 } else { 0 },
+                0,
+            ),
+            fragment_duration,
+        }
+    }
+}
+
+impl Default for mehd {
+    //! extends FullBox(‘mehd’, version, 0) {
+    //!     if (version==1) {
+    //!         unsigned int(64) fragment_duration;
+    //!     } else { // version==0
+    //!         unsigned int(32) fragment_duration;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Debug for mehd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\tfragment_duration: {:?}", self.fragment_duration))?;
+
+        Ok(())
+    }
+}
+
+impl IO for mehd {
+    fn len(&self) -> usize {
+        self.base.len() + if (u32::MAX as u64) < self.fragment_duration { 8 } else { 4 }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let fragment_duration = if 1 == base.version {
+            r.get_u64()
+        } else {
+            r.get_u32() as u64
+        };
+
+        Self {
+            base,
+            fragment_duration,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.version = if (u32::MAX as u64) < self.fragment_duration { 1 } else { 0 };
+        w.put(self.base.as_bytes());
+
+        if 1 == self.base.version {
+            w.put_u64(self.fragment_duration);
+        } else {
+            w.put_u32(self.fragment_duration as u32);
+        }
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct trep {
+    base: FullBox,
+
+    pub track_id: u32,
+    /// child boxes, such as `cslg` or `assp`, kept as is
+    pub ext: BytesMut,
+}
+
+impl trep {
+    pub const BOX_TYPE: u32 = types::trep;
+}
+
+impl Default for trep {
+    //! extends FullBox(‘trep’, 0, 0) {
+    //!     unsigned int(32) track_ID;
+    //!     // Any number of boxes may follow
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            track_id: 0,
+            ext: BytesMut::new(),
+        }
+    }
+}
+
+impl Debug for trep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\ttrack_id: {:?}", self.track_id))?;
+        f.write_fmt(format_args!("\n\t\t\text: {:?} bytes", self.ext.len()))?;
+
+        Ok(())
+    }
+}
+
+impl IO for trep {
+    fn len(&self) -> usize {
+        self.base.len() + 4 + self.ext.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let track_id = r.get_u32();
+
+        Self {
+            base,
+            track_id,
+            ext: r.split_to(r.len()),
         }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+        w.put_u32(self.track_id);
+        w.put(self.ext.chunk());
 
         w
     }
@@ -2676,11 +6083,15 @@ impl trex {
 
 #[cfg(test)]
 mod tests {
-    use bytes::BytesMut;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use bytes::{BufMut, BytesMut};
 
-    use crate::{IO, Object};
+    use crate::{Error, IO, Object};
     use crate::types::types;
-    use crate::moov::{dinf, edts, elst, hdlr, mdhd, mdia, MediaInformationHeader, minf, moov, mvex, mvhd, SampleEntry, smhd, stbl, stsd, tkhd, trak, trex, vmhd};
+    use crate::udta::{Chapter, MetadataValue, udta};
+    use crate::moov::{clap, clli, colr, ctts, dac3, dec3, dfLa, dinf, dOps, EC3IndependentSubstream, Edit, edts, elst, FLACMetadataBlock, HdrTransfer, hdlr, hvcC, mdcv, mdhd, mdia, MediaInformationHeader, mehd, minf, moov, mvex, mvhd, pasp, SampleEntry, smhd, SoundDescriptionVersion, stbl, stsd, tkhd, TrackReferenceType, trak, tref, trep, trex, vmhd};
 
     #[test]
     fn chk_moov() {
@@ -2704,6 +6115,7 @@ mod tests {
 
                         v
                     },
+                    tref: None,
                     edts: Some(
                         edts {
                             elst: Some({
@@ -2758,6 +6170,7 @@ mod tests {
                             },
                         },
                     },
+                    udta: None,
                 },
                 trak {
                     tkhd: {
@@ -2769,6 +6182,7 @@ mod tests {
 
                         v
                     },
+                    tref: None,
                     edts: Some(
                         edts {
                             elst: Some({
@@ -2805,8 +6219,13 @@ mod tests {
                                                 handler_type: types::mp4a,
                                                 data_reference_index: 1,
                                             }),
+                                            version: SoundDescriptionVersion::V0,
+                                            revision_level: 0,
+                                            vendor: 0,
                                             channel_count: 2,
                                             sample_size: 16,
+                                            compression_id: 0,
+                                            packet_size: 0,
                                             sample_rate: 1445068800,
                                         }),
                                         ext: BytesMut::new(),
@@ -2819,9 +6238,11 @@ mod tests {
                             },
                         },
                     },
+                    udta: None,
                 },
             ],
-            mvex: mvex {
+            mvex: Some(mvex {
+                mehd: Some(mehd::new(u32::MAX as u64 + 1)),
                 trexs: vec![
                     {
                         let mut v = trex::default();
@@ -2840,16 +6261,654 @@ mod tests {
 
                         v
                     },
-                ]
-            }
+                ],
+                treps: vec![
+                    {
+                        let mut v = trep::default();
+
+                        v.track_id = 1;
+                        v.ext = BytesMut::from(&[0, 0, 0, 8, 0x61, 0x73, 0x73, 0x70][..]);
+
+                        v
+                    },
+                ],
+            }),
+            udta: None,
         };
-        let mut obj = Object::parse(&mut Object {
-            box_type: moov::BOX_TYPE,
-            payload: b.as_bytes(),
-        }.as_bytes());
+        let mut obj = Object::parse(&mut Object::new(moov::BOX_TYPE, b.as_bytes()).as_bytes());
 
         assert_eq!(moov::BOX_TYPE, obj.box_type);
         assert_eq!(b.len(), obj.payload.len());
         assert_eq!(b, moov::parse(&mut obj.payload));
+
+        b.mvex = None;
+        let mut obj = Object::parse(&mut Object::new(moov::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, moov::parse(&mut obj.payload));
+
+        b.set_tag(types::_nam, MetadataValue::Text("Title".to_owned()));
+        b.set_tag(types::_too, MetadataValue::Text("isobmff".to_owned()));
+        b.remove_tag(types::_too);
+        b.traks[1].udta = Some(udta {
+            meta: None,
+            chpl: None,
+            ext: BytesMut::from(&[0, 0, 0, 12, 0x6e, 0x61, 0x6d, 0x65, 0x61, 0x75, 0x64, 0x69][..]),
+        });
+        let mut obj = Object::parse(&mut Object::new(moov::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, moov::parse(&mut obj.payload));
+        assert_eq!(Some(&MetadataValue::Text("Title".to_owned())), b.tag(types::_nam));
+        assert_eq!(1, b.ilst().unwrap().items.len());
+    }
+
+    #[test]
+    fn chk_opus_flac() {
+        let mut b = stsd::default();
+
+        b.entries.push(SampleEntry::Opus {
+            base: Box::new(SampleEntry::Audio {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: types::Opus,
+                    data_reference_index: 1,
+                }),
+                version: SoundDescriptionVersion::V0,
+                revision_level: 0,
+                vendor: 0,
+                channel_count: 6,
+                sample_size: 16,
+                compression_id: 0,
+                packet_size: 0,
+                sample_rate: 48000 << 16,
+            }),
+            dOps: {
+                let mut v = dOps::default();
+
+                v.output_channel_count = 6;
+                v.pre_skip = 312;
+                v.output_gain = -256;
+                v.channel_mapping_family = 1;
+                v.stream_count = 4;
+                v.coupled_count = 2;
+                v.channel_mapping = vec![0, 4, 1, 2, 3, 5];
+
+                Some(v)
+            },
+            ext: BytesMut::new(),
+        });
+        b.entries.push(SampleEntry::fLaC {
+            base: Box::new(SampleEntry::Audio {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: types::fLaC,
+                    data_reference_index: 1,
+                }),
+                version: SoundDescriptionVersion::V0,
+                revision_level: 0,
+                vendor: 0,
+                channel_count: 2,
+                sample_size: 24,
+                compression_id: 0,
+                packet_size: 0,
+                sample_rate: 44100 << 16,
+            }),
+            dfLa: {
+                let mut v = dfLa::default();
+
+                v.blocks.push(FLACMetadataBlock::StreamInfo {
+                    min_block_size: 4096,
+                    max_block_size: 4096,
+                    min_frame_size: 14,
+                    max_frame_size: 12345,
+                    sample_rate: 44100,
+                    channels: 2,
+                    bits_per_sample: 24,
+                    total_samples: 0x1_2345_6789,
+                    md5: [0xA5; 16],
+                });
+                v.blocks.push(FLACMetadataBlock::Unknown {
+                    block_type: 4,
+                    data: BytesMut::from(&b"\x08\0\0\0reference"[..]),
+                });
+
+                Some(v)
+            },
+            ext: BytesMut::new(),
+        });
+
+        b.entries.push(SampleEntry::fLaC {
+            base: Box::new(SampleEntry::Audio {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: types::fLaC,
+                    data_reference_index: 1,
+                }),
+                version: SoundDescriptionVersion::V0,
+                revision_level: 0,
+                vendor: 0,
+                channel_count: 2,
+                sample_size: 16,
+                compression_id: 0,
+                packet_size: 0,
+                sample_rate: 48000 << 16,
+            }),
+            dfLa: None,
+            ext: BytesMut::new(),
+        });
+
+        let mut obj = Object::parse(&mut Object::new(stsd::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, stsd::parse(&mut obj.payload));
+    }
+
+    #[test]
+    fn chk_ac3_ec3() {
+        let mut b = stsd::default();
+
+        b.entries.push(SampleEntry::ac_3 {
+            base: Box::new(SampleEntry::Audio {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: types::ac_3,
+                    data_reference_index: 1,
+                }),
+                version: SoundDescriptionVersion::V0,
+                revision_level: 0,
+                vendor: 0,
+                channel_count: 2,
+                sample_size: 16,
+                compression_id: 0,
+                packet_size: 0,
+                sample_rate: 48000 << 16,
+            }),
+            dac3: {
+                let mut v = dac3::default();
+
+                v.acmod = 7;
+                v.lfeon = true;
+                v.bit_rate_code = 14;
+
+                Some(v)
+            },
+            ext: BytesMut::new(),
+        });
+        b.entries.push(SampleEntry::ec_3 {
+            base: Box::new(SampleEntry::Audio {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: types::ec_3,
+                    data_reference_index: 1,
+                }),
+                version: SoundDescriptionVersion::V0,
+                revision_level: 0,
+                vendor: 0,
+                channel_count: 2,
+                sample_size: 16,
+                compression_id: 0,
+                packet_size: 0,
+                sample_rate: 48000 << 16,
+            }),
+            dec3: {
+                let mut v = dec3::default();
+
+                v.data_rate = 768;
+                v.substreams.clear();
+                v.substreams.push(EC3IndependentSubstream {
+                    fscod: 0,
+                    bsid: 16,
+                    asvc: false,
+                    bsmod: 0,
+                    acmod: 7,
+                    lfeon: true,
+                    num_dep_sub: 1,
+                    chan_loc: 0x100,
+                });
+                v.substreams.push(EC3IndependentSubstream {
+                    fscod: 0,
+                    bsid: 16,
+                    asvc: true,
+                    bsmod: 2,
+                    acmod: 1,
+                    lfeon: false,
+                    num_dep_sub: 0,
+                    chan_loc: 0,
+                });
+                v.complexity_index_type_a = Some(16);
+
+                Some(v)
+            },
+            ext: BytesMut::new(),
+        });
+
+        for dec3 in [Some(dec3::default()), None] {
+            b.entries.push(SampleEntry::ec_3 {
+                base: Box::new(SampleEntry::Audio {
+                    base: Box::new(SampleEntry::Base {
+                        handler_type: types::ec_3,
+                        data_reference_index: 1,
+                    }),
+                    version: SoundDescriptionVersion::V0,
+                    revision_level: 0,
+                    vendor: 0,
+                    channel_count: 2,
+                    sample_size: 16,
+                    compression_id: 0,
+                    packet_size: 0,
+                    sample_rate: 48000 << 16,
+                }),
+                dec3,
+                ext: BytesMut::new(),
+            });
+        }
+
+        let mut obj = Object::parse(&mut Object::new(stsd::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, stsd::parse(&mut obj.payload));
+
+        // at most 8 independent substreams are written
+        let mut v = dec3::default();
+        v.substreams = vec![v.substreams[0].clone(); 9];
+        let mut w = v.as_bytes();
+        assert_eq!(v.len(), w.len());
+        assert_eq!(8, dec3::parse(&mut w).substreams.len());
+    }
+
+    #[test]
+    fn chk_unknown_sample_entry() {
+        let mut b = mdia {
+            mdhd: mdhd::default(),
+            hdlr: hdlr::vide("VideoHandler"),
+            minf: minf {
+                mhd: MediaInformationHeader::vmhd(vmhd::default()),
+                dinf: dinf::default(),
+                stbl: {
+                    let mut v = stbl::default();
+
+                    v.stsd.entries.push(SampleEntry::Unknown {
+                        base: Box::new(SampleEntry::Visual {
+                            base: Box::new(SampleEntry::Base {
+                                handler_type: 0x61706368,
+                                data_reference_index: 1,
+                            }),
+                            width: 1920,
+                            height: 1080,
+                            horiz_resolution: 0x00480000,
+                            vert_resolution: 0x00480000,
+                            frame_count: 1,
+                            compressor_name: "Apple ProRes 422 HQ".to_owned(),
+                            depth: 24,
+                        }),
+                        ext: Object::new(types::btrt, BytesMut::from(&[0_u8; 12][..])).as_bytes(),
+                    });
+
+                    v
+                },
+            },
+        };
+        let mut obj = Object::parse(&mut Object::new(mdia::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, mdia::parse(&mut obj.payload));
+    }
+
+    #[test]
+    fn chk_sound_description() {
+        let mut b = mdia {
+            mdhd: mdhd::default(),
+            hdlr: hdlr {
+                component_type: types::mhlr,
+                ..hdlr::soun("SoundHandler")
+            },
+            minf: minf {
+                mhd: MediaInformationHeader::smhd(smhd::default()),
+                dinf: dinf::default(),
+                stbl: {
+                    let mut v = stbl::default();
+
+                    v.stsd.entries.push(SampleEntry::Unknown {
+                        base: Box::new(SampleEntry::Audio {
+                            base: Box::new(SampleEntry::Base {
+                                handler_type: 0x74776f73,
+                                data_reference_index: 1,
+                            }),
+                            version: SoundDescriptionVersion::V1 {
+                                samples_per_packet: 1,
+                                bytes_per_packet: 2,
+                                bytes_per_frame: 4,
+                                bytes_per_sample: 2,
+                            },
+                            revision_level: 0,
+                            vendor: 0x6170706c,
+                            channel_count: 2,
+                            sample_size: 16,
+                            compression_id: 0,
+                            packet_size: 0,
+                            sample_rate: 48000 << 16,
+                        }),
+                        ext: BytesMut::new(),
+                    });
+                    v.stsd.entries.push(SampleEntry::Unknown {
+                        base: Box::new(SampleEntry::Audio {
+                            base: Box::new(SampleEntry::Base {
+                                handler_type: 0x6c70636d,
+                                data_reference_index: 1,
+                            }),
+                            version: SoundDescriptionVersion::V2 {
+                                audio_sample_rate: 192000.0,
+                                num_audio_channels: 2,
+                                const_bits_per_channel: 24,
+                                format_specific_flags: 0x0000000C,
+                                const_bytes_per_audio_packet: 6,
+                                const_lpcm_frames_per_audio_packet: 1,
+                            },
+                            revision_level: 0,
+                            vendor: 0,
+                            channel_count: 3,
+                            sample_size: 16,
+                            compression_id: -2,
+                            packet_size: 0,
+                            sample_rate: 0x00010000,
+                        }),
+                        ext: BytesMut::new(),
+                    });
+                    v.stsd.entries.push(SampleEntry::Unknown {
+                        base: Box::new(SampleEntry::Audio {
+                            base: Box::new(SampleEntry::Base {
+                                handler_type: 0x696d6134,
+                                data_reference_index: 1,
+                            }),
+                            version: SoundDescriptionVersion::V0,
+                            revision_level: 0,
+                            vendor: 0,
+                            channel_count: 1,
+                            sample_size: 16,
+                            compression_id: -1,
+                            packet_size: 34,
+                            sample_rate: 44100 << 16,
+                        }),
+                        ext: BytesMut::new(),
+                    });
+
+                    v
+                },
+            },
+        };
+        let mut obj = Object::parse(&mut Object::new(mdia::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        let v = mdia::parse(&mut obj.payload);
+        assert_eq!(Some(48000.0), v.minf.stbl.stsd.entries[0].sample_rate());
+        assert_eq!(Some(192000.0), v.minf.stbl.stsd.entries[1].sample_rate());
+        assert_eq!(Some(2), v.minf.stbl.stsd.entries[1].channel_count());
+        assert_eq!(b, v);
+
+        // the same entries in an ISO file keep the extension in `ext`
+        b.hdlr.component_type = 0;
+        let v = mdia::parse(&mut b.as_bytes());
+        let SampleEntry::Unknown { base, ext } = &v.minf.stbl.stsd.entries[0] else { panic!() };
+        assert!(matches!(**base, SampleEntry::Audio { version: SoundDescriptionVersion::Iso { version: 1 }, vendor: 0x6170706c, .. }));
+        assert_eq!(16, ext.len());
+        assert_eq!(Some(1.0), v.minf.stbl.stsd.entries[1].sample_rate());
+
+        // a QuickTime entry too short for its version
+        let mut e = Object::new(types::mp4a, BytesMut::from(&[0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2, 0, 16, 0xff, 0xfe, 0, 0, 0, 1, 0, 0][..])).as_bytes();
+        let SampleEntry::mp4a { base, ext } = SampleEntry::parse_with(&mut e, true) else { panic!() };
+        assert!(matches!(*base, SampleEntry::Audio { version: SoundDescriptionVersion::Iso { version: 2 }, .. }));
+        assert!(ext.is_empty());
+    }
+
+    #[test]
+    fn chk_colour_information() {
+        let mut ext = Object::new(hvcC::BOX_TYPE, BytesMut::from(&[1_u8, 2, 3, 4][..])).as_bytes();
+        ext.put(Object::new(colr::BOX_TYPE, colr::nclx {
+            colour_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
+            full_range_flag: false,
+        }.as_bytes()).as_bytes());
+        ext.put(Object::new(mdcv::BOX_TYPE, {
+            let mut v = mdcv::default();
+
+            v.display_primaries = [(8500, 39850), (6550, 2300), (35400, 14600)];
+            v.white_point = (15635, 16450);
+            v.max_display_mastering_luminance = 10000000;
+            v.min_display_mastering_luminance = 50;
+
+            v.as_bytes()
+        }).as_bytes());
+        ext.put(Object::new(types::btrt, BytesMut::from(&[0_u8; 12][..])).as_bytes());
+        ext.put(Object::new(clli::BOX_TYPE, {
+            let mut v = clli::default();
+
+            v.max_content_light_level = 1000;
+            v.max_pic_average_light_level = 400;
+
+            v.as_bytes()
+        }).as_bytes());
+        ext.put(Object::new(pasp::BOX_TYPE, pasp::new(4, 3).as_bytes()).as_bytes());
+        ext.put(Object::new(clap::BOX_TYPE, {
+            let mut v = clap::default();
+
+            v.clean_aperture_width_n = 1440;
+            v.clean_aperture_height_n = 1080;
+
+            v.as_bytes()
+        }).as_bytes());
+
+        let mut b = trak::default();
+
+        b.mdia.hdlr = hdlr::vide("VideoHandler");
+        b.mdia.minf.mhd = MediaInformationHeader::vmhd(vmhd::default());
+        b.mdia.minf.stbl.stsd.entries.push(SampleEntry::hvc1 {
+            base: Box::new(SampleEntry::Visual {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: types::hvc1,
+                    data_reference_index: 1,
+                }),
+                width: 1440,
+                height: 1088,
+                horiz_resolution: 0x00480000,
+                vert_resolution: 0x00480000,
+                frame_count: 1,
+                compressor_name: "".to_owned(),
+                depth: 24,
+            }),
+            ext,
+        });
+
+        let bytes = b.as_bytes();
+        let mut obj = Object::parse(&mut Object::new(trak::BOX_TYPE, bytes.clone()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        let mut v = trak::parse(&mut obj.payload);
+        assert_eq!(Some((16, 9)), v.display_aspect_ratio());
+        assert_eq!(Some(HdrTransfer::Pq), v.hdr_transfer());
+        assert_eq!(Some(400), v.mdia.minf.stbl.stsd.entries[0].clli().map(|it| it.max_pic_average_light_level));
+        assert_eq!(Some(50), v.mdia.minf.stbl.stsd.entries[0].mdcv().map(|it| it.min_display_mastering_luminance));
+        assert_eq!(b, v);
+        // children keep their order
+        assert_eq!(bytes, v.as_bytes());
+    }
+
+    #[test]
+    fn chk_next_track_id() {
+        let mut b = moov::default();
+        assert_eq!(1, b.next_track_id());
+
+        b.mvhd.next_track_id = 5;
+        b.traks.push(trak::default());
+        b.traks[0].tkhd.track_id = 2;
+        assert_eq!(5, b.next_track_id());
+
+        b.mvhd.next_track_id = u32::MAX;
+        assert_eq!(3, b.next_track_id());
+
+        b.traks.push(trak::default());
+        b.traks[1].tkhd.track_id = u32::MAX;
+        assert_eq!(1, b.next_track_id());
+
+        let _ = b.as_bytes();
+        assert_eq!(u32::MAX, b.mvhd.next_track_id);
+        b.set_chapters(&[Chapter { start: Duration::ZERO, title: "Intro".to_owned() }], 0);
+        assert_eq!(Some(1), b.chapter_track().map(|it| it.tkhd.track_id));
+        assert_eq!(u32::MAX, b.mvhd.next_track_id);
+    }
+
+    #[test]
+    fn chk_chapters() {
+        let mut b = moov::default();
+        b.mvhd.timescale = 1000;
+        b.mvhd.duration = 60000;
+        b.mvhd.next_track_id = 2;
+        b.traks.push({
+            let mut v = trak::default();
+
+            v.tkhd.track_id = 1;
+            v.mdia.hdlr = hdlr::vide("Video");
+
+            v
+        });
+
+        let chapters = vec![
+            Chapter { start: Duration::ZERO, title: "Intro".to_owned() },
+            Chapter { start: Duration::from_secs(10), title: "Part 1".to_owned() },
+            Chapter { start: Duration::from_millis(42500), title: "Questions".to_owned() },
+        ];
+        let samples = b.set_chapters(&chapters, 100);
+        assert_eq!(Some(&[2][..]), b.traks[0].tref.as_ref().map(|it| it.track_ids(TrackReferenceType::Chap)));
+        assert_eq!(Ok(()), b.validate_track_references());
+        assert_eq!(3, b.mvhd.next_track_id);
+
+        let mut file = vec![0; 100];
+        file.extend_from_slice(&samples);
+
+        let mut obj = Object::parse(&mut Object::new(moov::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        let mut v = moov::parse(&mut obj.payload);
+        assert_eq!(b, v);
+        assert_eq!(chapters, v.chapters(&mut Cursor::new(&file)).unwrap());
+        assert_eq!(&[(1, 10000), (1, 32500), (1, 17500)][..], &v.chapter_track().unwrap().mdia.minf.stbl.stts.entries[..]);
+
+        v.traks.pop();
+        assert_eq!(chapters, v.chapters(&mut Cursor::new(&file)).unwrap());
+
+        // above 4 GiB
+        v.set_chapters(&chapters, 1 << 32);
+        let stbl = &v.chapter_track().unwrap().mdia.minf.stbl;
+        assert_eq!(Some(&[1 << 32][..]), stbl.co64.as_ref().map(|it| &it.entries[..]));
+        assert!(stbl.stco.entries.is_empty());
+
+        // unordered, with the first one starting after 0
+        let chapters = vec![
+            Chapter { start: Duration::from_secs(10), title: "Part 2".to_owned() },
+            Chapter { start: Duration::from_secs(5), title: "Part 1".to_owned() },
+        ];
+        let samples = v.set_chapters(&chapters, 100);
+        let mut file = vec![0; 100];
+        file.extend_from_slice(&samples);
+        let sorted = vec![chapters[1].clone(), chapters[0].clone()];
+        assert_eq!(sorted, v.chapters(&mut Cursor::new(&file)).unwrap());
+        assert_eq!(Some(&sorted), v.udta.as_ref().and_then(|it| it.chpl.as_ref()).map(|it| &it.chapters));
+        assert_eq!(&[(2, 5000), (1, 50000)][..], &v.chapter_track().unwrap().mdia.minf.stbl.stts.entries[..]);
+
+        assert!(b.set_chapters(&[], 0).is_empty());
+        assert_eq!(1, b.traks.len());
+        assert!(b.traks[0].tref.is_none());
+        assert!(b.udta.unwrap().chpl.is_none());
+    }
+
+    #[test]
+    fn chk_edits() {
+        let mut b = trak::default();
+        b.mdia.mdhd.timescale = 1000;
+        b.mdia.minf.stbl.stts.entries.push((10, 100));
+        b.mdia.minf.stbl.ctts = Some({
+            let mut v = ctts::default();
+
+            v.entries.push((10, 100));
+
+            v
+        });
+        b.mdia.minf.stbl.stsc.entries.push((1, 10, 1));
+        b.mdia.minf.stbl.stsz.entries = vec![1; 10];
+        b.mdia.minf.stbl.stco.entries.push(0);
+
+        let v = b.edits(500);
+        assert_eq!(1, v.len());
+        assert_eq!((0, 500, Some(0), 0..9), (v[0].start, v[0].duration, v[0].media_time, v[0].samples.clone()));
+
+        b.edts = Some(edts {
+            elst: Some({
+                let mut v = elst::default();
+
+                v.entries = vec![(100, -1, 1), (500, 100, 1), (50, 300, 0), (250, 600, 1)];
+
+                v
+            }),
+        });
+
+        let v = b.edits(500);
+        assert_eq!(vec![
+            Edit { start: 0, duration: 100, media_time: None, media_rate: 1, samples: 0..0 },
+            Edit { start: 100, duration: 500, media_time: Some(100), media_rate: 1, samples: 0..10 },
+            Edit { start: 600, duration: 50, media_time: Some(300), media_rate: 0, samples: 2..3 },
+            Edit { start: 650, duration: 250, media_time: Some(600), media_rate: 1, samples: 5..10 },
+        ], v);
+        assert_eq!(Some(100), b.presentation_time(100, 500));
+        assert_eq!(Some(200), b.presentation_time(300, 500));
+        assert_eq!(None, b.presentation_time(50, 500));
+        assert_eq!(None, b.media_time(50, 500));
+        assert_eq!(Some(300), b.media_time(620, 500));
+        assert_eq!(Some(700), b.media_time(700, 500));
+        assert_eq!(None, b.media_time(900, 500));
+
+        b.mdia.minf.stbl.ctts.as_mut().unwrap().entries.push((1, -100));
+        let mut obj = Object::parse(&mut Object::new(trak::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, trak::parse(&mut obj.payload));
+    }
+
+    #[test]
+    fn chk_tref() {
+        let mut b = trak::default();
+        b.tkhd.track_id = 2;
+        b.tref = Some({
+            let mut v = tref::default();
+
+            v.add(TrackReferenceType::Cdsc, 1);
+            v.add(TrackReferenceType::Subt, 1);
+            v.add(TrackReferenceType::Subt, 3);
+            v.add(TrackReferenceType::Subt, 3);
+            v.add(TrackReferenceType::Other(0x61647264), 1);
+
+            v
+        });
+
+        let mut obj = Object::parse(&mut Object::new(trak::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        let v = trak::parse(&mut obj.payload);
+        assert_eq!(b, v);
+        assert_eq!(&[1, 3], v.tref.as_ref().unwrap().track_ids(TrackReferenceType::Subt));
+        assert_eq!(TrackReferenceType::Subt, TrackReferenceType::from(types::subt));
+
+        let mut m = moov::default();
+        m.traks.push({
+            let mut v = trak::default();
+
+            v.tkhd.track_id = 1;
+
+            v
+        });
+        m.traks.push(b);
+        assert_eq!(Err(Error::UnknownTrackReference {
+            track_id: 2,
+            reference_type: types::subt,
+            reference_track_id: 3,
+        }), m.validate_track_references());
+
+        m.traks[1].tref.as_mut().unwrap().references.retain(|it| TrackReferenceType::Subt != it.reference_type);
+        assert_eq!(Ok(()), m.validate_track_references());
     }
 }
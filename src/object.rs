@@ -1,42 +1,69 @@
 use bytes::{Buf, BufMut, BytesMut};
 
 use crate::traits::IO;
+use crate::types::types;
 
 // An object in this terminology is a box.
 // but, Rust has same name Box for Heap allocation related
 pub struct Object {
     pub box_type: u32,
+    /// extended type of a `uuid` box
+    pub usertype: Option<[u8; 16]>,
     pub payload: BytesMut,
 }
 
-impl IO for Object {
-    fn len(&self) -> usize {
-        let size = self.payload.len();
-
-        let mut v = 8 + size;
+impl Object {
+    pub fn new(box_type: u32, payload: BytesMut) -> Self {
+        Self {
+            box_type,
+            usertype: None,
+            payload,
+        }
+    }
 
-        if (u32::MAX as usize) < size {
-            v += 8;
+    /// `uuid` box of the extended type `usertype`.
+    pub fn uuid(usertype: [u8; 16], payload: BytesMut) -> Self {
+        Self {
+            box_type: types::uuid,
+            usertype: Some(usertype),
+            payload,
         }
+    }
+}
 
-        v
+impl IO for Object {
+    fn len(&self) -> usize {
+        box_len(self.payload.len() + if self.usertype.is_some() { 16 } else { 0 })
     }
 
     fn parse(r: &mut BytesMut) -> Self {
         let mut size = r.get_u32() as u64;
         let box_type = r.get_u32();
+        let mut header = 8;
         if 1 == size {
-            size = r.get_u64() - 8;
+            size = r.get_u64();
+            header += 8;
         }
+        let usertype = if types::uuid == box_type {
+            let mut v = [0_u8; 16];
+            r.copy_to_slice(&mut v);
+            header += 16;
+
+            Some(v)
+        } else {
+            None
+        };
         if 0 == size {
             Self {
                 box_type,
+                usertype,
                 payload: r.split_to(r.len()),
             }
         } else {
             Self {
                 box_type,
-                payload: r.split_to((size as usize) - 8),
+                usertype,
+                payload: r.split_to((size as usize) - header),
             }
         }
     }
@@ -45,16 +72,18 @@ impl IO for Object {
         let mut w = BytesMut::new();
 
         let size = self.payload.len();
-        /*  */ if (u32::MAX as usize) < size {
+        let header = if self.usertype.is_some() { 24 } else { 8 };
+        /*  */ if (u32::MAX as usize) < size + header {
             w.put_u32(1);
-        } else if (u64::MAX as usize) < size {
-            w.put_u32(0);
         } else {
-            w.put_u32(8 + size as u32);
+            w.put_u32((header + size) as u32);
         }
         w.put_u32(self.box_type);
-        /*  */ if (u32::MAX as usize) < size && size <= (u64::MAX as usize) {
-            w.put_u64(4 + size as u64);
+        /*  */ if (u32::MAX as usize) < size + header {
+            w.put_u64((8 + header + size) as u64);
+        }
+        if let Some(v) = &self.usertype {
+            w.put_slice(v);
         }
         w.put(self.payload.chunk());
 
@@ -62,6 +91,15 @@ impl IO for Object {
     }
 }
 
+/// Size of a box with a payload of `size` bytes, its header included.
+pub(crate) fn box_len(size: usize) -> usize {
+    if (u32::MAX as usize) < 8 + size {
+        16 + size
+    } else {
+        8 + size
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct FullBox {
     pub(crate) version: u8,
@@ -0,0 +1,558 @@
+use std::fmt::{Debug, Formatter};
+use std::io::ErrorKind;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{Error, FullBox, IO};
+use crate::moof::{self, SampleEncryptionEntry, tfdt};
+use crate::uuid::Registry;
+
+/// Registers the parsers of the PIFF boxes to `registry`.
+pub fn register(registry: &mut Registry) {
+    registry.register(tfxd::USERTYPE, tfxd::parse);
+    registry.register(tfrf::USERTYPE, tfrf::parse);
+    registry.register(senc::USERTYPE, senc::parse);
+    registry.register(tenc::USERTYPE, tenc::parse);
+}
+
+/// Smooth Streaming fragment time, the counterpart of `tfdt`.
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct tfxd {
+    base: FullBox,
+
+    pub fragment_absolute_time: u64,
+    pub fragment_duration: u64,
+}
+
+impl tfxd {
+    pub const USERTYPE: [u8; 16] = [
+        0x6d, 0x1d, 0x9b, 0x05, 0x42, 0xd5, 0x44, 0xe6,
+        0x80, 0xe2, 0x14, 0x1d, 0xaf, 0xf7, 0x57, 0xb2,
+    ];
+
+    pub fn new(fragment_absolute_time: u64, fragment_duration: u64) -> Self {
+        Self {
+            base: FullBox::new(1, 0),
+            fragment_absolute_time,
+            fragment_duration,
+        }
+    }
+
+    /// `tfxd` of a track fragment starting at `tfdt` and lasting
+    /// `fragment_duration`.
+    pub fn from_tfdt(tfdt: &tfdt, fragment_duration: u64) -> Self {
+        Self::new(tfdt.base_media_decode_time, fragment_duration)
+    }
+
+    pub fn to_tfdt(&self) -> tfdt {
+        tfdt::new(self.fragment_absolute_time)
+    }
+
+    // version 1 when kept so or when the times need it
+    fn version(&self) -> u8 {
+        if (u32::MAX as u64) < self.fragment_absolute_time || (u32::MAX as u64) < self.fragment_duration {
+            1
+        } else {
+            self.base.version
+        }
+    }
+}
+
+impl Default for tfxd {
+    //! extends FullBox(‘uuid’ 6d1d9b05-42d5-44e6-80e2-141daff757b2, version, 0) {
+    //!     if (version == 1) {
+    //!         unsigned int(64) fragment_absolute_time;
+    //!         unsigned int(64) fragment_duration;
+    //!     } else {
+    //!         unsigned int(32) fragment_absolute_time;
+    //!         unsigned int(32) fragment_duration;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl Debug for tfxd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\tfragment_absolute_time: {:?}", self.fragment_absolute_time))?;
+        f.write_fmt(format_args!("\n\t\t\tfragment_duration: {:?}", self.fragment_duration))?;
+
+        Ok(())
+    }
+}
+
+impl IO for tfxd {
+    fn len(&self) -> usize {
+        self.base.len() + if 1 == self.version() { 16 } else { 8 }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let (fragment_absolute_time, fragment_duration) = if 1 == base.version {
+            (r.get_u64(), r.get_u64())
+        } else {
+            (r.get_u32() as u64, r.get_u32() as u64)
+        };
+
+        Self {
+            base,
+            fragment_absolute_time,
+            fragment_duration,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.version = self.version();
+        w.put(self.base.as_bytes());
+
+        if 1 == self.base.version {
+            w.put_u64(self.fragment_absolute_time);
+            w.put_u64(self.fragment_duration);
+        } else {
+            w.put_u32(self.fragment_absolute_time as u32);
+            w.put_u32(self.fragment_duration as u32);
+        }
+
+        w
+    }
+}
+
+/// Smooth Streaming lookahead: absolute time and duration of the fragments
+/// following this one in a live presentation.
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct tfrf {
+    base: FullBox,
+
+    pub fragments: Vec<(u64, u64)>,
+}
+
+impl tfrf {
+    pub const USERTYPE: [u8; 16] = [
+        0xd4, 0x80, 0x7e, 0xf2, 0xca, 0x39, 0x46, 0x95,
+        0x8e, 0x54, 0x26, 0xcb, 0x9e, 0x46, 0xa7, 0x9f,
+    ];
+
+    // version 1 when kept so or when the times need it
+    fn version(&self) -> u8 {
+        if self.fragments.iter().any(|it| (u32::MAX as u64) < it.0 || (u32::MAX as u64) < it.1) {
+            1
+        } else {
+            self.base.version
+        }
+    }
+}
+
+impl Default for tfrf {
+    //! extends FullBox(‘uuid’ d4807ef2-ca39-4695-8e54-26cb9e46a79f, version, 0) {
+    //!     unsigned int(8) fragment_count;
+    //!     for (i = 0; i < fragment_count; i++) {
+    //!         if (version == 1) {
+    //!             unsigned int(64) fragment_absolute_time;
+    //!             unsigned int(64) fragment_duration;
+    //!         } else {
+    //!             unsigned int(32) fragment_absolute_time;
+    //!             unsigned int(32) fragment_duration;
+    //!         }
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(1, 0),
+            fragments: vec![],
+        }
+    }
+}
+
+impl Debug for tfrf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\tfragment_count: {:?}", self.fragments.len()))?;
+        for (fragment_absolute_time, fragment_duration) in &self.fragments {
+            f.write_fmt(format_args!("\n\t\t\t\t{{"))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\tfragment_absolute_time: {:?}", fragment_absolute_time))?;
+            f.write_fmt(format_args!("\n\t\t\t\t\tfragment_duration: {:?}", fragment_duration))?;
+            f.write_fmt(format_args!("\n\t\t\t\t}}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for tfrf {
+    fn len(&self) -> usize {
+        self.base.len() + 1 + self.fragments.len() * if 1 == self.version() { 16 } else { 8 }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let fragment_count = r.get_u8();
+
+        let mut fragments = Vec::with_capacity(fragment_count as usize);
+        for _ in 0..fragment_count {
+            fragments.push(if 1 == base.version {
+                (r.get_u64(), r.get_u64())
+            } else {
+                (r.get_u32() as u64, r.get_u32() as u64)
+            });
+        }
+
+        Self {
+            base,
+            fragments,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.version = self.version();
+        w.put(self.base.as_bytes());
+
+        w.put_u8(self.fragments.len() as u8);
+        for (fragment_absolute_time, fragment_duration) in &self.fragments {
+            if 1 == self.base.version {
+                w.put_u64(*fragment_absolute_time);
+                w.put_u64(*fragment_duration);
+            } else {
+                w.put_u32(*fragment_absolute_time as u32);
+                w.put_u32(*fragment_duration as u32);
+            }
+        }
+
+        w
+    }
+}
+
+/// `AlgorithmID`, `IV_size` and `KID` of PIFF encrypted samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EncryptionParameters {
+    pub algorithm_id: u32,
+    pub iv_size: u8,
+    pub kid: [u8; 16],
+}
+
+impl EncryptionParameters {
+    fn parse(r: &mut BytesMut) -> Self {
+        let algorithm_id = (r.get_u16() as u32) << 8 | r.get_u8() as u32;
+        let iv_size = r.get_u8();
+        let mut kid = [0_u8; 16];
+        r.copy_to_slice(&mut kid);
+
+        Self {
+            algorithm_id,
+            iv_size,
+            kid,
+        }
+    }
+
+    fn as_bytes(&self, w: &mut BytesMut) {
+        w.put_u16((self.algorithm_id >> 8) as u16);
+        w.put_u8(self.algorithm_id as u8);
+        w.put_u8(self.iv_size);
+        w.put_slice(&self.kid);
+    }
+}
+
+/// PIFF sample encryption, the counterpart of the CENC `senc`.
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct senc {
+    base: FullBox,
+
+    /// overrides the track encryption defaults of `tenc`
+    pub parameters: Option<EncryptionParameters>,
+    /// `sample_count` and the entries, kept as stored
+    data: BytesMut,
+}
+
+impl senc {
+    pub const USERTYPE: [u8; 16] = [
+        0xa2, 0x39, 0x4f, 0x52, 0x5a, 0x9b, 0x4f, 0x14,
+        0xa2, 0x44, 0x6c, 0x42, 0x7c, 0x64, 0x8d, 0xf4,
+    ];
+
+    /// `flags`: `AlgorithmID`, `IV_size` and `KID` follow
+    pub const OVERRIDE_TRACK_ENCRYPTION_BOX_PARAMETERS: u32 = 0x000001;
+    /// `flags`: subsample information follows each IV
+    pub const USE_SUBSAMPLE_ENCRYPTION: u32 = 0x000002;
+
+    pub fn new(parameters: Option<EncryptionParameters>, samples: Vec<SampleEncryptionEntry>) -> Self {
+        let subsamples = samples.iter().any(|it| !it.subsamples.is_empty());
+        let mut data = BytesMut::new();
+        moof::put_sample_encryption_entries(&mut data, &samples, subsamples);
+
+        Self {
+            base: FullBox::new(0, if subsamples { senc::USE_SUBSAMPLE_ENCRYPTION } else { 0 }),
+            parameters,
+            data,
+        }
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        moof::sample_count(&self.data)
+    }
+
+    /// Entries of the samples, with IVs of the `IV_size` of `parameters`,
+    /// else of the track's `tenc`; fails without either, or when the
+    /// entries don't span the box.
+    pub fn samples(&self, tenc: Option<&tenc>) -> Result<Vec<SampleEncryptionEntry>, Error> {
+        let Some(iv_size) = self.parameters.as_ref().or(tenc.map(|it| &it.defaults)).map(|it| it.iv_size) else {
+            return Err(Error::Io(ErrorKind::InvalidData));
+        };
+
+        moof::parse_sample_encryption_entries(
+            &self.data,
+            0 != (senc::USE_SUBSAMPLE_ENCRYPTION & self.base.flags),
+            iv_size as usize,
+        )
+    }
+
+    /// PIFF form of the CENC `senc`.
+    pub fn from_cenc(senc: &moof::senc) -> Self {
+        let (subsamples, data) = senc.entries();
+
+        Self {
+            base: FullBox::new(0, if subsamples { senc::USE_SUBSAMPLE_ENCRYPTION } else { 0 }),
+            parameters: None,
+            data: data.clone(),
+        }
+    }
+
+    /// CENC form of this box, unless it overrides the track encryption
+    /// parameters: those belong to `tenc` or `seig` instead.
+    pub fn to_cenc(&self) -> Option<moof::senc> {
+        if self.parameters.is_some() {
+            return None;
+        }
+
+        Some(moof::senc::from_entries(0 != (senc::USE_SUBSAMPLE_ENCRYPTION & self.base.flags), self.data.clone()))
+    }
+}
+
+impl Default for senc {
+    //! extends FullBox(‘uuid’ a2394f52-5a9b-4f14-a244-6c427c648df4, version=0, flags) {
+    //!     if (flags & 0x000001) {
+    //!         unsigned int(24) AlgorithmID;
+    //!         unsigned int(8) IV_size;
+    //!         unsigned int(8)[16] KID;
+    //!     }
+    //!     unsigned int(32) sample_count;
+    //!     {
+    //!         unsigned int(IV_size*8) InitializationVector;
+    //!         if (flags & 0x000002) {
+    //!             unsigned int(16) NumberOfEntries;
+    //!             {
+    //!                 unsigned int(16) BytesOfClearData;
+    //!                 unsigned int(32) BytesOfEncryptedData;
+    //!             } [NumberOfEntries]
+    //!         }
+    //!     } [sample_count]
+    //! }
+    fn default() -> Self {
+        Self::new(None, vec![])
+    }
+}
+
+impl Debug for senc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(it) = &self.parameters {
+            f.write_fmt(format_args!("\t\t\tAlgorithmID: {:?}", it.algorithm_id))?;
+            f.write_fmt(format_args!("\n\t\t\tIV_size: {:?}", it.iv_size))?;
+            f.write_fmt(format_args!("\n\t\t\tKID: {:02x?}\n", it.kid))?;
+        }
+        f.write_fmt(format_args!("\t\t\tsample_count: {:?}", self.sample_count()))
+    }
+}
+
+impl IO for senc {
+    fn len(&self) -> usize {
+        self.base.len() + if self.parameters.is_some() { 20 } else { 0 } + self.data.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let parameters = if 0 != (senc::OVERRIDE_TRACK_ENCRYPTION_BOX_PARAMETERS & base.flags) {
+            Some(EncryptionParameters::parse(r))
+        } else {
+            None
+        };
+
+        Self {
+            base,
+            parameters,
+            data: r.split_to(r.len()),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.flags &= senc::USE_SUBSAMPLE_ENCRYPTION;
+        if self.parameters.is_some() {
+            self.base.flags |= senc::OVERRIDE_TRACK_ENCRYPTION_BOX_PARAMETERS;
+        }
+        w.put(self.base.as_bytes());
+
+        if let Some(it) = &self.parameters {
+            it.as_bytes(&mut w);
+        }
+        w.put_slice(&self.data);
+
+        w
+    }
+}
+
+/// PIFF track encryption defaults.
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq)]
+pub struct tenc {
+    base: FullBox,
+
+    pub defaults: EncryptionParameters,
+}
+
+impl tenc {
+    pub const USERTYPE: [u8; 16] = [
+        0x89, 0x74, 0xdb, 0xce, 0x7b, 0xe7, 0x4c, 0x51,
+        0x84, 0xf9, 0x71, 0x48, 0xf9, 0x88, 0x25, 0x54,
+    ];
+}
+
+impl Default for tenc {
+    //! extends FullBox(‘uuid’ 8974dbce-7be7-4c51-84f9-7148f9882554, version=0, flags=0) {
+    //!     unsigned int(24) default_AlgorithmID;
+    //!     unsigned int(8) default_IV_size;
+    //!     unsigned int(8)[16] default_KID;
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            defaults: EncryptionParameters {
+                algorithm_id: 1,
+                iv_size: 8,
+                kid: [0; 16],
+            },
+        }
+    }
+}
+
+impl Debug for tenc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\tdefault_AlgorithmID: {:?}", self.defaults.algorithm_id))?;
+        f.write_fmt(format_args!("\n\tdefault_IV_size: {:?}", self.defaults.iv_size))?;
+        f.write_fmt(format_args!("\n\tdefault_KID: {:02x?}", self.defaults.kid))?;
+
+        Ok(())
+    }
+}
+
+impl IO for tenc {
+    fn len(&self) -> usize {
+        self.base.len() + 20
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            base: FullBox::parse(r),
+            defaults: EncryptionParameters::parse(r),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+        self.defaults.as_bytes(&mut w);
+
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::{Error, IO, Object};
+    use crate::moof::{SampleEncryptionEntry, tfdt, traf, trun};
+    use crate::piff::{self, EncryptionParameters, senc, tenc, tfrf, tfxd};
+    use crate::uuid::Registry;
+
+    #[test]
+    fn chk_piff() {
+        let samples = vec![
+            SampleEncryptionEntry {
+                iv: vec![1; 8],
+                subsamples: vec![(16, 1000)],
+            },
+            SampleEncryptionEntry {
+                iv: vec![2; 8],
+                subsamples: vec![(16, 800), (4, 100)],
+            },
+        ];
+
+        let mut b = traf::default();
+        b.tfhd.track_id = 1;
+        b.truns.push({
+            let mut v = trun::default();
+
+            v.samples.push((Some(1000), Some(1016), None, None));
+            v.samples.push((Some(1000), Some(920), None, None));
+
+            v
+        });
+        b.tfxd = Some(tfxd::new(u32::MAX as u64 + 1, 2000));
+        b.tfrf = Some({
+            let mut v = tfrf::default();
+
+            v.fragments.push((u32::MAX as u64 + 2001, 2000));
+
+            v
+        });
+        b.piff_senc = Some(senc::new(Some(EncryptionParameters {
+            algorithm_id: 1,
+            iv_size: 8,
+            kid: [0xAB; 16],
+        }), samples.clone()));
+
+        let mut obj = Object::parse(&mut Object::new(traf::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, traf::parse(&mut obj.payload));
+
+        assert_eq!(Ok(samples.clone()), b.piff_senc.as_ref().unwrap().samples(None));
+        assert_eq!(None, b.piff_senc.as_ref().unwrap().to_cenc());
+        assert_eq!(Err(Error::UnsupportedEncryptionOverride { track_id: 1 }), b.to_cmaf());
+        assert!(b.tfxd.is_some() && b.piff_senc.is_some());
+
+        b.piff_senc = Some(senc::new(None, samples.clone()));
+        let piff_senc = b.piff_senc.as_ref().unwrap();
+        assert_eq!(Err(Error::Io(ErrorKind::InvalidData)), piff_senc.samples(None));
+        assert_eq!(Ok(samples.clone()), piff_senc.samples(Some(&tenc::default())));
+
+        let cenc = piff_senc.to_cenc().unwrap();
+        assert_eq!(Ok(samples), cenc.samples(8));
+        assert_eq!(Err(Error::Io(ErrorKind::InvalidData)), cenc.samples(16));
+        assert_eq!(piff_senc, &senc::from_cenc(&cenc));
+        assert_eq!(tfdt::new(u32::MAX as u64 + 1), b.tfxd.as_ref().unwrap().to_tfdt());
+        assert_eq!(2000, tfxd::from_tfdt(&tfdt::new(0), 2000).fragment_duration);
+
+        assert_eq!(Ok(()), b.to_cmaf());
+        assert!(b.tfxd.is_none() && b.piff_senc.is_none());
+        assert_eq!(Some(cenc), b.senc);
+
+        let mut obj = Object::parse(&mut Object::new(traf::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, traf::parse(&mut obj.payload));
+
+        let mut registry = Registry::default();
+        piff::register(&mut registry);
+        let mut obj = Object::parse(&mut Object::uuid(tenc::USERTYPE, tenc::default().as_bytes()).as_bytes());
+
+        assert!(registry.parse(&mut obj).as_deref().and_then(|it| it.downcast_ref::<tenc>()) == Some(&tenc::default()));
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::{Debug, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{FullBox, IO};
+use crate::moof::tfdt;
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> prft {
+    prft::parse(r)
+}
+
+/// Seconds from the NTP epoch, 1900-01-01, to the UNIX epoch.
+const NTP_UNIX_OFFSET: u64 = 2208988800;
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct prft {
+    base: FullBox,
+
+    pub reference_track_id: u32,
+    /// NTP timestamp, 32.32 fixed point seconds since 1900
+    pub ntp_timestamp: u64,
+    pub media_time: u64,
+}
+
+impl prft {
+    pub const BOX_TYPE: u32 = types::prft;
+
+    /// `flags`: the UTC time is the time of capture of the encoder input
+    pub const INPUT_CAPTURE: u32 = 0;
+    /// `flags`: the UTC time is the time the encoder output the sample
+    pub const ENCODER_OUTPUT: u32 = 1;
+    /// `flags`: the UTC time is the time the `moof` was finalized
+    pub const MOOF_FINALIZED: u32 = 2;
+    /// `flags`: the UTC time is the time the `moof` was written
+    pub const MOOF_WRITTEN: u32 = 4;
+    /// `flags`: the UTC time is arbitrary but consistent across fragments
+    pub const CONSISTENT: u32 = 8;
+    /// `flags`: the UTC time was captured by an external time source
+    pub const EXTERNAL: u32 = 24;
+
+    pub fn new(flags: u32, reference_track_id: u32, time: SystemTime, media_time: u64) -> Self {
+        Self {
+            base: FullBox::new(0, flags),
+            reference_track_id,
+            ntp_timestamp: ntp_timestamp(time),
+            media_time,
+        }
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.base.flags
+    }
+
+    /// Wall-clock time of `ntp_timestamp`.
+    pub fn time(&self) -> SystemTime {
+        let v = (self.ntp_timestamp as u128 * 1_000_000_000) >> 32;
+        let offset = NTP_UNIX_OFFSET as u128 * 1_000_000_000;
+
+        if offset <= v {
+            UNIX_EPOCH + Duration::from_nanos((v - offset) as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_nanos((offset - v) as u64)
+        }
+    }
+
+    /// Wall-clock time of the fragment starting at `tfdt`, on the reference
+    /// track whose media timescale is `timescale`.
+    pub fn wall_clock(&self, tfdt: &tfdt, timescale: u32) -> SystemTime {
+        let time = self.time();
+
+        if self.media_time <= tfdt.base_media_decode_time {
+            time + media_duration(tfdt.base_media_decode_time - self.media_time, timescale)
+        } else {
+            time - media_duration(self.media_time - tfdt.base_media_decode_time, timescale)
+        }
+    }
+}
+
+/// NTP timestamp of `time`, which must not be before 1900.
+pub fn ntp_timestamp(time: SystemTime) -> u64 {
+    let v = match time.duration_since(UNIX_EPOCH) {
+        Ok(v) => { NTP_UNIX_OFFSET as u128 * 1_000_000_000 + v.as_nanos() }
+        Err(e) => { NTP_UNIX_OFFSET as u128 * 1_000_000_000 - e.duration().as_nanos() }
+    };
+
+    ((v << 32) / 1_000_000_000) as u64
+}
+
+fn media_duration(v: u64, timescale: u32) -> Duration {
+    Duration::from_nanos((v as u128 * 1_000_000_000 / timescale.max(1) as u128) as u64)
+}
+
+impl Default for prft {
+    //! extends FullBox(‘prft’, version, flags) {
+    //!     unsigned int(32) reference_track_ID;
+    //!     unsigned int(64) ntp_timestamp;
+    //!     if (version==0) {
+    //!         unsigned int(32) media_time;
+    //!     } else {
+    //!         unsigned int(64) media_time;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            reference_track_id: 0,
+            ntp_timestamp: 0,
+            media_time: 0,
+        }
+    }
+}
+
+impl Debug for prft {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\tflags: {:?}", self.base.flags))?;
+        f.write_fmt(format_args!("\n\treference_track_id: {:?}", self.reference_track_id))?;
+        f.write_fmt(format_args!("\n\tntp_timestamp: 0x{:016x?}", self.ntp_timestamp))?;
+        f.write_fmt(format_args!("\n\tmedia_time: {:?}", self.media_time))?;
+
+        Ok(())
+    }
+}
+
+impl IO for prft {
+    fn len(&self) -> usize {
+        self.base.len() + 12 + if (u32::MAX as u64) < self.media_time { 8 } else { 4 }
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let reference_track_id = r.get_u32();
+        let ntp_timestamp = r.get_u64();
+        let media_time = if 1 == base.version {
+            r.get_u64()
+        } else {
+            r.get_u32() as u64
+        };
+
+        Self {
+            base,
+            reference_track_id,
+            ntp_timestamp,
+            media_time,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.version = if (u32::MAX as u64) < self.media_time { 1 } else { 0 };
+        w.put(self.base.as_bytes());
+
+        w.put_u32(self.reference_track_id);
+        w.put_u64(self.ntp_timestamp);
+        if 1 == self.base.version {
+            w.put_u64(self.media_time);
+        } else {
+            w.put_u32(self.media_time as u32);
+        }
+
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{IO, Object};
+    use crate::moof::tfdt;
+    use crate::prft::prft;
+
+    #[test]
+    fn chk_prft() {
+        let time = UNIX_EPOCH + Duration::from_secs(1700000000);
+        let mut b = prft::new(prft::MOOF_WRITTEN, 1, time, 90000 * 10);
+
+        let mut obj = Object::parse(&mut Object::new(prft::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(prft::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, prft::parse(&mut obj.payload));
+
+        assert_eq!(prft::MOOF_WRITTEN, b.flags());
+        assert_eq!(time, b.time());
+        assert_eq!(time + Duration::from_millis(1500), b.wall_clock(&tfdt::new(90000 * 10 + 135000), 90000));
+        assert_eq!(time - Duration::from_secs(10), b.wall_clock(&tfdt::new(0), 90000));
+
+        b.media_time = u32::MAX as u64 + 1;
+        let mut obj = Object::parse(&mut Object::new(prft::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, prft::parse(&mut obj.payload));
+    }
+}
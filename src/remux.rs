@@ -0,0 +1,617 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+use crate::{Error, IO, Object};
+use crate::ftyp::ftyp;
+use crate::moov::{Edit, edts, elst, moov, Sample, trak};
+use crate::types::types;
+
+/// Copies the `[start, end)` range of the movie in `r`, in the movie
+/// timescale, to a new file written to `w`, without re-encoding.
+///
+/// Each track keeps its samples from the sync sample preceding `start`, and
+/// gets an edit list so that its presentation starts exactly at `start`.
+/// The edit list of an input track may hold an empty edit followed by a
+/// single edit at normal rate.
+/// Returns the `moov` of the new file.
+pub fn trim<R, W>(r: &mut R, ftyp: &ftyp, mut moov: moov, start: u64, end: u64, w: &mut W) -> Result<moov, Error>
+    where R: Read + Seek, W: Write {
+    if end <= start {
+        return Err(Error::InvalidRange { start, end });
+    }
+    if moov.mvex.is_some() {
+        return Err(Error::Fragmented);
+    }
+
+    let movie_timescale = moov.mvhd.timescale;
+    let mut samples = vec![];
+
+    for trak in moov.traks.iter_mut() {
+        let edits = trak.edits(movie_timescale);
+        let (lead, edit) = match &edits[..] {
+            [e] if e.media_time.is_some() && 1 == e.media_rate => { (0, e) }
+            [empty, e] if empty.media_time.is_none() && e.media_time.is_some() && 1 == e.media_rate => { (empty.duration, e) }
+            _ => { return Err(Error::UnsupportedEditList { track_id: trak.tkhd.track_id }) }
+        };
+
+        let media_timescale = trak.mdia.mdhd.timescale.max(1) as u128;
+        let media_time = |v: u64| -> i64 {
+            edit.media_time.unwrap_or(0) as i64 + ((v - lead) as u128 * media_timescale / movie_timescale.max(1) as u128) as i64
+        };
+
+        let first = start.max(lead);
+        let last = end.min(lead + edit.duration);
+        let all = trak.mdia.minf.stbl.samples();
+        let mut kept = vec![];
+
+        if first < last {
+            let begin = media_time(first);
+            let end = media_time(last).max(begin + 1);
+            let presented = all.iter().enumerate()
+                .filter(|(_, it)| it.composition_time() < end && begin < it.composition_time() + it.duration.max(1) as i64)
+                .map(|(i, _)| i);
+            let (from, to) = presented.fold((usize::MAX, 0), |(from, to), i| (from.min(i), to.max(i + 1)));
+
+            if from < to {
+                let from = all[..=from].iter().rposition(|it| it.is_sync).unwrap_or(0);
+                let decode_time = all[from].decode_time;
+
+                kept = all[from..to].to_vec();
+                for it in kept.iter_mut() {
+                    it.decode_time -= decode_time;
+                }
+
+                let mut entries = vec![];
+                if start < first {
+                    entries.push((first - start, -1, 1));
+                }
+                entries.push((last - first, begin - decode_time as i64, 1));
+                trak.edts = Some(edts {
+                    elst: Some({
+                        let mut v = elst::default();
+
+                        v.entries = entries;
+
+                        v
+                    }),
+                });
+                trak.tkhd.duration = last - start;
+            }
+        }
+
+        if kept.is_empty() {
+            trak.edts = None;
+            trak.tkhd.duration = 0;
+        }
+        trak.mdia.mdhd.duration = kept.iter().map(|it| it.duration as u64).sum();
+        samples.push(kept.into_iter().map(|it| (0, it)).collect());
+    }
+    moov.mvhd.duration = moov.traks.iter().map(|it| it.tkhd.duration).max().unwrap_or(0);
+
+    if let Some(chpl) = moov.udta.as_mut().and_then(|it| it.chpl.as_mut()) {
+        let to_duration = |v: u64| Duration::from_nanos((v as u128 * 1_000_000_000 / movie_timescale.max(1) as u128) as u64);
+        let (start, end) = (to_duration(start), to_duration(end));
+
+        let from = chpl.chapters.iter().rposition(|it| it.start <= start).unwrap_or(0);
+        chpl.chapters.drain(..from);
+        chpl.chapters.retain(|it| it.start < end);
+        for it in chpl.chapters.iter_mut() {
+            it.start = it.start.saturating_sub(start);
+        }
+    }
+
+    write(std::slice::from_mut(r), ftyp, moov, samples, w)
+}
+
+/// Joins the movies of `inputs`, each a file with its `moov`, one after the
+/// other into a new file written to `w`.
+///
+/// Tracks are matched by position, and must agree on handler type, media
+/// timescale and sample entry format, for audio on sample rate and channel
+/// count, and for video on width and height. Sample entries differing
+/// otherwise are added to `stsd`.
+/// The timestamps of each input continue from the end of the previous one.
+/// Returns the `moov` of the new file.
+pub fn concat<R, W>(inputs: Vec<(R, moov)>, ftyp: &ftyp, w: &mut W) -> Result<moov, Error>
+    where R: Read + Seek, W: Write {
+    let (mut sources, moovs): (Vec<R>, Vec<moov>) = inputs.into_iter().unzip();
+    let mut moovs = moovs.into_iter();
+    let Some(mut moov) = moovs.next() else { return Err(Error::NoInput) };
+    if moov.mvex.is_some() {
+        return Err(Error::Fragmented);
+    }
+
+    let movie_timescale = moov.mvhd.timescale.max(1) as u128;
+    let mut media_times = vec![];
+    let mut samples = vec![];
+    for trak in &moov.traks {
+        media_times.push(media_start(trak, moov.mvhd.timescale)?);
+        samples.push(trak.mdia.minf.stbl.samples().into_iter().map(|it| (0, it)).collect::<Vec<_>>());
+    }
+    let mut movie_end = moov.mvhd.duration;
+
+    for (input, m) in moovs.enumerate().map(|(i, it)| (i + 1, it)) {
+        if m.mvex.is_some() {
+            return Err(Error::Fragmented);
+        }
+        if m.traks.len() != moov.traks.len() {
+            let track_id = moov.traks.get(m.traks.len()).map_or(0, |it| it.tkhd.track_id);
+            return Err(Error::Incompatible { input, track_id });
+        }
+
+        for (t, trak) in m.traks.iter().enumerate() {
+            let out = &mut moov.traks[t];
+            if !is_compatible(out, trak) {
+                return Err(Error::Incompatible { input, track_id: out.tkhd.track_id });
+            }
+
+            let media_timescale = out.mdia.mdhd.timescale.max(1) as u128;
+            let start = (movie_end as u128 * media_timescale / movie_timescale) as i128
+                + media_times[t] as i128 - media_start(trak, m.mvhd.timescale)? as i128;
+            let start = start.max(0) as u64;
+            let offset = match samples[t].last_mut() {
+                Some((_, last)) => {
+                    // fill any gap by extending the last sample
+                    if last.decode_time + (last.duration as u64) < start {
+                        last.duration = (start - last.decode_time).min(u32::MAX as u64) as u32;
+                    }
+                    last.decode_time + last.duration as u64
+                }
+                None => { start }
+            };
+
+            let entries = &mut out.mdia.minf.stbl.stsd.entries;
+            let indices = trak.mdia.minf.stbl.stsd.entries.iter().map(|e| {
+                match entries.iter().position(|it| e == it) {
+                    Some(i) => { i as u32 + 1 }
+                    None => {
+                        entries.push(e.clone());
+                        entries.len() as u32
+                    }
+                }
+            }).collect::<Vec<_>>();
+
+            for mut it in trak.mdia.minf.stbl.samples() {
+                let Some(index) = (it.description_index as usize).checked_sub(1).and_then(|i| indices.get(i)) else {
+                    return Err(Error::Incompatible { input, track_id: out.tkhd.track_id });
+                };
+                it.decode_time += offset;
+                it.description_index = *index;
+                samples[t].push((input, it));
+            }
+        }
+
+        movie_end += (m.mvhd.duration as u128 * movie_timescale / m.mvhd.timescale.max(1) as u128) as u64;
+    }
+
+    for (t, trak) in moov.traks.iter_mut().enumerate() {
+        trak.tkhd.duration = movie_end;
+        trak.mdia.mdhd.duration = samples[t].iter().map(|it| it.1.duration as u64).sum();
+        if let Some(elst) = trak.edts.as_mut().and_then(|it| it.elst.as_mut()) {
+            elst.entries = vec![(movie_end, media_times[t] as i64, 1)];
+        }
+    }
+    moov.mvhd.duration = movie_end;
+
+    write(&mut sources, ftyp, moov, samples, w)
+}
+
+/// Writes a new file to `w` holding `tracks`, each the input index and track
+/// ID of a track of `inputs`, in that order.
+///
+/// Use it to drop tracks from a file, or to take tracks from several files,
+/// such as the video of one and the audio of another. Tracks are numbered
+/// from 1 in order and `tref` entries are remapped, dropping references to
+/// tracks left out. The movie header and user data come from the first
+/// input. Returns the `moov` of the new file.
+pub fn remux<R, W>(inputs: Vec<(R, moov)>, tracks: &[(usize, u32)], ftyp: &ftyp, w: &mut W) -> Result<moov, Error>
+    where R: Read + Seek, W: Write {
+    let (mut sources, mut moovs): (Vec<R>, Vec<moov>) = inputs.into_iter().unzip();
+    if tracks.is_empty() || moovs.is_empty() {
+        return Err(Error::NoInput);
+    }
+    if moovs.iter().any(|it| it.mvex.is_some()) {
+        return Err(Error::Fragmented);
+    }
+
+    let mut traks = vec![];
+    for (input, track_id) in tracks {
+        let Some(m) = moovs.get_mut(*input) else {
+            return Err(Error::UnknownTrack { input: *input, track_id: *track_id });
+        };
+        let Some(i) = m.traks.iter().position(|it| *track_id == it.tkhd.track_id) else {
+            return Err(Error::UnknownTrack { input: *input, track_id: *track_id });
+        };
+
+        traks.push((*input, m.mvhd.timescale, m.traks.remove(i)));
+    }
+
+    let mut moov = moovs.swap_remove(0);
+    moov.traks.clear();
+    moov.mvhd.next_track_id = 1;
+
+    let movie_timescale = moov.mvhd.timescale.max(1) as u128;
+    let new_track_id = |input: usize, track_id: u32| {
+        tracks.iter().position(|it| (input, track_id) == *it).map(|i| i as u32 + 1)
+    };
+
+    let mut samples = vec![];
+    for (input, timescale, mut trak) in traks {
+        let scale = |v: u64| (v as u128 * movie_timescale / timescale.max(1) as u128) as u64;
+
+        trak.tkhd.duration = scale(trak.tkhd.duration);
+        if let Some(elst) = trak.edts.as_mut().and_then(|it| it.elst.as_mut()) {
+            for it in elst.entries.iter_mut() {
+                it.0 = scale(it.0);
+            }
+        }
+        if let Some(tref) = &mut trak.tref {
+            for it in tref.references.iter_mut() {
+                it.track_ids = it.track_ids.iter().filter_map(|it| new_track_id(input, *it)).collect();
+            }
+            tref.references.retain(|it| !it.track_ids.is_empty());
+            if tref.references.is_empty() {
+                trak.tref = None;
+            }
+        }
+        trak.tkhd.track_id = new_track_id(input, trak.tkhd.track_id).unwrap_or(0);
+
+        samples.push(trak.mdia.minf.stbl.samples().into_iter().map(|it| (input, it)).collect());
+        moov.traks.push(trak);
+    }
+    moov.mvhd.duration = moov.traks.iter().map(|it| it.tkhd.duration).max().unwrap_or(0);
+
+    write(&mut sources, ftyp, moov, samples, w)
+}
+
+/// Media time presented first by `trak`, which must have a single edit at
+/// normal rate, or no edit list.
+fn media_start(trak: &trak, movie_timescale: u32) -> Result<u64, Error> {
+    match &trak.edits(movie_timescale)[..] {
+        [Edit { media_time: Some(v), media_rate: 1, .. }] => { Ok(*v) }
+        _ => { Err(Error::UnsupportedEditList { track_id: trak.tkhd.track_id }) }
+    }
+}
+
+fn is_compatible(a: &trak, b: &trak) -> bool {
+    let entries = &a.mdia.minf.stbl.stsd.entries;
+
+    a.mdia.hdlr.handler_type == b.mdia.hdlr.handler_type &&
+        a.mdia.mdhd.timescale == b.mdia.mdhd.timescale &&
+        b.mdia.minf.stbl.stsd.entries.iter().all(|e| {
+            entries.iter().any(|it| {
+                it.get_handler_type() == e.get_handler_type() &&
+                    (it.sample_rate(), it.channel_count()) == (e.sample_rate(), e.channel_count()) &&
+                    it.dimensions() == e.dimensions()
+            })
+        })
+}
+
+/// Writes `ftyp`, `moov` and an `mdat` holding `samples`, the samples of
+/// each track of `moov` with the index of the source they are read from,
+/// interleaved in chunks of about one second. The sample tables of `moov`
+/// are rebuilt for the new layout.
+pub(crate) fn write<R, W>(sources: &mut [R], ftyp: &ftyp, mut moov: moov, samples: Vec<Vec<(usize, Sample)>>, w: &mut W) -> Result<moov, Error>
+    where R: Read + Seek, W: Write {
+    let (inputs, mut samples): (Vec<Vec<_>>, Vec<Vec<_>>) = samples.into_iter()
+        .map(|it| it.into_iter().map(|(source, it)| ((source, it.offset), it)).unzip())
+        .unzip();
+
+    // (track, first sample, last sample)
+    let mut chunks = vec![];
+    for (track, it) in samples.iter().enumerate() {
+        let timescale = moov.traks[track].mdia.mdhd.timescale.max(1) as u64;
+        let mut first = 0;
+
+        for i in 1..=it.len() {
+            if i == it.len() || it[i].description_index != it[first].description_index ||
+                timescale <= it[i].decode_time - it[first].decode_time {
+                chunks.push((track, first, i));
+                first = i;
+            }
+        }
+    }
+    chunks.sort_by(|a, b| {
+        let a_time = samples[a.0][a.1].decode_time as u128 * moov.traks[b.0].mdia.mdhd.timescale as u128;
+        let b_time = samples[b.0][b.1].decode_time as u128 * moov.traks[a.0].mdia.mdhd.timescale as u128;
+
+        a_time.cmp(&b_time).then(a.0.cmp(&b.0))
+    });
+
+    let mut size = 0_u64;
+    for (track, first, last) in &chunks {
+        for it in samples[*track][*first..*last].iter_mut() {
+            it.offset = size;
+            size += it.size as u64;
+        }
+    }
+
+    let mdat_header = if (u32::MAX as u64) < 8 + size { 16 } else { 8 };
+    let mut base = 0;
+    loop {
+        for (trak, it) in moov.traks.iter_mut().zip(&samples) {
+            let v = it.iter().map(|it| Sample { offset: base + it.offset, ..*it }).collect::<Vec<_>>();
+            trak.mdia.minf.stbl.set_samples(&v);
+        }
+
+        let v = 16 + ftyp.len() as u64 + moov.len() as u64 + mdat_header;
+        if base == v {
+            break;
+        }
+        base = v;
+    }
+
+    let mut v = Object::new(ftyp::BOX_TYPE, ftyp {
+        major_brand: ftyp.major_brand,
+        minor_version: ftyp.minor_version,
+        compatible_brands: ftyp.compatible_brands.clone(),
+    }.as_bytes());
+    w.write_all(&v.as_bytes())?;
+    let mut v = Object::new(moov::BOX_TYPE, moov.as_bytes());
+    w.write_all(&v.as_bytes())?;
+    if 16 == mdat_header {
+        w.write_all(&1_u32.to_be_bytes())?;
+        w.write_all(&types::mdat.to_be_bytes())?;
+        w.write_all(&(16 + size).to_be_bytes())?;
+    } else {
+        w.write_all(&(8 + size as u32).to_be_bytes())?;
+        w.write_all(&types::mdat.to_be_bytes())?;
+    }
+
+    let mut buf = vec![];
+    for (track, first, last) in &chunks {
+        for i in *first..*last {
+            let (source, offset) = inputs[*track][i];
+            let r = &mut sources[source];
+
+            buf.resize(samples[*track][i].size as usize, 0);
+            r.seek(SeekFrom::Start(offset))?;
+            r.read_exact(&mut buf)?;
+            w.write_all(&buf)?;
+        }
+    }
+
+    Ok(moov)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bytes::BytesMut;
+
+    use crate::{IO, Object};
+    use crate::ftyp::ftyp;
+    use crate::Error;
+    use crate::moov::{hdlr, moov, Sample, SampleEntry, TrackReferenceType, trak, tref};
+    use crate::remux::{concat, remux, trim, write};
+    use crate::types::types;
+
+    fn new_trak(track_id: u32, handler: hdlr, sync: &[usize]) -> (trak, Vec<Sample>) {
+        let mut v = trak::default();
+
+        v.tkhd.track_id = track_id;
+        v.tkhd.duration = 1000;
+        v.mdia.mdhd.timescale = 1000;
+        v.mdia.mdhd.duration = 1000;
+        v.mdia.hdlr = handler;
+
+        let samples = (0..10).map(|i| Sample {
+            offset: 40 * (track_id as u64 - 1) + 4 * i as u64,
+            size: 4,
+            decode_time: 100 * i as u64,
+            duration: 100,
+            composition_offset: 0,
+            is_sync: sync.is_empty() || sync.contains(&i),
+            description_index: 1,
+        }).collect();
+
+        (v, samples)
+    }
+
+    fn new_file(source: &[u8], entry: &[u8]) -> (ftyp, Vec<u8>) {
+        let ftyp = ftyp {
+            major_brand: types::isom,
+            minor_version: 0,
+            compatible_brands: vec![types::isom, types::mp41],
+        };
+
+        let mut m = moov::default();
+        m.mvhd.timescale = 1000;
+        m.mvhd.duration = 1000;
+        let (mut video, video_samples) = new_trak(1, hdlr::vide("Video"), &[0, 4, 8]);
+        video.mdia.minf.stbl.stsd.entries.push(SampleEntry::Unknown {
+            base: Box::new(SampleEntry::Base {
+                handler_type: 0x76703039,
+                data_reference_index: 1,
+            }),
+            ext: BytesMut::from(entry),
+        });
+        let (mut audio, audio_samples) = new_trak(2, hdlr::soun("Audio"), &[]);
+        audio.mdia.minf.stbl.stsd.entries.push(SampleEntry::Unknown {
+            base: Box::new(SampleEntry::Base {
+                handler_type: 0x74776f73,
+                data_reference_index: 1,
+            }),
+            ext: BytesMut::new(),
+        });
+        m.traks = vec![video, audio];
+
+        let mut file = vec![];
+        let samples = vec![video_samples, audio_samples].into_iter().map(|it| it.into_iter().map(|it| (0, it)).collect()).collect();
+        write(&mut [Cursor::new(source)], &ftyp, m, samples, &mut file).unwrap();
+
+        (ftyp, file)
+    }
+
+    fn read(file: &[u8]) -> moov {
+        let mut r = BytesMut::from(file);
+
+        loop {
+            let mut b = Object::parse(&mut r);
+            if moov::BOX_TYPE == b.box_type {
+                return moov::parse(&mut b.payload);
+            }
+        }
+    }
+
+    #[test]
+    fn chk_trim() {
+        let ftyp = ftyp {
+            major_brand: types::isom,
+            minor_version: 0,
+            compatible_brands: vec![types::isom, types::mp41],
+        };
+        let source = (0..80).collect::<Vec<u8>>();
+
+        let mut m = moov::default();
+        m.mvhd.timescale = 1000;
+        m.mvhd.duration = 1000;
+        let (video, video_samples) = new_trak(1, hdlr::vide("Video"), &[0, 4, 8]);
+        let (audio, audio_samples) = new_trak(2, hdlr::soun("Audio"), &[]);
+        m.traks = vec![video, audio];
+
+        let mut file = vec![];
+        let samples = vec![video_samples, audio_samples].into_iter().map(|it| it.into_iter().map(|it| (0, it)).collect()).collect();
+        let m = write(&mut [Cursor::new(&source)], &ftyp, m, samples, &mut file).unwrap();
+        assert_eq!(m, read(&file));
+
+        let samples = m.traks[0].mdia.minf.stbl.samples();
+        assert_eq!(10, samples.len());
+        assert_eq!(vec![true, false, false, false, true, false, false, false, true, false], samples.iter().map(|it| it.is_sync).collect::<Vec<_>>());
+        assert_eq!(&source[12..16], &file[samples[3].offset as usize..][..4]);
+
+        let mut output = vec![];
+        let v = trim(&mut Cursor::new(&file), &ftyp, m, 450, 800, &mut output).unwrap();
+        assert_eq!(v, read(&output));
+        assert_eq!(350, v.mvhd.duration);
+
+        for (i, it) in v.traks.iter().enumerate() {
+            assert_eq!(350, it.tkhd.duration);
+            assert_eq!(Some(&vec![(350, 50, 1)]), it.edts.as_ref().and_then(|it| it.elst.as_ref()).map(|it| &it.entries));
+
+            let samples = it.mdia.minf.stbl.samples();
+            assert_eq!(4, samples.len());
+            assert!(samples[0].is_sync);
+            for (j, it) in samples.iter().enumerate() {
+                let n = 40 * i + 4 * (4 + j);
+                assert_eq!(&source[n..n + 4], &output[it.offset as usize..][..4]);
+            }
+        }
+
+        let v = read(&output);
+        assert!(trim(&mut Cursor::new(&output), &ftyp, v, 300, 300, &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn chk_concat() {
+        let source = (0..80).collect::<Vec<u8>>();
+        let (ftyp, a) = new_file(&source, &[1, 2, 3]);
+        let (_, b) = new_file(&source[..].iter().rev().copied().collect::<Vec<_>>(), &[4, 5, 6]);
+
+        let mut output = vec![];
+        let v = concat(vec![(Cursor::new(&a), read(&a)), (Cursor::new(&b), read(&b))], &ftyp, &mut output).unwrap();
+        assert_eq!(v, read(&output));
+        assert_eq!(2000, v.mvhd.duration);
+
+        let stbl = &v.traks[0].mdia.minf.stbl;
+        assert_eq!(2, stbl.stsd.entries.len());
+        let samples = stbl.samples();
+        assert_eq!(20, samples.len());
+        assert_eq!((0..20).map(|i| 100 * i).collect::<Vec<u64>>(), samples.iter().map(|it| it.decode_time).collect::<Vec<_>>());
+        assert_eq!((1, 2), (samples[9].description_index, samples[10].description_index));
+        assert_eq!(&source[..4], &output[samples[0].offset as usize..][..4]);
+        assert_eq!(&[79, 78, 77, 76], &output[samples[10].offset as usize..][..4]);
+        assert_eq!(2000, v.traks[1].mdia.mdhd.duration);
+
+        let mut m = read(&b);
+        m.traks[1].mdia.hdlr = hdlr::vide("Video");
+        assert_eq!(
+            Some(Error::Incompatible { input: 1, track_id: 2 }),
+            concat(vec![(Cursor::new(&a), read(&a)), (Cursor::new(&b), m)], &ftyp, &mut vec![]).err()
+        );
+
+        // a sample description index of 0
+        let mut m = read(&b);
+        m.traks[1].mdia.minf.stbl.stsc.entries[0].2 = 0;
+        assert_eq!(
+            Some(Error::Incompatible { input: 1, track_id: 2 }),
+            concat(vec![(Cursor::new(&a), read(&a)), (Cursor::new(&b), m)], &ftyp, &mut vec![]).err()
+        );
+
+        // video of different sizes
+        let visual = |width| SampleEntry::Unknown {
+            base: Box::new(SampleEntry::Visual {
+                base: Box::new(SampleEntry::Base {
+                    handler_type: 0x76703039,
+                    data_reference_index: 1,
+                }),
+                width,
+                height: 720,
+                horiz_resolution: 0x00480000,
+                vert_resolution: 0x00480000,
+                frame_count: 1,
+                compressor_name: "".to_owned(),
+                depth: 24,
+            }),
+            ext: BytesMut::new(),
+        };
+        let (mut m, mut n) = (read(&a), read(&b));
+        m.traks[0].mdia.minf.stbl.stsd.entries[0] = visual(1280);
+        n.traks[0].mdia.minf.stbl.stsd.entries[0] = visual(1920);
+        assert_eq!(
+            Some(Error::Incompatible { input: 1, track_id: 1 }),
+            concat(vec![(Cursor::new(&a), m), (Cursor::new(&b), n)], &ftyp, &mut vec![]).err()
+        );
+        assert_eq!(Some(Error::NoInput), concat(Vec::<(Cursor<Vec<u8>>, moov)>::new(), &ftyp, &mut vec![]).err());
+    }
+
+    #[test]
+    fn chk_remux() {
+        let source = (0..80).collect::<Vec<u8>>();
+        let (ftyp, a) = new_file(&source, &[1, 2, 3]);
+        let (_, b) = new_file(&source[..].iter().rev().copied().collect::<Vec<_>>(), &[4, 5, 6]);
+
+        let mut m = read(&a);
+        m.traks[0].tref = Some({
+            let mut v = tref::default();
+
+            v.add(TrackReferenceType::Cdsc, 2);
+
+            v
+        });
+
+        let mut output = vec![];
+        let v = remux(vec![(Cursor::new(&a), m), (Cursor::new(&b), read(&b))], &[(1, 2), (0, 1)], &ftyp, &mut output).unwrap();
+        assert_eq!(v, read(&output));
+        assert_eq!(vec![1, 2], v.traks.iter().map(|it| it.tkhd.track_id).collect::<Vec<_>>());
+        assert_eq!(3, v.mvhd.next_track_id);
+        assert_eq!(types::soun, v.traks[0].mdia.hdlr.handler_type);
+        assert!(v.traks[1].tref.is_none());
+        assert_eq!(Ok(()), v.validate_track_references());
+
+        let samples = v.traks[0].mdia.minf.stbl.samples();
+        assert_eq!(&[39, 38, 37, 36], &output[samples[0].offset as usize..][..4]);
+        let samples = v.traks[1].mdia.minf.stbl.samples();
+        assert_eq!(&source[..4], &output[samples[0].offset as usize..][..4]);
+
+        let mut m = read(&a);
+        m.traks[0].tref = Some({
+            let mut v = tref::default();
+
+            v.add(TrackReferenceType::Cdsc, 2);
+
+            v
+        });
+        let v = remux(vec![(Cursor::new(&a), m)], &[(0, 2), (0, 1)], &ftyp, &mut vec![]).unwrap();
+        assert_eq!(&[1], v.traks[1].tref.as_ref().unwrap().track_ids(TrackReferenceType::Cdsc));
+
+        let v = remux(vec![(Cursor::new(&a), read(&a))], &[(0, 2)], &ftyp, &mut vec![]).unwrap();
+        assert_eq!(1, v.traks.len());
+        assert_eq!(2, v.mvhd.next_track_id);
+        assert_eq!(
+            Some(Error::UnknownTrack { input: 0, track_id: 3 }),
+            remux(vec![(Cursor::new(&a), read(&a))], &[(0, 3)], &ftyp, &mut vec![]).err()
+        );
+    }
+}
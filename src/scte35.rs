@@ -0,0 +1,713 @@
+use bytes::{Buf, BufMut, BytesMut};
+
+/// `emsg` scheme carrying a binary `splice_info_section`.
+pub const SCHEME_ID_URI: &str = "urn:scte:scte35:2013:bin";
+
+/// "CUEI", the identifier of the descriptors defined by SCTE 35.
+pub const CUEI: u32 = 0x43554549;
+
+/// SCTE 35 `splice_info_section`, unencrypted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpliceInfoSection {
+    pub sap_type: u8,
+    pub protocol_version: u8,
+    pub pts_adjustment: u64,
+    pub cw_index: u8,
+    pub tier: u16,
+    pub splice_command: SpliceCommand,
+    pub splice_descriptors: Vec<SpliceDescriptor>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpliceCommand {
+    SpliceNull,
+    SpliceInsert(SpliceInsert),
+    /// `pts_time` of the `splice_time()`, if specified
+    TimeSignal(Option<u64>),
+    BandwidthReservation,
+    /// splice_schedule, private_command and reserved types, kept as is
+    Unknown {
+        splice_command_type: u8,
+        data: BytesMut,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpliceInsert {
+    pub splice_event_id: u32,
+    pub splice_event_cancel_indicator: bool,
+    pub out_of_network_indicator: bool,
+    pub program_splice_flag: bool,
+    pub splice_immediate_flag: bool,
+    /// `pts_time` of the program `splice_time()`, if specified
+    pub splice_time: Option<u64>,
+    /// `component_tag` and `pts_time` of each component, when
+    /// `program_splice_flag` is not set
+    pub components: Vec<(u8, Option<u64>)>,
+    pub break_duration: Option<BreakDuration>,
+    pub unique_program_id: u16,
+    pub avail_num: u8,
+    pub avails_expected: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BreakDuration {
+    pub auto_return: bool,
+    pub duration: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpliceDescriptor {
+    Segmentation(SegmentationDescriptor),
+    Unknown {
+        splice_descriptor_tag: u8,
+        identifier: u32,
+        data: BytesMut,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SegmentationDescriptor {
+    pub segmentation_event_id: u32,
+    pub segmentation_event_cancel_indicator: bool,
+    pub segmentation_event_id_compliance_indicator: bool,
+    pub program_segmentation_flag: bool,
+    /// `None` when `delivery_not_restricted_flag` is set
+    pub delivery_restrictions: Option<DeliveryRestrictions>,
+    /// `component_tag` and `pts_offset` of each component, when
+    /// `program_segmentation_flag` is not set
+    pub components: Vec<(u8, u64)>,
+    pub segmentation_duration: Option<u64>,
+    pub segmentation_upid_type: u8,
+    pub segmentation_upid: BytesMut,
+    pub segmentation_type_id: u8,
+    pub segment_num: u8,
+    pub segments_expected: u8,
+    /// `sub_segment_num` and `sub_segments_expected`
+    pub sub_segment: Option<(u8, u8)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeliveryRestrictions {
+    pub web_delivery_allowed_flag: bool,
+    pub no_regional_blackout_flag: bool,
+    pub archive_allowed_flag: bool,
+    pub device_restrictions: u8,
+}
+
+impl SpliceInfoSection {
+    pub const TABLE_ID: u8 = 0xFC;
+
+    pub fn new(splice_command: SpliceCommand) -> Self {
+        Self {
+            sap_type: 3,
+            protocol_version: 0,
+            pts_adjustment: 0,
+            cw_index: 0xFF,
+            tier: 0xFFF,
+            splice_command,
+            splice_descriptors: vec![],
+        }
+    }
+
+    /// `pts_time` of the splice, with `pts_adjustment` applied, in 90 kHz
+    /// units.
+    pub fn pts_time(&self) -> Option<u64> {
+        let v = match &self.splice_command {
+            SpliceCommand::SpliceInsert(it) => { it.splice_time }
+            SpliceCommand::TimeSignal(it) => { *it }
+            _ => { None }
+        }?;
+
+        Some(0x1FFFFFFFF & (v + self.pts_adjustment))
+    }
+
+    /// Decodes a section, as carried in the `message_data` of an `emsg`.
+    ///
+    /// Returns `None` when the section is truncated, fails its CRC or is
+    /// encrypted.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 3 || Self::TABLE_ID != data[0] {
+            return None;
+        }
+        let section_length = (0x0FFF & u16::from_be_bytes([data[1], data[2]])) as usize;
+        if data.len() < 3 + section_length || section_length < 15 {
+            return None;
+        }
+        let data = &data[..3 + section_length];
+        if 0 != crc32(data) {
+            return None;
+        }
+
+        let mut r = BytesMut::from(&data[1..data.len() - 4]);
+        let sap_type = (0b11 & (r.get_u16() >> 12)) as u8;
+        let protocol_version = r.get_u8();
+        let v = r.get_u8();
+        if 0 != (0x80 & v) {
+            return None;
+        }
+        let pts_adjustment = ((1 & v) as u64) << 32 | r.get_u32() as u64;
+        let cw_index = r.get_u8();
+        let v = get_u24(&mut r);
+        let tier = (v >> 12) as u16;
+        let splice_command_length = (0x0FFF & v) as usize;
+        let splice_command_type = r.get_u8();
+
+        let splice_command = if 0x0FFF == splice_command_length {
+            // legacy senders leave the length unspecified
+            SpliceCommand::parse(splice_command_type, &mut r, false)?
+        } else {
+            if r.len() < splice_command_length {
+                return None;
+            }
+            SpliceCommand::parse(splice_command_type, &mut r.split_to(splice_command_length), true)?
+        };
+
+        if r.len() < 2 {
+            return None;
+        }
+        let descriptor_loop_length = r.get_u16() as usize;
+        if r.len() < descriptor_loop_length {
+            return None;
+        }
+        let mut d = r.split_to(descriptor_loop_length);
+        let mut splice_descriptors = vec![];
+        while !d.is_empty() {
+            if d.len() < 2 || d.len() < 2 + d[1] as usize {
+                return None;
+            }
+            let splice_descriptor_tag = d.get_u8();
+            let descriptor_length = d.get_u8() as usize;
+            splice_descriptors.push(SpliceDescriptor::parse(splice_descriptor_tag, &mut d.split_to(descriptor_length))?);
+        }
+
+        Some(Self {
+            sap_type,
+            protocol_version,
+            pts_adjustment,
+            cw_index,
+            tier,
+            splice_command,
+            splice_descriptors,
+        })
+    }
+
+    /// Encodes the section, lengths and CRC included.
+    pub fn encode(&self) -> BytesMut {
+        let command = self.splice_command.as_bytes();
+        let mut descriptors = BytesMut::new();
+        for it in &self.splice_descriptors {
+            let v = it.as_bytes();
+            descriptors.put_u8(it.splice_descriptor_tag());
+            descriptors.put_u8(v.len() as u8);
+            descriptors.put(v);
+        }
+
+        let mut w = BytesMut::new();
+
+        w.put_u8(Self::TABLE_ID);
+        w.put_u16((0b11 & self.sap_type as u16) << 12 | (0x0FFF & (11 + command.len() + 2 + descriptors.len() + 4) as u16));
+        w.put_u8(self.protocol_version);
+        w.put_u8((1 & (self.pts_adjustment >> 32)) as u8);
+        w.put_u32(self.pts_adjustment as u32);
+        w.put_u8(self.cw_index);
+        put_u24(&mut w, (0x0FFF & self.tier as u32) << 12 | (0x0FFF & command.len() as u32));
+        w.put_u8(self.splice_command.splice_command_type());
+        w.put(command);
+        w.put_u16(descriptors.len() as u16);
+        w.put(descriptors);
+        let crc = crc32(&w);
+        w.put_u32(crc);
+
+        w
+    }
+}
+
+impl SpliceCommand {
+    pub fn splice_command_type(&self) -> u8 {
+        match self {
+            SpliceCommand::SpliceNull => { 0x00 }
+            SpliceCommand::SpliceInsert(_) => { 0x05 }
+            SpliceCommand::TimeSignal(_) => { 0x06 }
+            SpliceCommand::BandwidthReservation => { 0x07 }
+            SpliceCommand::Unknown { splice_command_type, .. } => { *splice_command_type }
+        }
+    }
+
+    // `sized` tells whether `r` holds exactly the command, so that unknown
+    // commands can be kept
+    fn parse(splice_command_type: u8, r: &mut BytesMut, sized: bool) -> Option<Self> {
+        let rst = match splice_command_type {
+            0x00 => { SpliceCommand::SpliceNull }
+            0x05 => { SpliceCommand::SpliceInsert(SpliceInsert::parse(r)?) }
+            0x06 => { SpliceCommand::TimeSignal(get_splice_time(r)?) }
+            0x07 => { SpliceCommand::BandwidthReservation }
+            _ => {
+                if !sized {
+                    return None;
+                }
+
+                SpliceCommand::Unknown {
+                    splice_command_type,
+                    data: r.split_to(r.len()),
+                }
+            }
+        };
+
+        Some(rst)
+    }
+
+    fn as_bytes(&self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        match self {
+            SpliceCommand::SpliceInsert(it) => { it.as_bytes(&mut w) }
+            SpliceCommand::TimeSignal(it) => { put_splice_time(&mut w, *it) }
+            SpliceCommand::Unknown { data, .. } => { w.put(data.chunk()) }
+            _ => {}
+        }
+
+        w
+    }
+}
+
+impl SpliceInsert {
+    //! splice_insert() {
+    //!     splice_event_id                                 32
+    //!     splice_event_cancel_indicator                   1
+    //!     reserved                                        7
+    //!     if (splice_event_cancel_indicator == ‘0’) {
+    //!         out_of_network_indicator                    1
+    //!         program_splice_flag                         1
+    //!         duration_flag                               1
+    //!         splice_immediate_flag                       1
+    //!         reserved                                    4
+    //!         if ((program_splice_flag == ‘1’) && (splice_immediate_flag == ‘0’))
+    //!             splice_time()
+    //!         if (program_splice_flag == ‘0’) {
+    //!             component_count                         8
+    //!             for (i = 0; i < component_count; i++) {
+    //!                 component_tag                       8
+    //!                 if (splice_immediate_flag == ‘0’)
+    //!                     splice_time()
+    //!             }
+    //!         }
+    //!         if (duration_flag == ‘1’)
+    //!             break_duration()
+    //!         unique_program_id                           16
+    //!         avail_num                                   8
+    //!         avails_expected                             8
+    //!     }
+    //! }
+    fn parse(r: &mut BytesMut) -> Option<Self> {
+        need(r, 5)?;
+        let mut rst = Self {
+            splice_event_id: r.get_u32(),
+            splice_event_cancel_indicator: 0 != (0x80 & r.get_u8()),
+            ..Self::default()
+        };
+        if rst.splice_event_cancel_indicator {
+            return Some(rst);
+        }
+
+        need(r, 1)?;
+        let v = r.get_u8();
+        rst.out_of_network_indicator = 0 != (0x80 & v);
+        rst.program_splice_flag = 0 != (0x40 & v);
+        let duration_flag = 0 != (0x20 & v);
+        rst.splice_immediate_flag = 0 != (0x10 & v);
+
+        if rst.program_splice_flag {
+            if !rst.splice_immediate_flag {
+                rst.splice_time = get_splice_time(r)?;
+            }
+        } else {
+            need(r, 1)?;
+            let component_count = r.get_u8();
+            for _ in 0..component_count {
+                need(r, 1)?;
+                let component_tag = r.get_u8();
+                let splice_time = if rst.splice_immediate_flag {
+                    None
+                } else {
+                    get_splice_time(r)?
+                };
+                rst.components.push((component_tag, splice_time));
+            }
+        }
+        if duration_flag {
+            need(r, 5)?;
+            let v = r.get_u8();
+            rst.break_duration = Some(BreakDuration {
+                auto_return: 0 != (0x80 & v),
+                duration: ((1 & v) as u64) << 32 | r.get_u32() as u64,
+            });
+        }
+        need(r, 4)?;
+        rst.unique_program_id = r.get_u16();
+        rst.avail_num = r.get_u8();
+        rst.avails_expected = r.get_u8();
+
+        Some(rst)
+    }
+
+    fn as_bytes(&self, w: &mut BytesMut) {
+        w.put_u32(self.splice_event_id);
+        w.put_u8((self.splice_event_cancel_indicator as u8) << 7 | 0x7F);
+        if self.splice_event_cancel_indicator {
+            return;
+        }
+
+        w.put_u8(
+            (self.out_of_network_indicator as u8) << 7 |
+                (self.program_splice_flag as u8) << 6 |
+                (self.break_duration.is_some() as u8) << 5 |
+                (self.splice_immediate_flag as u8) << 4 |
+                0x0F
+        );
+        if self.program_splice_flag {
+            if !self.splice_immediate_flag {
+                put_splice_time(w, self.splice_time);
+            }
+        } else {
+            w.put_u8(self.components.len() as u8);
+            for (component_tag, splice_time) in &self.components {
+                w.put_u8(*component_tag);
+                if !self.splice_immediate_flag {
+                    put_splice_time(w, *splice_time);
+                }
+            }
+        }
+        if let Some(it) = &self.break_duration {
+            w.put_u8((it.auto_return as u8) << 7 | 0x7E | (1 & (it.duration >> 32)) as u8);
+            w.put_u32(it.duration as u32);
+        }
+        w.put_u16(self.unique_program_id);
+        w.put_u8(self.avail_num);
+        w.put_u8(self.avails_expected);
+    }
+}
+
+impl SpliceDescriptor {
+    pub fn splice_descriptor_tag(&self) -> u8 {
+        match self {
+            SpliceDescriptor::Segmentation(_) => { 0x02 }
+            SpliceDescriptor::Unknown { splice_descriptor_tag, .. } => { *splice_descriptor_tag }
+        }
+    }
+
+    fn parse(splice_descriptor_tag: u8, r: &mut BytesMut) -> Option<Self> {
+        need(r, 4)?;
+        let identifier = r.get_u32();
+
+        if 0x02 == splice_descriptor_tag && CUEI == identifier {
+            Some(SpliceDescriptor::Segmentation(SegmentationDescriptor::parse(r)?))
+        } else {
+            Some(SpliceDescriptor::Unknown {
+                splice_descriptor_tag,
+                identifier,
+                data: r.split_to(r.len()),
+            })
+        }
+    }
+
+    fn as_bytes(&self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        match self {
+            SpliceDescriptor::Segmentation(it) => {
+                w.put_u32(CUEI);
+                it.as_bytes(&mut w);
+            }
+            SpliceDescriptor::Unknown { identifier, data, .. } => {
+                w.put_u32(*identifier);
+                w.put(data.chunk());
+            }
+        }
+
+        w
+    }
+}
+
+impl SegmentationDescriptor {
+    //! segmentation_descriptor() {
+    //!     splice_descriptor_tag                           8
+    //!     descriptor_length                               8
+    //!     identifier                                      32
+    //!     segmentation_event_id                           32
+    //!     segmentation_event_cancel_indicator             1
+    //!     segmentation_event_id_compliance_indicator      1
+    //!     reserved                                        6
+    //!     if (segmentation_event_cancel_indicator == ‘0’) {
+    //!         program_segmentation_flag                   1
+    //!         segmentation_duration_flag                  1
+    //!         delivery_not_restricted_flag                1
+    //!         if (delivery_not_restricted_flag == ‘0’) {
+    //!             web_delivery_allowed_flag               1
+    //!             no_regional_blackout_flag               1
+    //!             archive_allowed_flag                    1
+    //!             device_restrictions                     2
+    //!         } else {
+    //!             reserved                                5
+    //!         }
+    //!         if (program_segmentation_flag == ‘0’) {
+    //!             component_count                         8
+    //!             for (i = 0; i < component_count; i++) {
+    //!                 component_tag                       8
+    //!                 reserved                            7
+    //!                 pts_offset                          33
+    //!             }
+    //!         }
+    //!         if (segmentation_duration_flag == ‘1’)
+    //!             segmentation_duration                   40
+    //!         segmentation_upid_type                      8
+    //!         segmentation_upid_length                    8
+    //!         segmentation_upid()
+    //!         segmentation_type_id                        8
+    //!         segment_num                                 8
+    //!         segments_expected                           8
+    //!         if (segmentation_type_id == ‘0x34’ || ‘0x36’ || ‘0x38’ || ‘0x3A’) {
+    //!             sub_segment_num                         8
+    //!             sub_segments_expected                   8
+    //!         }
+    //!     }
+    //! }
+    fn parse(r: &mut BytesMut) -> Option<Self> {
+        need(r, 5)?;
+        let segmentation_event_id = r.get_u32();
+        let v = r.get_u8();
+        let mut rst = Self {
+            segmentation_event_id,
+            segmentation_event_cancel_indicator: 0 != (0x80 & v),
+            segmentation_event_id_compliance_indicator: 0 != (0x40 & v),
+            ..Self::default()
+        };
+        if rst.segmentation_event_cancel_indicator {
+            return Some(rst);
+        }
+
+        need(r, 1)?;
+        let v = r.get_u8();
+        rst.program_segmentation_flag = 0 != (0x80 & v);
+        let segmentation_duration_flag = 0 != (0x40 & v);
+        if 0 == (0x20 & v) {
+            rst.delivery_restrictions = Some(DeliveryRestrictions {
+                web_delivery_allowed_flag: 0 != (0x10 & v),
+                no_regional_blackout_flag: 0 != (0x08 & v),
+                archive_allowed_flag: 0 != (0x04 & v),
+                device_restrictions: 0b11 & v,
+            });
+        }
+
+        if !rst.program_segmentation_flag {
+            need(r, 1)?;
+            let component_count = r.get_u8();
+            for _ in 0..component_count {
+                need(r, 6)?;
+                let component_tag = r.get_u8();
+                let pts_offset = ((1 & r.get_u8()) as u64) << 32 | r.get_u32() as u64;
+                rst.components.push((component_tag, pts_offset));
+            }
+        }
+        if segmentation_duration_flag {
+            need(r, 5)?;
+            rst.segmentation_duration = Some((r.get_u8() as u64) << 32 | r.get_u32() as u64);
+        }
+        need(r, 2)?;
+        rst.segmentation_upid_type = r.get_u8();
+        let segmentation_upid_length = r.get_u8() as usize;
+        need(r, segmentation_upid_length + 3)?;
+        rst.segmentation_upid = r.split_to(segmentation_upid_length);
+        rst.segmentation_type_id = r.get_u8();
+        rst.segment_num = r.get_u8();
+        rst.segments_expected = r.get_u8();
+        // only present in sections written after SCTE 35 2016
+        if 2 <= r.len() {
+            rst.sub_segment = Some((r.get_u8(), r.get_u8()));
+        }
+
+        Some(rst)
+    }
+
+    fn as_bytes(&self, w: &mut BytesMut) {
+        w.put_u32(self.segmentation_event_id);
+        w.put_u8(
+            (self.segmentation_event_cancel_indicator as u8) << 7 |
+                (self.segmentation_event_id_compliance_indicator as u8) << 6 |
+                0x3F
+        );
+        if self.segmentation_event_cancel_indicator {
+            return;
+        }
+
+        let v = (self.program_segmentation_flag as u8) << 7 |
+            (self.segmentation_duration.is_some() as u8) << 6;
+        w.put_u8(match &self.delivery_restrictions {
+            Some(it) => {
+                v | (it.web_delivery_allowed_flag as u8) << 4 |
+                    (it.no_regional_blackout_flag as u8) << 3 |
+                    (it.archive_allowed_flag as u8) << 2 |
+                    (0b11 & it.device_restrictions)
+            }
+            None => { v | 0x3F }
+        });
+        if !self.program_segmentation_flag {
+            w.put_u8(self.components.len() as u8);
+            for (component_tag, pts_offset) in &self.components {
+                w.put_u8(*component_tag);
+                w.put_u8(0xFE | (1 & (pts_offset >> 32)) as u8);
+                w.put_u32(*pts_offset as u32);
+            }
+        }
+        if let Some(v) = self.segmentation_duration {
+            w.put_u8((v >> 32) as u8);
+            w.put_u32(v as u32);
+        }
+        w.put_u8(self.segmentation_upid_type);
+        w.put_u8(self.segmentation_upid.len() as u8);
+        w.put(self.segmentation_upid.chunk());
+        w.put_u8(self.segmentation_type_id);
+        w.put_u8(self.segment_num);
+        w.put_u8(self.segments_expected);
+        if let Some((sub_segment_num, sub_segments_expected)) = self.sub_segment {
+            w.put_u8(sub_segment_num);
+            w.put_u8(sub_segments_expected);
+        }
+    }
+}
+
+fn need(r: &BytesMut, n: usize) -> Option<()> {
+    (n <= r.len()).then_some(())
+}
+
+fn get_u24(r: &mut BytesMut) -> u32 {
+    (r.get_u16() as u32) << 8 | r.get_u8() as u32
+}
+
+fn put_u24(w: &mut BytesMut, v: u32) {
+    w.put_u16((v >> 8) as u16);
+    w.put_u8(v as u8);
+}
+
+// splice_time(): time_specified_flag, then a 33-bit pts_time when set
+fn get_splice_time(r: &mut BytesMut) -> Option<Option<u64>> {
+    need(r, 1)?;
+    if 0 == (0x80 & r[0]) {
+        r.advance(1);
+        return Some(None);
+    }
+
+    need(r, 5)?;
+    Some(Some(((1 & r.get_u8()) as u64) << 32 | r.get_u32() as u64))
+}
+
+fn put_splice_time(w: &mut BytesMut, pts_time: Option<u64>) {
+    match pts_time {
+        Some(v) => {
+            w.put_u8(0xFE | (1 & (v >> 32)) as u8);
+            w.put_u32(v as u32);
+        }
+        None => { w.put_u8(0x7F) }
+    }
+}
+
+/// CRC-32/MPEG-2, which is 0 over a section ending with its own CRC.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFF_u32;
+
+    for it in data {
+        crc ^= (*it as u32) << 24;
+        for _ in 0..8 {
+            crc = if 0 != (0x80000000 & crc) { crc << 1 ^ 0x04C11DB7 } else { crc << 1 };
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::emsg::emsg;
+    use crate::scte35::{BreakDuration, SCHEME_ID_URI, SegmentationDescriptor, SpliceCommand, SpliceDescriptor, SpliceInfoSection, SpliceInsert};
+
+    #[test]
+    fn chk_scte35() {
+        // SCTE 35 sample 14.1, time_signal with a placement opportunity start
+        let time_signal = [
+            0xfc, 0x30, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xf0, 0x05, 0x06, 0xfe, 0x72,
+            0xbd, 0x00, 0x50, 0x00, 0x1e, 0x02, 0x1c, 0x43, 0x55, 0x45, 0x49, 0x48, 0x00, 0x00, 0x8e, 0x7f,
+            0xcf, 0x00, 0x01, 0xa5, 0x99, 0xb0, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x2c, 0xa0, 0xa1, 0x8a,
+            0x34, 0x02, 0x00, 0x9a, 0xc9, 0xd1, 0x7e,
+        ];
+        // SCTE 35 sample 14.2, splice_insert with an avail descriptor
+        let splice_insert = [
+            0xfc, 0x30, 0x2f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xf0, 0x14, 0x05, 0x48, 0x00,
+            0x00, 0x8f, 0x7f, 0xef, 0xfe, 0x73, 0x69, 0xc0, 0x2e, 0xfe, 0x00, 0x52, 0xcc, 0xf5, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x0a, 0x00, 0x08, 0x43, 0x55, 0x45, 0x49, 0x00, 0x00, 0x01, 0x35, 0x62, 0xdb,
+            0xa3, 0x0a,
+        ];
+
+        let b = SpliceInfoSection::decode(&time_signal).unwrap();
+        assert_eq!(SpliceCommand::TimeSignal(Some(0x072bd0050)), b.splice_command);
+        match &b.splice_descriptors[..] {
+            [SpliceDescriptor::Segmentation(it)] => {
+                assert_eq!(0x4800008e, it.segmentation_event_id);
+                assert_eq!(Some(0x0001a599b0), it.segmentation_duration);
+                assert_eq!(0x34, it.segmentation_type_id);
+                assert_eq!(None, it.sub_segment);
+            }
+            _ => { panic!("{:?}", b.splice_descriptors) }
+        }
+        assert_eq!(&time_signal[..], &b.encode()[..]);
+
+        let b = SpliceInfoSection::decode(&splice_insert).unwrap();
+        assert_eq!(Some(0x07369c02e), b.pts_time());
+        assert_eq!(&splice_insert[..], &b.encode()[..]);
+
+        let mut corrupted = splice_insert;
+        corrupted[20] ^= 1;
+        assert_eq!(None, SpliceInfoSection::decode(&corrupted));
+
+        // splice_null with the legacy unspecified splice_command_length
+        let legacy_splice_null = [
+            0xfc, 0x30, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00,
+            0x43, 0x77, 0x5f, 0xdf,
+        ];
+        assert_eq!(Some(SpliceCommand::SpliceNull), SpliceInfoSection::decode(&legacy_splice_null).map(|it| it.splice_command));
+
+        // time_signal with the legacy length and no descriptor_loop_length
+        let truncated_time_signal = [
+            0xfc, 0x30, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x06, 0xfe, 0x00,
+            0x00, 0x00, 0x01, 0xc3, 0xa1, 0xfa, 0xa0,
+        ];
+        assert_eq!(None, SpliceInfoSection::decode(&truncated_time_signal));
+
+        let mut b = SpliceInfoSection::new(SpliceCommand::SpliceInsert(SpliceInsert {
+            splice_event_id: 1,
+            out_of_network_indicator: true,
+            splice_immediate_flag: true,
+            components: vec![(1, None), (2, None)],
+            break_duration: Some(BreakDuration {
+                auto_return: true,
+                duration: 30 * 90000,
+            }),
+            ..SpliceInsert::default()
+        }));
+        b.pts_adjustment = 0x1FFFFFFFF;
+        b.splice_descriptors.push(SpliceDescriptor::Segmentation(SegmentationDescriptor {
+            segmentation_event_id: 2,
+            components: vec![(1, 0x100000000)],
+            segmentation_upid: BytesMut::from(&b"upid"[..]),
+            segmentation_type_id: 0x34,
+            sub_segment: Some((1, 2)),
+            ..SegmentationDescriptor::default()
+        }));
+
+        let mut event = emsg::default();
+        event.set_splice_info_section(&b);
+        assert_eq!(SCHEME_ID_URI, event.scheme_id_uri);
+        assert_eq!(Some(b), event.splice_info_section());
+    }
+}
@@ -0,0 +1,519 @@
+use std::fmt::{Debug, Formatter};
+use std::time::SystemTime;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{IO, Object};
+use crate::object;
+use crate::emsg::emsg;
+use crate::mfra::mfra;
+use crate::moof::moof;
+use crate::moov::trex;
+use crate::prft::prft;
+use crate::sidx::sidx;
+use crate::styp::styp;
+use crate::types::types;
+
+/// Parses a whole media segment buffer, from `styp` to the last `mdat`.
+pub fn parse(r: &mut BytesMut) -> Segment {
+    Segment::parse(r)
+}
+
+/// A media segment: an optional `styp`, its segment indexes and the movie
+/// fragments that follow them.
+#[derive(Default, PartialEq)]
+pub struct Segment {
+    pub styp: Option<styp>,
+    pub sidxs: Vec<sidx>,
+    /// other boxes in front of the first fragment, such as an `mdat` of
+    /// unfragmented media data, kept as is
+    pub ext: BytesMut,
+    pub fragments: Vec<Fragment>,
+    /// producer reference time after the last fragment
+    pub prft: Option<prft>,
+    /// event messages after the last fragment
+    pub emsgs: Vec<emsg>,
+}
+
+/// A `moof` with the payload of the `mdat` that follows it, and the
+/// producer reference time and event messages written in front of it.
+#[derive(PartialEq)]
+pub struct Fragment {
+    pub prft: Option<prft>,
+    pub emsgs: Vec<emsg>,
+    pub moof: moof,
+    /// other boxes between the `moof` and the `mdat`, kept as is
+    pub ext: BytesMut,
+    pub mdat: BytesMut,
+    /// boxes after the `mdat` up to the next fragment, such as another
+    /// `mdat`, kept as is
+    pub trailer: BytesMut,
+}
+
+impl Fragment {
+    pub fn new(moof: moof, mdat: BytesMut) -> Self {
+        Self {
+            prft: None,
+            emsgs: vec![],
+            moof,
+            ext: BytesMut::new(),
+            mdat,
+            trailer: BytesMut::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.mdat_offset() + self.mdat.len() + self.trailer.len()
+    }
+
+    // size of the boxes in front of the `moof`
+    fn moof_offset(&self) -> usize {
+        self.prft.as_ref().map_or(0, |it| 8 + it.len()) +
+            self.emsgs.iter().map(|it| 8 + it.len()).sum::<usize>()
+    }
+
+    // size of the boxes and header in front of the `mdat` payload
+    fn mdat_offset(&self) -> usize {
+        self.moof_offset() + 8 + self.moof.len() + self.ext.len() + object::box_len(self.mdat.len()) - self.mdat.len()
+    }
+}
+
+impl Segment {
+    /// Injects `event` in front of the `moof` with `sequence_number`, and
+    /// grows the matching reference of a single `sidx` indexing one
+    /// subsegment per fragment.
+    ///
+    /// Returns `false` when no such `moof` exists, or when the reference
+    /// would outgrow its 31-bit `referenced_size`.
+    pub fn insert_emsg(&mut self, sequence_number: u32, event: emsg) -> bool {
+        let count = self.fragments.len();
+        let Some(i) = self.fragments.iter().position(|it| sequence_number == it.moof.mfhd.sequence_number) else {
+            return false;
+        };
+
+        if let [index] = &mut self.sidxs[..] {
+            if count == index.references.len() {
+                let referenced_size = index.references[i].referenced_size as usize + object::box_len(event.len());
+                if 0x7FFFFFFF < referenced_size {
+                    return false;
+                }
+                index.references[i].referenced_size = referenced_size as u32;
+            }
+        }
+        self.fragments[i].emsgs.push(event);
+
+        true
+    }
+
+    /// Random access index of the segment, written at file offset `offset`.
+    ///
+    /// `trexs` are the track defaults from `mvex`.
+    pub fn mfra(&self, offset: u64, trexs: &[trex]) -> mfra {
+        let mut offset = offset + self.header_len();
+
+        mfra::build(trexs, self.fragments.iter().map(|it| {
+            let moof_offset = offset + it.moof_offset() as u64;
+            offset += it.len() as u64;
+
+            (moof_offset, &it.moof)
+        }))
+    }
+
+    /// Wall-clock time of each fragment of the track `track_id`, whose media
+    /// timescale is `timescale`, from its `tfdt` and the latest `prft` so far
+    /// referencing that track.
+    pub fn wall_clock_times(&self, track_id: u32, timescale: u32) -> Vec<Option<SystemTime>> {
+        let mut reference = None;
+
+        self.fragments.iter().map(|it| {
+            if let Some(v) = it.prft.as_ref().filter(|v| track_id == v.reference_track_id) {
+                reference = Some(v);
+            }
+
+            let tfdt = it.moof.trafs.iter()
+                .find(|v| track_id == v.tfhd.track_id)
+                .and_then(|v| v.tfdt.as_ref())?;
+
+            Some(reference?.wall_clock(tfdt, timescale))
+        }).collect()
+    }
+
+    /// Sample description index and data of every sample of the track
+    /// `track_id`, the segment being read from file offset `offset`.
+    ///
+    /// `trexs` are the track defaults from `mvex`. Sample data is located
+    /// from the `tfhd` base data offset, or the start of the `moof` or the
+    /// end of the previous track fragment's data, and must lie in the
+    /// `mdat` following the `moof`; returns `None` otherwise.
+    pub fn samples(&self, offset: u64, track_id: u32, trexs: &[trex]) -> Option<Vec<(u32, &[u8])>> {
+        let mut rst = vec![];
+        let mut offset = offset.checked_add(self.header_len())?;
+
+        for fragment in &self.fragments {
+            let moof_offset = offset.checked_add(fragment.moof_offset() as u64)?;
+            let mdat_offset = offset.checked_add(fragment.mdat_offset() as u64)?;
+            offset = offset.checked_add(fragment.len() as u64)?;
+
+            let mut end_of_data = moof_offset;
+            for traf in &fragment.moof.trafs {
+                let trex = trexs.iter().find(|it| traf.tfhd.track_id == it.track_id);
+                let samples = traf.samples(trex);
+                let mut samples = samples.iter();
+                let description_index = traf.tfhd.sample_description_index
+                    .unwrap_or_else(|| trex.map_or(1, |it| it.default_sample_description_index));
+                let base = match traf.tfhd.base_data_offset {
+                    Some(v) => { v }
+                    None if traf.tfhd.default_base_is_moof() => { moof_offset }
+                    None => { end_of_data }
+                };
+
+                let mut position = base;
+                for trun in &traf.truns {
+                    if let Some(v) = trun.data_offset {
+                        position = base.checked_add_signed(v as i32 as i64)?;
+                    }
+
+                    for (_, size, _, _) in samples.by_ref().take(trun.samples.len()) {
+                        let (start, end) = (position, position.checked_add(*size as u64)?);
+                        position = end;
+
+                        if track_id == traf.tfhd.track_id {
+                            let start = start.checked_sub(mdat_offset)?;
+                            let end = end.checked_sub(mdat_offset)?;
+                            if (fragment.mdat.len() as u64) < end {
+                                return None;
+                            }
+
+                            rst.push((description_index, &fragment.mdat[start as usize..end as usize]));
+                        }
+                    }
+                }
+                end_of_data = position;
+            }
+        }
+
+        Some(rst)
+    }
+
+    // size of the boxes in front of the first fragment
+    fn header_len(&self) -> u64 {
+        self.styp.as_ref().map_or(0, |it| 8 + it.len() as u64) +
+            self.sidxs.iter().map(|it| 8 + it.len() as u64).sum::<u64>() +
+            self.ext.len() as u64
+    }
+}
+
+impl Debug for Segment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(v) = &self.styp {
+            f.write_fmt(format_args!("0x{:08x?}: \"styp\"\n", styp::BOX_TYPE))?;
+            v.fmt(f)?;
+            f.write_fmt(format_args!("\n"))?;
+        }
+        for it in &self.sidxs {
+            f.write_fmt(format_args!("0x{:08x?}: \"sidx\"\n", sidx::BOX_TYPE))?;
+            it.fmt(f)?;
+            f.write_fmt(format_args!("\n"))?;
+        }
+        if !self.ext.is_empty() {
+            f.write_fmt(format_args!("ext: {:?} bytes\n", self.ext.len()))?;
+        }
+        for it in &self.fragments {
+            fmt_events(f, &it.prft, &it.emsgs)?;
+            f.write_fmt(format_args!("0x{:08x?}: \"moof\"\n", moof::BOX_TYPE))?;
+            it.moof.fmt(f)?;
+            if !it.ext.is_empty() {
+                f.write_fmt(format_args!("\next: {:?} bytes", it.ext.len()))?;
+            }
+            f.write_fmt(format_args!("\n0x{:08x?}: \"mdat\"\n", types::mdat))?;
+            f.write_fmt(format_args!("\tsize: {:?}\n", it.mdat.len()))?;
+            if !it.trailer.is_empty() {
+                f.write_fmt(format_args!("ext: {:?} bytes\n", it.trailer.len()))?;
+            }
+        }
+        fmt_events(f, &self.prft, &self.emsgs)
+    }
+}
+
+impl IO for Segment {
+    fn len(&self) -> usize {
+        let mut v = self.ext.len();
+
+        if let Some(it) = &self.styp {
+            v += 8 + it.len();
+        }
+        for it in &self.sidxs {
+            v += 8 + it.len();
+        }
+        for it in &self.fragments {
+            v += it.len();
+        }
+        if let Some(it) = &self.prft {
+            v += 8 + it.len();
+        }
+        for it in &self.emsgs {
+            v += 8 + it.len();
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self::default();
+        let mut prft = None;
+        let mut emsgs = vec![];
+        // whether the last moof got its mdat
+        let mut mdat = false;
+
+        while !r.is_empty() {
+            let mut b = Object::parse(r);
+
+            match b.box_type {
+                // styp: Segment Type
+                styp::BOX_TYPE => {
+                    rst.styp = Some(styp::parse(&mut b.payload));
+                }
+                // sidx: Segment Index
+                sidx::BOX_TYPE => {
+                    rst.sidxs.push(sidx::parse(&mut b.payload));
+                }
+                // prft: Producer Reference Time, kept for the next moof
+                prft::BOX_TYPE => {
+                    prft = Some(prft::parse(&mut b.payload));
+                }
+                // emsg: Event Message, kept for the next moof
+                emsg::BOX_TYPE => {
+                    emsgs.push(emsg::parse(&mut b.payload));
+                }
+                // moof: Movie Fragment
+                moof::BOX_TYPE => {
+                    let mut v = Fragment::new(moof::parse(&mut b.payload), BytesMut::new());
+                    v.prft = prft.take();
+                    v.emsgs = std::mem::take(&mut emsgs);
+                    rst.fragments.push(v);
+                    mdat = false;
+                }
+                // mdat: Media Data, the first one following a moof
+                types::mdat if !mdat && !rst.fragments.is_empty() => {
+                    if let Some(it) = rst.fragments.last_mut() {
+                        it.mdat = b.payload;
+                    }
+                    mdat = true;
+                }
+                _ => {
+                    match rst.fragments.last_mut() {
+                        Some(it) if mdat => { it.trailer.put(b.as_bytes()) }
+                        Some(it) => { it.ext.put(b.as_bytes()) }
+                        None => { rst.ext.put(b.as_bytes()) }
+                    }
+                }
+            }
+        }
+        rst.prft = prft;
+        rst.emsgs = emsgs;
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        if let Some(it) = &mut self.styp {
+            w.put(Object::new(styp::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        for it in self.sidxs.iter_mut() {
+            w.put(Object::new(sidx::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        w.put(self.ext.chunk());
+        for it in self.fragments.iter_mut() {
+            put_events(&mut w, &mut it.prft, &mut it.emsgs);
+            w.put(Object::new(moof::BOX_TYPE, it.moof.as_bytes()).as_bytes());
+            w.put(it.ext.chunk());
+            w.put(Object::new(types::mdat, it.mdat.clone()).as_bytes());
+            w.put(it.trailer.chunk());
+        }
+        put_events(&mut w, &mut self.prft, &mut self.emsgs);
+
+        w
+    }
+}
+
+fn fmt_events(f: &mut Formatter<'_>, prft: &Option<prft>, emsgs: &[emsg]) -> std::fmt::Result {
+    if let Some(v) = prft {
+        f.write_fmt(format_args!("0x{:08x?}: \"prft\"\n", prft::BOX_TYPE))?;
+        v.fmt(f)?;
+        f.write_fmt(format_args!("\n"))?;
+    }
+    for it in emsgs {
+        f.write_fmt(format_args!("0x{:08x?}: \"emsg\"\n", emsg::BOX_TYPE))?;
+        it.fmt(f)?;
+        f.write_fmt(format_args!("\n"))?;
+    }
+
+    Ok(())
+}
+
+fn put_events(w: &mut BytesMut, prft: &mut Option<prft>, emsgs: &mut [emsg]) {
+    if let Some(v) = prft {
+        w.put(Object::new(prft::BOX_TYPE, v.as_bytes()).as_bytes());
+    }
+    for it in emsgs.iter_mut() {
+        w.put(Object::new(emsg::BOX_TYPE, it.as_bytes()).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use bytes::BytesMut;
+
+    use crate::{IO, Object};
+    use crate::emsg::{emsg, EventPresentationTime};
+    use crate::moof::{moof, tfdt, traf, trun};
+    use crate::moov::trex;
+    use crate::prft::prft;
+    use crate::segment::{Fragment, Segment};
+    use crate::sidx::sidx;
+    use crate::styp::styp;
+    use crate::types::types;
+
+    #[test]
+    fn chk_segment() {
+        let mut b = Segment::default();
+
+        b.styp = Some(styp::new(0x6d736468, 0, vec![0x6d736468, 0x6d736978]));
+        for i in 0..2 {
+            b.fragments.push(Fragment::new({
+                let mut v = moof::default();
+
+                v.mfhd.sequence_number = 1 + i;
+                v.trafs.push({
+                    let mut v = traf::default();
+
+                    v.tfhd.track_id = 1;
+                    v.tfhd.default_sample_duration = Some(1024);
+                    v.tfdt = Some(tfdt::new(1024 * 4 * i as u64));
+                    v.truns.push({
+                        let mut v = trun::default();
+
+                        v.data_offset = Some(0);
+                        for n in 0..4 {
+                            v.samples.push((None, Some(100 + n), None, None));
+                        }
+
+                        v
+                    });
+
+                    v
+                });
+
+                v
+            }, BytesMut::from(&[0x5a_u8; 406][..])));
+        }
+        let time = UNIX_EPOCH + Duration::from_secs(1700000000);
+        b.fragments[0].prft = Some(prft::new(prft::MOOF_WRITTEN, 1, time, 0));
+        assert_eq!(
+            vec![Some(time), Some(time + Duration::from_nanos(85333333))],
+            b.wall_clock_times(1, 48000)
+        );
+
+        b.sidxs.push(sidx::build(1, 48000, None, b.fragments.iter().map(|it| (&it.moof, it.mdat.len()))).unwrap());
+
+        let mut event = emsg::default();
+        event.scheme_id_uri = "urn:scte:scte35:2013:bin".to_owned();
+        event.timescale = 48000;
+        event.presentation_time = EventPresentationTime::Absolute(4096);
+        event.id = 1;
+
+        assert!(!b.insert_emsg(3, event.clone()));
+        assert!(b.insert_emsg(2, event.clone()));
+        assert_eq!(
+            b.fragments[1].len(),
+            b.sidxs[0].references[1].referenced_size as usize
+        );
+
+        let referenced_size = b.sidxs[0].references[0].referenced_size;
+        b.sidxs[0].references[0].referenced_size = 0x7FFFFFF0;
+        assert!(!b.insert_emsg(1, event.clone()));
+        assert!(b.fragments[0].emsgs.is_empty());
+        b.sidxs[0].references[0].referenced_size = referenced_size;
+
+        // boxes around the fragments, kept in place
+        let free = Object::new(types::free, BytesMut::new()).as_bytes();
+        let mdat = Object::new(types::mdat, BytesMut::from(&[0xa5_u8; 12][..])).as_bytes();
+        b.ext.extend_from_slice(&mdat);
+        b.ext.extend_from_slice(&free);
+        b.fragments[0].ext.extend_from_slice(&free);
+        b.fragments[1].trailer.extend_from_slice(&mdat);
+        b.emsgs.push(event.clone());
+
+        let mut w = b.as_bytes();
+
+        let index = b.mfra(0, &[]);
+        assert_eq!(2, index.tfras[0].entries.len());
+        for it in &index.tfras[0].entries {
+            assert_eq!(moof::BOX_TYPE.to_be_bytes(), w[it.moof_offset as usize + 4..it.moof_offset as usize + 8]);
+        }
+
+        assert_eq!(b.len(), w.len());
+        assert_eq!(b, Segment::parse(&mut w));
+    }
+
+    #[test]
+    fn chk_samples() {
+        let trexs = [1, 2].map(|i| {
+            let mut v = trex::default();
+
+            v.track_id = i;
+            v.default_sample_description_index = i;
+            v.default_sample_size = 3 * i;
+
+            v
+        });
+
+        let mut v = moof::default();
+        for i in [2, 1] {
+            v.trafs.push({
+                let mut v = traf::default();
+
+                v.tfhd.track_id = i;
+                v.truns.push({
+                    let mut v = trun::default();
+
+                    v.data_offset = Some(0);
+                    v.samples.push((None, None, None, None));
+                    v.samples.push((None, None, None, None));
+
+                    v
+                });
+
+                v
+            });
+        }
+        v.trafs[1].tfhd.base_data_offset = Some(0);
+        v.trafs[1].truns[0].data_offset = None;
+
+        let mdat_offset = 1000 + 8 + v.len() as u64 + 8;
+        v.trafs[0].truns[0].data_offset = Some(8 + v.len() as u32 + 8);
+        v.trafs[1].tfhd.base_data_offset = Some(mdat_offset + 12);
+
+        let mut b = Segment::default();
+        b.fragments.push(Fragment::new(v, BytesMut::from(&[2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1][..])));
+
+        assert_eq!(Some(vec![(1, &[1, 1, 1][..]), (1, &[1, 1, 1][..])]), b.samples(1000, 1, &trexs));
+        assert_eq!(Some(vec![(2, &[2; 6][..]), (2, &[2; 6][..])]), b.samples(1000, 2, &trexs));
+        // read from another offset, the base data offset misses the mdat
+        assert_eq!(None, b.samples(2000, 1, &trexs));
+
+        // a box between the moof and the mdat
+        b.fragments[0].ext = Object::new(types::free, BytesMut::new()).as_bytes();
+        b.fragments[0].moof.trafs[0].truns[0].data_offset = Some(8 + b.fragments[0].moof.len() as u32 + 8 + 8);
+        b.fragments[0].moof.trafs[1].tfhd.base_data_offset = Some(mdat_offset + 8 + 12);
+        assert_eq!(Some(vec![(1, &[1, 1, 1][..]), (1, &[1, 1, 1][..])]), b.samples(1000, 1, &trexs));
+        assert_eq!(Some(vec![(2, &[2; 6][..]), (2, &[2; 6][..])]), b.samples(1000, 2, &trexs));
+
+        b.fragments[0].moof.trafs[0].truns[0].data_offset = Some(-4_i32 as u32);
+        assert_eq!(None, b.samples(0, 2, &trexs));
+    }
+}
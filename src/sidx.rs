@@ -0,0 +1,322 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::Range;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{FullBox, IO};
+use crate::moof::moof;
+use crate::object;
+use crate::moov::trex;
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> sidx {
+    sidx::parse(r)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct sidx {
+    base: FullBox,
+
+    pub reference_id: u32,
+    pub timescale: u32,
+    pub earliest_presentation_time: u64,
+    pub first_offset: u64,
+    pub references: Vec<SegmentReference>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentReference {
+    /// `true` when the reference points to another `sidx` instead of media
+    pub reference_type: bool,
+    pub referenced_size: u32,
+    pub subsegment_duration: u32,
+    pub starts_with_sap: bool,
+    pub sap_type: u8,
+    pub sap_delta_time: u32,
+}
+
+impl sidx {
+    pub const BOX_TYPE: u32 = types::sidx;
+
+    /// Indexes `fragments`, each given as a `moof` and the payload size of
+    /// the `mdat` following it, as one subsegment per fragment of the track
+    /// `reference_id`.
+    ///
+    /// `timescale` is the media timescale of that track and `trex` its
+    /// defaults from `mvex`, if any. Returns `None` when a fragment is too
+    /// large for the 31-bit `referenced_size`.
+    pub fn build<'a, I>(reference_id: u32, timescale: u32, trex: Option<&trex>, fragments: I) -> Option<Self>
+        where I: IntoIterator<Item = (&'a moof, usize)> {
+        let mut rst = Self {
+            reference_id,
+            timescale,
+            ..Self::default()
+        };
+
+        let mut earliest_presentation_time = None;
+        for (moof, mdat_size) in fragments {
+            let referenced_size = object::box_len(moof.len()) + object::box_len(mdat_size);
+            if 0x7FFFFFFF < referenced_size {
+                return None;
+            }
+
+            let mut reference = SegmentReference {
+                reference_type: false,
+                referenced_size: referenced_size as u32,
+                subsegment_duration: 0,
+                starts_with_sap: false,
+                sap_type: 0,
+                sap_delta_time: 0,
+            };
+
+            if let Some(traf) = moof.trafs.iter().find(|it| reference_id == it.tfhd.track_id) {
+                let samples = traf.samples(trex);
+
+                reference.subsegment_duration = samples.iter().map(|it| it.0).sum();
+                if let Some((_, _, flags, _)) = samples.first() {
+                    // sample_is_non_sync_sample
+                    if 0 == (0x00010000 & flags) {
+                        reference.starts_with_sap = true;
+                        reference.sap_type = 1;
+                    }
+                }
+                if earliest_presentation_time.is_none() {
+                    earliest_presentation_time = traf.earliest_presentation_time(trex);
+                }
+            }
+
+            rst.references.push(reference);
+        }
+        rst.earliest_presentation_time = earliest_presentation_time.unwrap_or(0);
+
+        Some(rst)
+    }
+
+    /// Byte range of the subsegment presented at `time`, in `timescale`
+    /// units.
+    ///
+    /// `anchor` is the offset of the first byte after this `sidx` box, that
+    /// `first_offset` counts from.
+    pub fn lookup(&self, time: u64, anchor: u64) -> Option<Range<u64>> {
+        if time < self.earliest_presentation_time {
+            return None;
+        }
+
+        let mut start_time = self.earliest_presentation_time;
+        let mut offset = anchor + self.first_offset;
+        for it in &self.references {
+            let end_time = start_time + it.subsegment_duration as u64;
+            let end = offset + it.referenced_size as u64;
+
+            if time < end_time {
+                return Some(offset..end);
+            }
+
+            start_time = end_time;
+            offset = end;
+        }
+
+        None
+    }
+}
+
+impl Default for sidx {
+    //! extends FullBox(‘sidx’, version, 0) {
+    //!     unsigned int(32) reference_ID;
+    //!     unsigned int(32) timescale;
+    //!     if (version==0) {
+    //!         unsigned int(32) earliest_presentation_time;
+    //!         unsigned int(32) first_offset;
+    //!     }
+    //!     else {
+    //!         unsigned int(64) earliest_presentation_time;
+    //!         unsigned int(64) first_offset;
+    //!     }
+    //!     unsigned int(16) reserved = 0;
+    //!     unsigned int(16) reference_count;
+    //!     for(i=1; i <= reference_count; i++)
+    //!     {
+    //!         bit (1) reference_type;
+    //!         unsigned int(31) referenced_size;
+    //!         unsigned int(32) subsegment_duration;
+    //!         bit(1) starts_with_SAP;
+    //!         unsigned int(3) SAP_type;
+    //!         unsigned int(28) SAP_delta_time;
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self {
+            base: FullBox::new(0, 0),
+            reference_id: 0,
+            timescale: 0,
+            earliest_presentation_time: 0,
+            first_offset: 0,
+            references: vec![],
+        }
+    }
+}
+
+impl Debug for sidx {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\treference_id: {:?}", self.reference_id))?;
+        f.write_fmt(format_args!("\n\ttimescale: {:?}", self.timescale))?;
+        f.write_fmt(format_args!("\n\tearliest_presentation_time: {:?}", self.earliest_presentation_time))?;
+        f.write_fmt(format_args!("\n\tfirst_offset: {:?}", self.first_offset))?;
+        f.write_fmt(format_args!("\n\treference_count: {:?}", self.references.len()))?;
+        for it in &self.references {
+            f.write_fmt(format_args!("\n\t\t{{"))?;
+            f.write_fmt(format_args!("\n\t\t\treference_type: {:?}", it.reference_type as u8))?;
+            f.write_fmt(format_args!("\n\t\t\treferenced_size: {:?}", it.referenced_size))?;
+            f.write_fmt(format_args!("\n\t\t\tsubsegment_duration: {:?}", it.subsegment_duration))?;
+            f.write_fmt(format_args!("\n\t\t\tstarts_with_SAP: {:?}", it.starts_with_sap as u8))?;
+            f.write_fmt(format_args!("\n\t\t\tSAP_type: {:?}", it.sap_type))?;
+            f.write_fmt(format_args!("\n\t\t\tSAP_delta_time: {:?}", it.sap_delta_time))?;
+            f.write_fmt(format_args!("\n\t\t}}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for sidx {
+    fn len(&self) -> usize {
+        let mut v = self.base.len() + 20 + 12 * self.references.len();
+
+        if (u32::MAX as u64) < self.earliest_presentation_time ||
+            (u32::MAX as u64) < self.first_offset {
+            v += 8;
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+        let reference_id = r.get_u32();
+        let timescale = r.get_u32();
+        let (earliest_presentation_time, first_offset) = if 1 == base.version {
+            (r.get_u64(), r.get_u64())
+        } else {
+            (r.get_u32() as u64, r.get_u32() as u64)
+        };
+        let _ = r.get_u16();
+        let reference_count = r.get_u16();
+
+        let mut references = Vec::with_capacity(reference_count as usize);
+        for _ in 0..reference_count {
+            let t = r.get_u32();
+            let subsegment_duration = r.get_u32();
+            let s = r.get_u32();
+
+            references.push(SegmentReference {
+                reference_type: 0 != (0x80000000 & t),
+                referenced_size: 0x7FFFFFFF & t,
+                subsegment_duration,
+                starts_with_sap: 0 != (0x80000000 & s),
+                sap_type: (0b111 & (s >> 28)) as u8,
+                sap_delta_time: 0x0FFFFFFF & s,
+            });
+        }
+
+        Self {
+            base,
+            reference_id,
+            timescale,
+            earliest_presentation_time,
+            first_offset,
+            references,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        self.base.version = if (u32::MAX as u64) < self.earliest_presentation_time ||
+            (u32::MAX as u64) < self.first_offset { 1 } else { 0 };
+        w.put(self.base.as_bytes());
+
+        w.put_u32(self.reference_id);
+        w.put_u32(self.timescale);
+        if 1 == self.base.version {
+            w.put_u64(self.earliest_presentation_time);
+            w.put_u64(self.first_offset);
+        } else {
+            w.put_u32(self.earliest_presentation_time as u32);
+            w.put_u32(self.first_offset as u32);
+        }
+        w.put_u16(0);
+        w.put_u16(self.references.len() as u16);
+        for it in &self.references {
+            w.put_u32((it.reference_type as u32) << 31 | (0x7FFFFFFF & it.referenced_size));
+            w.put_u32(it.subsegment_duration);
+            w.put_u32(
+                (it.starts_with_sap as u32) << 31 |
+                    (0b111 & it.sap_type as u32) << 28 |
+                    (0x0FFFFFFF & it.sap_delta_time)
+            );
+        }
+
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{IO, Object};
+    use crate::moof::{moof, tfdt, traf, trun};
+    use crate::sidx::sidx;
+
+    #[test]
+    fn chk_sidx() {
+        let mut fragments = vec![];
+        for i in 0..3_u64 {
+            let mut v = moof::default();
+
+            v.mfhd.sequence_number = 1 + i as u32;
+            v.trafs.push({
+                let mut v = traf::default();
+
+                v.tfhd.track_id = 1;
+                v.tfhd.default_sample_duration = Some(3000);
+                v.tfhd.default_sample_flags = Some(0x00010000);
+                v.tfdt = Some(tfdt::new(90000 * 2 * i));
+                v.truns.push({
+                    let mut v = trun::default();
+
+                    v.data_offset = Some(0);
+                    v.first_sample_flags = Some(0x02000000);
+                    for _ in 0..60 {
+                        v.samples.push((None, Some(1000), None, None));
+                    }
+
+                    v
+                });
+
+                v
+            });
+
+            fragments.push((v, BytesMut::from(&vec![0_u8; 60000][..])));
+        }
+
+        let mut b = sidx::build(1, 90000, None, fragments.iter().map(|(moof, mdat)| (moof, mdat.len()))).unwrap();
+        let mut obj = Object::parse(&mut Object::new(sidx::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(sidx::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, sidx::parse(&mut obj.payload));
+
+        let size = 8 + fragments[0].0.len() as u64 + 8 + 60000;
+        assert_eq!(3, b.references.len());
+        assert_eq!(180000, b.references[1].subsegment_duration);
+        assert!(b.references[2].starts_with_sap);
+        assert_eq!(Some(1000 + size..1000 + 2 * size), b.lookup(270000, 1000));
+        assert_eq!(None, b.lookup(540000, 1000));
+
+        // above 2 GiB, and an mdat above 4 GiB with its 64-bit size
+        assert!(sidx::build(1, 90000, None, [(&fragments[0].0, 0x7FFFFFFF)]).is_none());
+        assert!(sidx::build(1, 90000, None, [(&fragments[0].0, 1 << 32)]).is_none());
+    }
+}
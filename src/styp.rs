@@ -0,0 +1,82 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+
+use bytes::BytesMut;
+
+use crate::ftyp::ftyp;
+use crate::IO;
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> styp {
+    styp::parse(r)
+}
+
+/// Segment type box, laid out exactly like `ftyp`.
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct styp(pub ftyp);
+
+impl styp {
+    pub const BOX_TYPE: u32 = types::styp;
+
+    pub fn new(major_brand: u32, minor_version: u32, compatible_brands: Vec<u32>) -> Self {
+        Self(ftyp {
+            major_brand,
+            minor_version,
+            compatible_brands,
+        })
+    }
+}
+
+impl Deref for styp {
+    type Target = ftyp;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for styp {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Debug for styp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl IO for styp {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self(ftyp::parse(r))
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        self.0.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IO, Object};
+    use crate::styp::styp;
+
+    #[test]
+    fn chk_styp() {
+        let mut b = styp::new(0x6d736468, 0, vec![
+            0x6d736468,
+            0x6d736978,
+        ]);
+        let mut obj = Object::parse(&mut Object::new(styp::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(styp::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, styp::parse(&mut obj.payload));
+    }
+}
@@ -24,6 +24,7 @@ pub mod types {
     pub const hdlr: u32 = 0x68646c72;
     pub const vide: u32 = 0x76696465;
     pub const soun: u32 = 0x736f756e;
+    pub const mhlr: u32 = 0x6d686c72;
     pub const minf: u32 = 0x6d696e66;
     pub const stbl: u32 = 0x7374626c;
     pub const stts: u32 = 0x73747473;
@@ -35,8 +36,13 @@ pub mod types {
     pub const stsz: u32 = 0x7374737a;
     pub const stsc: u32 = 0x73747363;
     pub const stco: u32 = 0x7374636f;
+    pub const ctts: u32 = 0x63747473;
+    pub const stss: u32 = 0x73747373;
+    pub const co64: u32 = 0x636f3634;
     pub const mvex: u32 = 0x6d766578;
     pub const trex: u32 = 0x74726578;
+    pub const mehd: u32 = 0x6d656864;
+    pub const trep: u32 = 0x74726570;
     pub const moof: u32 = 0x6d6f6f66;
     pub const mfhd: u32 = 0x6d666864;
     pub const traf: u32 = 0x74726166;
@@ -51,6 +57,59 @@ pub mod types {
     pub const btrt: u32 = 0x62747274;
     pub const mp4a: u32 = 0x6d703461;
     pub const avcC: u32 = 0x61766343;
+    pub const hvc1: u32 = 0x68766331;
+    pub const hvcC: u32 = 0x68766343;
     pub const colr: u32 = 0x636F6C72;
     pub const esds: u32 = 0x65736473;
+    pub const Opus: u32 = 0x4f707573;
+    pub const dOps: u32 = 0x644f7073;
+    pub const fLaC: u32 = 0x664c6143;
+    pub const dfLa: u32 = 0x64664c61;
+    pub const ac_3: u32 = 0x61632d33;
+    pub const dac3: u32 = 0x64616333;
+    pub const ec_3: u32 = 0x65632d33;
+    pub const dec3: u32 = 0x64656333;
+    pub const nclx: u32 = 0x6e636c78;
+    pub const nclc: u32 = 0x6e636c63;
+    pub const rICC: u32 = 0x72494343;
+    pub const prof: u32 = 0x70726f66;
+    pub const mdcv: u32 = 0x6d646376;
+    pub const clli: u32 = 0x636c6c69;
+    pub const pasp: u32 = 0x70617370;
+    pub const clap: u32 = 0x636c6170;
+    pub const sidx: u32 = 0x73696478;
+    pub const styp: u32 = 0x73747970;
+    pub const emsg: u32 = 0x656d7367;
+    pub const prft: u32 = 0x70726674;
+    pub const mfra: u32 = 0x6d667261;
+    pub const tfra: u32 = 0x74667261;
+    pub const mfro: u32 = 0x6d66726f;
+    pub const free: u32 = 0x66726565;
+    pub const skip: u32 = 0x736b6970;
+    pub const uuid: u32 = 0x75756964;
+    pub const senc: u32 = 0x73656e63;
+    pub const udta: u32 = 0x75647461;
+    pub const meta: u32 = 0x6d657461;
+    pub const ilst: u32 = 0x696c7374;
+    pub const data: u32 = 0x64617461;
+    pub const mean: u32 = 0x6d65616e;
+    pub const name: u32 = 0x6e616d65;
+    pub const mdir: u32 = 0x6d646972;
+    pub const covr: u32 = 0x636f7672;
+    pub const trkn: u32 = 0x74726b6e;
+    pub const _nam: u32 = 0xa96e616d;
+    pub const _ART: u32 = 0xa9415254;
+    pub const _day: u32 = 0xa9646179;
+    pub const _too: u32 = 0xa9746f6f;
+    pub const ____: u32 = 0x2d2d2d2d;
+    pub const chpl: u32 = 0x6368706c;
+    pub const tref: u32 = 0x74726566;
+    pub const chap: u32 = 0x63686170;
+    pub const text: u32 = 0x74657874;
+    pub const encd: u32 = 0x656e6364;
+    pub const hint: u32 = 0x68696e74;
+    pub const cdsc: u32 = 0x63647363;
+    pub const sync: u32 = 0x73796e63;
+    pub const vdep: u32 = 0x76646570;
+    pub const subt: u32 = 0x73756274;
 }
@@ -0,0 +1,768 @@
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{FullBox, IO, Object};
+use crate::moov::hdlr;
+use crate::types::types;
+
+pub fn parse(r: &mut BytesMut) -> udta {
+    udta::parse(r)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Default, PartialEq)]
+pub struct udta {
+    pub meta: Option<meta>,
+    /// Nero chapters
+    pub chpl: Option<chpl>,
+    /// other child boxes, kept as is
+    pub ext: BytesMut,
+}
+
+impl udta {
+    pub const BOX_TYPE: u32 = types::udta;
+}
+
+impl Debug for udta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(it) = &self.meta {
+            f.write_fmt(format_args!("\t\t0x{:08x?}: \"meta\"\n", meta::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
+        if let Some(it) = &self.chpl {
+            f.write_fmt(format_args!("\n\t\t0x{:08x?}: \"chpl\"\n", chpl::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
+        if !self.ext.is_empty() {
+            f.write_fmt(format_args!("\n\t\text: {:?} bytes", self.ext.len()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for udta {
+    fn len(&self) -> usize {
+        let mut v = self.ext.len();
+
+        if let Some(it) = &self.meta {
+            v += 8 + it.len();
+        }
+        if let Some(it) = &self.chpl {
+            v += 8 + it.len();
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self::default();
+
+        // QuickTime may end the list with a 32-bit zero
+        while 8 <= r.len() {
+            let mut b = Object::parse(r);
+
+            match b.box_type {
+                // meta: Meta Box
+                meta::BOX_TYPE => {
+                    rst.meta = Some(meta::parse(&mut b.payload));
+                }
+                // chpl: Nero Chapter List
+                chpl::BOX_TYPE => {
+                    rst.chpl = Some(chpl::parse(&mut b.payload));
+                }
+                _ => {
+                    rst.ext.put(b.as_bytes());
+                }
+            }
+        }
+        rst.ext.put(r.split_to(r.len()));
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        if let Some(it) = &mut self.meta {
+            w.put(Object::new(meta::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        if let Some(it) = &mut self.chpl {
+            w.put(Object::new(chpl::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        w.put(self.ext.chunk());
+
+        w
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct meta {
+    /// `None` for the QuickTime layout, which has no version and flags
+    base: Option<FullBox>,
+
+    pub hdlr: hdlr,
+    pub ilst: Option<ilst>,
+    /// other child boxes, kept as is
+    pub ext: BytesMut,
+}
+
+impl meta {
+    pub const BOX_TYPE: u32 = types::meta;
+
+    /// Metadata with an empty item list, as written by iTunes.
+    pub fn mdir() -> Self {
+        let mut v = Self::default();
+
+        v.hdlr.handler_type = types::mdir;
+        v.ilst = Some(Default::default());
+
+        v
+    }
+}
+
+impl Default for meta {
+    //! extends FullBox(‘meta’, version = 0, 0) {
+    //!     HandlerBox(handler_type) theHandler;
+    //!     PrimaryItemBox primary_resource; // optional
+    //!     DataInformationBox file_locations; // optional
+    //!     ItemLocationBox item_locations; // optional
+    //!     ItemProtectionBox protections; // optional
+    //!     ItemInfoBox item_infos; // optional
+    //!     IPMPControlBox IPMP_control; // optional
+    //!     ItemReferenceBox item_refs; // optional
+    //!     ItemDataBox item_data; // optional
+    //!     Box other_boxes[]; // optional
+    //! }
+    fn default() -> Self {
+        Self {
+            base: Some(FullBox::new(0, 0)),
+            hdlr: Default::default(),
+            ilst: None,
+            ext: BytesMut::new(),
+        }
+    }
+}
+
+impl Debug for meta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\t0x{:08x?}: \"hdlr\"\n", hdlr::BOX_TYPE))?;
+        self.hdlr.fmt(f)?;
+        if let Some(it) = &self.ilst {
+            f.write_fmt(format_args!("\n\t\t\t0x{:08x?}: \"ilst\"\n", ilst::BOX_TYPE))?;
+            it.fmt(f)?;
+        }
+        if !self.ext.is_empty() {
+            f.write_fmt(format_args!("\n\t\t\text: {:?} bytes", self.ext.len()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for meta {
+    fn len(&self) -> usize {
+        let mut v = 8 + self.hdlr.len() + self.ext.len();
+
+        if let Some(it) = &self.base {
+            v += it.len();
+        }
+        if let Some(it) = &self.ilst {
+            v += 8 + it.len();
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = if 8 <= r.len() && types::hdlr.to_be_bytes() == r[4..8] {
+            None
+        } else {
+            Some(FullBox::parse(r))
+        };
+        let mut rst = Self {
+            base,
+            ..Default::default()
+        };
+
+        while 8 <= r.len() {
+            let mut b = Object::parse(r);
+
+            match b.box_type {
+                // hdlr: Handler Reference
+                hdlr::BOX_TYPE => {
+                    rst.hdlr = hdlr::parse(&mut b.payload);
+                }
+                // ilst: Metadata Item List
+                ilst::BOX_TYPE => {
+                    rst.ilst = Some(ilst::parse(&mut b.payload));
+                }
+                _ => {
+                    rst.ext.put(b.as_bytes());
+                }
+            }
+        }
+        rst.ext.put(r.split_to(r.len()));
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        if let Some(it) = &mut self.base {
+            w.put(it.as_bytes());
+        }
+        w.put(Object::new(hdlr::BOX_TYPE, self.hdlr.as_bytes()).as_bytes());
+        if let Some(it) = &mut self.ilst {
+            w.put(Object::new(ilst::BOX_TYPE, it.as_bytes()).as_bytes());
+        }
+        w.put(self.ext.chunk());
+
+        w
+    }
+}
+
+/// iTunes-style metadata item list.
+#[allow(non_camel_case_types)]
+#[derive(Default, PartialEq)]
+pub struct ilst {
+    pub items: Vec<MetadataItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetadataItem {
+    /// key of the item, such as `©nam`, `covr` or `----`
+    pub item_type: u32,
+    pub value: MetadataValue,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    /// UTF-8 text, such as `©nam`, `©ART` or `©day`
+    Text(String),
+    /// cover art of `covr`
+    Images(Vec<Image>),
+    /// `trkn`
+    TrackNumber {
+        number: u16,
+        total: u16,
+    },
+    /// freeform `----` item, keyed by `mean` and `name`
+    Freeform {
+        mean: String,
+        name: String,
+        data: Vec<data>,
+    },
+    /// any other value, kept as its `data` boxes
+    Data(Vec<data>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Image {
+    Jpeg(BytesMut),
+    Png(BytesMut),
+    Bmp(BytesMut),
+}
+
+impl ilst {
+    pub const BOX_TYPE: u32 = types::ilst;
+
+    /// Value of the first `item_type` item.
+    pub fn get(&self, item_type: u32) -> Option<&MetadataValue> {
+        self.items.iter().find(|it| item_type == it.item_type).map(|it| &it.value)
+    }
+
+    /// Sets `item_type` to `value`, replacing the items with that key, or
+    /// for freeform values the one with the same `mean` and `name`.
+    pub fn set(&mut self, item_type: u32, value: MetadataValue) {
+        let position = match &value {
+            MetadataValue::Freeform { mean, name, .. } => {
+                self.items.iter().position(|it| match &it.value {
+                    MetadataValue::Freeform { mean: m, name: n, .. } => { mean == m && name == n }
+                    _ => { false }
+                })
+            }
+            _ => {
+                let position = self.items.iter().position(|it| item_type == it.item_type);
+                if let Some(n) = position {
+                    let mut i = 0;
+                    self.items.retain(|it| {
+                        i += 1;
+                        i <= n + 1 || item_type != it.item_type
+                    });
+                }
+
+                position
+            }
+        };
+
+        let item = MetadataItem {
+            item_type,
+            value,
+        };
+        match position {
+            Some(n) => { self.items[n] = item }
+            None => { self.items.push(item) }
+        }
+    }
+
+    /// Removes every `item_type` item.
+    pub fn remove(&mut self, item_type: u32) {
+        self.items.retain(|it| item_type != it.item_type);
+    }
+
+    pub fn text(&self, item_type: u32) -> Option<&str> {
+        match self.get(item_type)? {
+            MetadataValue::Text(v) => { Some(v) }
+            _ => { None }
+        }
+    }
+
+    pub fn set_text(&mut self, item_type: u32, value: &str) {
+        self.set(item_type, MetadataValue::Text(value.to_owned()));
+    }
+
+    /// Track number and total count of tracks.
+    pub fn track_number(&self) -> Option<(u16, u16)> {
+        match self.get(types::trkn)? {
+            MetadataValue::TrackNumber { number, total } => { Some((*number, *total)) }
+            _ => { None }
+        }
+    }
+
+    pub fn images(&self) -> &[Image] {
+        match self.get(types::covr) {
+            Some(MetadataValue::Images(v)) => { v }
+            _ => { &[] }
+        }
+    }
+
+    /// Data of the freeform item with `mean` and `name`.
+    pub fn freeform(&self, mean: &str, name: &str) -> Option<&[data]> {
+        self.items.iter().find_map(|it| match &it.value {
+            MetadataValue::Freeform { mean: m, name: n, data } if mean == m && name == n => { Some(&data[..]) }
+            _ => { None }
+        })
+    }
+
+    /// Sets the freeform item with `mean` and `name` to the text `value`.
+    pub fn set_freeform(&mut self, mean: &str, name: &str, value: &str) {
+        self.set(types::____, MetadataValue::Freeform {
+            mean: mean.to_owned(),
+            name: name.to_owned(),
+            data: vec![data::text(value)],
+        });
+    }
+}
+
+impl Debug for ilst {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, it) in self.items.iter().enumerate() {
+            if 0 < i {
+                f.write_fmt(format_args!("\n"))?;
+            }
+            let key = it.item_type.to_be_bytes();
+            let key = key.iter().map(|c| if 0xA9 == *c { '©' } else { *c as char }).collect::<String>();
+            match &it.value {
+                MetadataValue::Text(v) => {
+                    f.write_fmt(format_args!("\t\t\t\t{:}: {:?}", key, v))?;
+                }
+                MetadataValue::Images(v) => {
+                    f.write_fmt(format_args!("\t\t\t\t{:}: {:?} images", key, v.len()))?;
+                }
+                MetadataValue::TrackNumber { number, total } => {
+                    f.write_fmt(format_args!("\t\t\t\t{:}: {:?}/{:?}", key, number, total))?;
+                }
+                MetadataValue::Freeform { mean, name, data } => {
+                    f.write_fmt(format_args!("\t\t\t\t{:}: {:?}:{:?}: {:?} data", key, mean, name, data.len()))?;
+                }
+                MetadataValue::Data(v) => {
+                    f.write_fmt(format_args!("\t\t\t\t{:}: {:?} data", key, v.len()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IO for ilst {
+    fn len(&self) -> usize {
+        self.items.iter().map(|it| 8 + item_len(&it.value)).sum()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let mut rst = Self::default();
+
+        while 8 <= r.len() {
+            let mut b = Object::parse(r);
+
+            rst.items.push(MetadataItem {
+                item_type: b.box_type,
+                value: parse_item(b.box_type, &mut b.payload),
+            });
+        }
+
+        rst
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        for it in &self.items {
+            w.put(Object::new(it.item_type, item_as_bytes(&it.value)).as_bytes());
+        }
+
+        w
+    }
+}
+
+fn parse_item(item_type: u32, r: &mut BytesMut) -> MetadataValue {
+    let mut mean = None;
+    let mut name = None;
+    let mut values = vec![];
+
+    while 8 <= r.len() {
+        let mut b = Object::parse(r);
+
+        match b.box_type {
+            types::mean => {
+                let _ = FullBox::parse(&mut b.payload);
+                mean = Some(String::from_utf8_lossy(&b.payload).into_owned());
+            }
+            types::name => {
+                let _ = FullBox::parse(&mut b.payload);
+                name = Some(String::from_utf8_lossy(&b.payload).into_owned());
+            }
+            data::BOX_TYPE => {
+                values.push(data::parse(&mut b.payload));
+            }
+            _ => {}
+        }
+    }
+
+    if let (types::____, Some(mean), Some(name)) = (item_type, &mean, &name) {
+        return MetadataValue::Freeform {
+            mean: mean.clone(),
+            name: name.clone(),
+            data: values,
+        };
+    }
+
+    match (item_type, &values[..]) {
+        (_, [v]) if data::UTF8 == v.data_type => {
+            MetadataValue::Text(String::from_utf8_lossy(&v.value).into_owned())
+        }
+        (types::trkn, [v]) if 6 <= v.value.len() => {
+            MetadataValue::TrackNumber {
+                number: u16::from_be_bytes([v.value[2], v.value[3]]),
+                total: u16::from_be_bytes([v.value[4], v.value[5]]),
+            }
+        }
+        (types::covr, _) if values.iter().all(|it| [data::JPEG, data::PNG, data::BMP].contains(&it.data_type)) => {
+            MetadataValue::Images(values.into_iter().map(|it| match it.data_type {
+                data::JPEG => { Image::Jpeg(it.value) }
+                data::PNG => { Image::Png(it.value) }
+                _ => { Image::Bmp(it.value) }
+            }).collect())
+        }
+        _ => { MetadataValue::Data(values) }
+    }
+}
+
+fn item_data(value: &MetadataValue) -> Vec<data> {
+    match value {
+        MetadataValue::Text(v) => { vec![data::text(v)] }
+        MetadataValue::Images(v) => {
+            v.iter().map(|it| {
+                let (data_type, value) = match it {
+                    Image::Jpeg(v) => { (data::JPEG, v) }
+                    Image::Png(v) => { (data::PNG, v) }
+                    Image::Bmp(v) => { (data::BMP, v) }
+                };
+
+                data {
+                    data_type,
+                    locale: 0,
+                    value: value.clone(),
+                }
+            }).collect()
+        }
+        MetadataValue::TrackNumber { number, total } => {
+            let mut value = BytesMut::new();
+            value.put_u16(0);
+            value.put_u16(*number);
+            value.put_u16(*total);
+            value.put_u16(0);
+
+            vec![data {
+                data_type: data::IMPLICIT,
+                locale: 0,
+                value,
+            }]
+        }
+        MetadataValue::Freeform { data, .. } => { data.clone() }
+        MetadataValue::Data(v) => { v.clone() }
+    }
+}
+
+fn item_len(value: &MetadataValue) -> usize {
+    let mut v = item_data(value).iter().map(|it| 8 + it.len()).sum();
+
+    if let MetadataValue::Freeform { mean, name, .. } = value {
+        v += 12 + mean.len() + 12 + name.len();
+    }
+
+    v
+}
+
+fn item_as_bytes(value: &MetadataValue) -> BytesMut {
+    let mut w = BytesMut::new();
+
+    if let MetadataValue::Freeform { mean, name, .. } = value {
+        for (box_type, v) in [(types::mean, mean), (types::name, name)] {
+            let mut payload = FullBox::new(0, 0).as_bytes();
+            payload.put_slice(v.as_bytes());
+
+            w.put(Object::new(box_type, payload).as_bytes());
+        }
+    }
+    for mut it in item_data(value) {
+        w.put(Object::new(data::BOX_TYPE, it.as_bytes()).as_bytes());
+    }
+
+    w
+}
+
+/// Value of a metadata item, with its well-known type.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct data {
+    pub data_type: u32,
+    pub locale: u32,
+    pub value: BytesMut,
+}
+
+impl data {
+    pub const BOX_TYPE: u32 = types::data;
+
+    /// well-known type: the type is implied by the item key
+    pub const IMPLICIT: u32 = 0;
+    pub const UTF8: u32 = 1;
+    pub const JPEG: u32 = 13;
+    pub const PNG: u32 = 14;
+    pub const BE_SIGNED_INTEGER: u32 = 21;
+    pub const BMP: u32 = 27;
+
+    pub fn text(value: &str) -> Self {
+        Self {
+            data_type: data::UTF8,
+            locale: 0,
+            value: BytesMut::from(value.as_bytes()),
+        }
+    }
+}
+
+impl IO for data {
+    fn len(&self) -> usize {
+        8 + self.value.len()
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        Self {
+            data_type: 0x00FFFFFF & r.get_u32(),
+            locale: r.get_u32(),
+            value: r.split_to(r.len()),
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put_u32(0x00FFFFFF & self.data_type);
+        w.put_u32(self.locale);
+        w.put(self.value.chunk());
+
+        w
+    }
+}
+
+/// A chapter marker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub start: Duration,
+    pub title: String,
+}
+
+/// Nero chapter list, as read by most non-Apple players.
+#[allow(non_camel_case_types)]
+#[derive(PartialEq)]
+pub struct chpl {
+    base: FullBox,
+
+    /// up to 255 chapters, with titles of up to 255 bytes
+    pub chapters: Vec<Chapter>,
+}
+
+impl chpl {
+    pub const BOX_TYPE: u32 = types::chpl;
+
+    pub fn new(chapters: &[Chapter]) -> Self {
+        Self {
+            base: FullBox::new(1, 0),
+            chapters: chapters.to_vec(),
+        }
+    }
+}
+
+impl Default for chpl {
+    //! extends FullBox(‘chpl’, version, 0) {
+    //!     if (version==1) {
+    //!         unsigned int(32) reserved = 0;
+    //!     }
+    //!     unsigned int(8) chapter_count;
+    //!     for (i=1; i <= chapter_count; i++) {
+    //!         unsigned int(64) start_time; // in 100 nanoseconds
+    //!         unsigned int(8) title_length;
+    //!         utf8 title[title_length];
+    //!     }
+    //! }
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Debug for chpl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("\t\t\tchapter_count: {:?}", self.chapters.len()))?;
+        for it in &self.chapters {
+            f.write_fmt(format_args!("\n\t\t\t\t{:?}: {:?}", it.start, it.title))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `title` cut to at most 255 bytes on a character boundary.
+fn chpl_title(title: &str) -> &str {
+    let mut n = title.len().min(u8::MAX as usize);
+    while !title.is_char_boundary(n) {
+        n -= 1;
+    }
+
+    &title[..n]
+}
+
+impl IO for chpl {
+    fn len(&self) -> usize {
+        let mut v = self.base.len() + 1;
+
+        if 1 == self.base.version {
+            v += 4;
+        }
+        for it in self.chapters.iter().take(u8::MAX as usize) {
+            v += 9 + chpl_title(&it.title).len();
+        }
+
+        v
+    }
+
+    fn parse(r: &mut BytesMut) -> Self {
+        let base = FullBox::parse(r);
+
+        if 1 == base.version {
+            let _ = r.get_u32();
+        }
+
+        let chapter_count = r.get_u8();
+        let mut chapters = vec![];
+        for _ in 0..chapter_count {
+            let start = r.get_u64();
+            let title_length = r.get_u8() as usize;
+            let title = String::from_utf8_lossy(&r.split_to(title_length)).into_owned();
+
+            chapters.push(Chapter {
+                start: Duration::from_nanos(start.saturating_mul(100)),
+                title,
+            });
+        }
+
+        Self {
+            base,
+            chapters,
+        }
+    }
+
+    fn as_bytes(&mut self) -> BytesMut {
+        let mut w = BytesMut::new();
+
+        w.put(self.base.as_bytes());
+
+        if 1 == self.base.version {
+            w.put_u32(0);
+        }
+        w.put_u8(self.chapters.len().min(u8::MAX as usize) as u8);
+        for it in self.chapters.iter().take(u8::MAX as usize) {
+            let title = chpl_title(&it.title);
+
+            w.put_u64((it.start.as_nanos() / 100) as u64);
+            w.put_u8(title.len() as u8);
+            w.put_slice(title.as_bytes());
+        }
+
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{IO, Object};
+    use crate::types::types;
+    use crate::udta::{Image, meta, MetadataValue, udta};
+
+    #[test]
+    fn chk_udta() {
+        let mut b = udta::default();
+
+        b.meta = Some({
+            let mut v = meta::mdir();
+            let ilst = v.ilst.as_mut().unwrap();
+
+            ilst.set_text(types::_nam, "Title");
+            ilst.set_text(types::_ART, "Artist");
+            ilst.set_text(types::_day, "2024");
+            ilst.set(types::trkn, MetadataValue::TrackNumber {
+                number: 3,
+                total: 12,
+            });
+            ilst.set(types::covr, MetadataValue::Images(vec![
+                Image::Jpeg(BytesMut::from(&[0xFF, 0xD8, 0xFF, 0xD9][..])),
+            ]));
+            ilst.set_freeform("com.apple.iTunes", "iTunSMPB", "00000000");
+            ilst.set_freeform("com.apple.iTunes", "ENCODER", "x");
+            ilst.set_text(types::_nam, "Another Title");
+
+            v
+        });
+        b.ext = BytesMut::from(&[0, 0, 0, 12, 0x63, 0x70, 0x72, 0x74, 0, 0, 0, 0, 0, 0, 0, 0][..]);
+
+        let mut obj = Object::parse(&mut Object::new(udta::BOX_TYPE, b.as_bytes()).as_bytes());
+
+        assert_eq!(udta::BOX_TYPE, obj.box_type);
+        assert_eq!(b.len(), obj.payload.len());
+        assert_eq!(b, udta::parse(&mut obj.payload));
+
+        let ilst = b.meta.as_ref().unwrap().ilst.as_ref().unwrap();
+        assert_eq!(7, ilst.items.len());
+        assert_eq!(Some("Another Title"), ilst.text(types::_nam));
+        assert_eq!(Some((3, 12)), ilst.track_number());
+        assert_eq!(1, ilst.images().len());
+        assert_eq!(&b"x"[..], &ilst.freeform("com.apple.iTunes", "ENCODER").unwrap()[0].value[..]);
+    }
+}
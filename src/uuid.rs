@@ -0,0 +1,130 @@
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+
+use bytes::BytesMut;
+
+use crate::Object;
+use crate::types::types;
+
+/// A `uuid` box parsed by a [`Registry`] handler.
+pub trait UuidBox: Any + Debug {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Debug> UuidBox for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl dyn UuidBox {
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+type Handler = Box<dyn Fn(&mut BytesMut) -> Box<dyn UuidBox>>;
+
+/// Parsers of `uuid` boxes, keyed by their extended type.
+#[derive(Default)]
+pub struct Registry {
+    handlers: Vec<([u8; 16], Handler)>,
+}
+
+impl Registry {
+    /// Registers `handler` to parse the payload of `uuid` boxes with
+    /// `usertype`, replacing any previous one.
+    pub fn register<F, T>(&mut self, usertype: [u8; 16], handler: F)
+        where F: Fn(&mut BytesMut) -> T + 'static, T: UuidBox {
+        let handler: Handler = Box::new(move |r| Box::new(handler(r)));
+
+        match self.handlers.iter_mut().find(|it| usertype == it.0) {
+            Some(it) => { it.1 = handler }
+            None => { self.handlers.push((usertype, handler)) }
+        }
+    }
+
+    pub fn is_registered(&self, usertype: &[u8; 16]) -> bool {
+        self.handlers.iter().any(|it| usertype == &it.0)
+    }
+
+    /// Parses `obj` with the handler registered for its extended type.
+    ///
+    /// Returns `None`, leaving `obj` untouched, when it is not a `uuid` box
+    /// or no handler is registered for it.
+    pub fn parse(&self, obj: &mut Object) -> Option<Box<dyn UuidBox>> {
+        if types::uuid != obj.box_type {
+            return None;
+        }
+        let usertype = obj.usertype?;
+        let (_, handler) = self.handlers.iter().find(|it| usertype == it.0)?;
+
+        Some(handler(&mut obj.payload))
+    }
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (usertype, _)) in self.handlers.iter().enumerate() {
+            if 0 < i {
+                f.write_fmt(format_args!("\n"))?;
+            }
+            f.write_fmt(format_args!("\t{:}", format_usertype(usertype)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `usertype` in the usual 8-4-4-4-12 hexadecimal form.
+pub fn format_usertype(usertype: &[u8; 16]) -> String {
+    let v = usertype.iter().map(|it| format!("{:02x}", it)).collect::<String>();
+
+    format!("{}-{}-{}-{}-{}", &v[..8], &v[8..12], &v[12..16], &v[16..20], &v[20..])
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Buf, BufMut, BytesMut};
+
+    use crate::{IO, Object};
+    use crate::uuid::{format_usertype, Registry};
+
+    #[derive(Debug, PartialEq)]
+    struct Sample {
+        value: u32,
+    }
+
+    const USERTYPE: [u8; 16] = [
+        0x6d, 0x1d, 0x9b, 0x05, 0x42, 0xd5, 0x44, 0xe6,
+        0x80, 0xe2, 0x14, 0x1d, 0xaf, 0xf7, 0x57, 0xb2,
+    ];
+
+    #[test]
+    fn chk_uuid() {
+        let mut payload = BytesMut::new();
+        payload.put_u32(42);
+
+        let mut b = Object::uuid(USERTYPE, payload);
+        let mut w = b.as_bytes();
+        assert_eq!(b.len(), w.len());
+        assert_eq!(28, w.len());
+
+        let mut obj = Object::parse(&mut w);
+        assert_eq!(Some(USERTYPE), obj.usertype);
+        assert_eq!(4, obj.payload.len());
+
+        let mut registry = Registry::default();
+        assert!(registry.parse(&mut obj).is_none());
+
+        registry.register(USERTYPE, |r| Sample {
+            value: r.get_u32(),
+        });
+        assert!(registry.is_registered(&USERTYPE));
+        assert_eq!(
+            Some(&Sample { value: 42 }),
+            registry.parse(&mut obj).as_deref().and_then(|it| it.downcast_ref::<Sample>())
+        );
+        assert_eq!("6d1d9b05-42d5-44e6-80e2-141daff757b2", format_usertype(&USERTYPE));
+    }
+}